@@ -0,0 +1,96 @@
+use super::sort_utils::run_network;
+use crate::models::SortBar;
+use crate::sorting::{Cancelled, Category, Complexity, NetworkLayer, Sink, SortAlgorithm};
+
+pub struct BitonicSort;
+
+impl SortAlgorithm for BitonicSort {
+    fn name(&self) -> &'static str {
+        "Bitonic Sort"
+    }
+
+    fn category(&self) -> Category {
+        Category::Specialized
+    }
+
+    fn is_stable(&self) -> bool {
+        false
+    }
+
+    fn complexity(&self) -> Complexity {
+        Complexity {
+            best: "O(n log² n)",
+            average: "O(n log² n)",
+            worst: "O(n log² n)",
+            space: "O(1)",
+        }
+    }
+
+    fn run(&self, bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+        bitonic_sort(bars, sink)
+    }
+
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "for k = 2, 4, 8, ... up to n rounded up to a power of two:",
+            "    one layer: in each block of k, compare the first half",
+            "        with the second half mirrored",
+            "    for j = k/4, k/8, ..., 1:",
+            "        one layer: compare a[i] with a[i + j] where i mod 2j < j",
+            "every comparator puts the smaller value first;",
+            "ones reaching past the end of the array are left out",
+        ]
+    }
+
+    fn network(&self, n: usize) -> Option<Vec<NetworkLayer>> {
+        Some(bitonic_network(n))
+    }
+}
+
+// Lines of the listing in `BitonicSort::pseudocode`
+const LINE_MIRROR: usize = 2;
+const LINE_HALVES: usize = 5;
+
+/// Batcher's bitonic sort, as a sorting network of `bitonic_network`.
+pub fn bitonic_sort(bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+    run_network(bars, &bitonic_network(bars.len()), sink)
+}
+
+/// Batcher's bitonic sorting network on `n` elements, in the form with
+/// every comparator facing the same way: each merge starts by comparing
+/// the first half of a block with its second half mirrored, which turns
+/// the two sorted halves into a bitonic sequence without flipping one.
+///
+/// Built for `n` rounded up to a power of two, as if the array were padded
+/// with values greater than any in it. Comparators reaching into the
+/// padding would never swap, so they are left out.
+pub fn bitonic_network(n: usize) -> Vec<NetworkLayer> {
+    let size = n.next_power_of_two();
+    let mut network = Vec::new();
+    let mut k = 2;
+    while k <= size {
+        let comparators = (0..n)
+            .filter(|i| i % k < k / 2)
+            .map(|i| (i, i - i % k + k - 1 - i % k))
+            .filter(|&(_, j)| j < n)
+            .collect();
+        network.push(NetworkLayer {
+            comparators,
+            line: LINE_MIRROR,
+        });
+        let mut j = k / 4;
+        while j >= 1 {
+            let comparators = (0..n)
+                .filter(|i| i % (2 * j) < j && i + j < n)
+                .map(|i| (i, i + j))
+                .collect();
+            network.push(NetworkLayer {
+                comparators,
+                line: LINE_HALVES,
+            });
+            j /= 2;
+        }
+        k *= 2;
+    }
+    network
+}
@@ -0,0 +1,591 @@
+use crate::models::SortBar;
+use crate::sorting::{Cancelled, Category, Complexity, Operation, Sink, SortAlgorithm};
+use eframe::egui::Color32;
+
+pub struct BlockMergeSort;
+
+impl SortAlgorithm for BlockMergeSort {
+    fn name(&self) -> &'static str {
+        "Block Merge Sort"
+    }
+
+    fn category(&self) -> Category {
+        Category::Research
+    }
+
+    fn is_stable(&self) -> bool {
+        true
+    }
+
+    fn complexity(&self) -> Complexity {
+        Complexity {
+            best: "O(n)",
+            average: "O(n log n)",
+            worst: "O(n log n)",
+            space: "O(1)",
+        }
+    }
+
+    fn run(&self, bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+        block_merge_sort(bars, sink)
+    }
+
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "pull up to 2·sqrt(n) distinct keys to the front, by rotation",
+            "if there are too few:",
+            "    merge sort by rotations alone; return",
+            "split the keys into tags and a buffer of sqrt(n)",
+            "insertion sort runs of 16",
+            "for width = 16, 32, ... while width < n:",
+            "    for each pair of runs A, B not already in order:",
+            "        if A fits in the buffer:",
+            "            swap A into the buffer and merge it back with B",
+            "        else:",
+            "            tag A's blocks, then B's, with keys in order",
+            "            selection sort the blocks by first value, then tag",
+            "            for each block, in order:",
+            "                if it came from the other run than the last:",
+            "                    merge what is left of the last with it,",
+            "                    through the buffer",
+            "            merge B's last partial block in, through the buffer",
+            "insertion sort the keys",
+            "merge the keys back in, by rotation",
+        ]
+    }
+}
+
+/// Runs this long are insertion sorted before any merging.
+const RUN: usize = 16;
+
+// Lines of the listing in `BlockMergeSort::pseudocode`
+const LINE_COLLECT_KEYS: usize = 1;
+const LINE_TOO_FEW: usize = 2;
+const LINE_ROTATIONS_ONLY: usize = 3;
+const LINE_SPLIT_KEYS: usize = 4;
+const LINE_RUNS: usize = 5;
+const LINE_WIDTH: usize = 6;
+const LINE_PAIR: usize = 7;
+const LINE_FITS: usize = 8;
+const LINE_BUFFER_MERGE: usize = 9;
+const LINE_TAG: usize = 11;
+const LINE_SELECT: usize = 12;
+const LINE_EACH_BLOCK: usize = 13;
+const LINE_OTHER_RUN: usize = 14;
+const LINE_MERGE_BLOCK: usize = 15;
+const LINE_PARTIAL_BLOCK: usize = 17;
+const LINE_SORT_KEYS: usize = 18;
+const LINE_MERGE_KEYS: usize = 19;
+
+/// Block merge sort in the style of GrailSort: stable, in place and
+/// O(n log n).
+///
+/// The first occurrences of about 2·sqrt(n) distinct values are pulled to
+/// the front as keys. Some of them tag the blocks of each merge, so blocks
+/// can be reordered and still be told apart; the rest are an internal
+/// buffer the merges swap elements through instead of copying them out.
+/// With too few distinct values for that it merges by rotations alone,
+/// in O(n log² n).
+///
+/// The keys are announced with `Operation::Keys`, each block merge with
+/// `Operation::Blocks` and each rotation with `Operation::Rotate`.
+pub fn block_merge_sort(bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+    let n = bars.len();
+    if n <= RUN {
+        sink.send(Operation::Line(LINE_RUNS))?;
+        insertion_sort(bars, 0, n, sink)?;
+        return finish(n, sink);
+    }
+
+    // A power of two no longer than sqrt(n), so blocks divide every merge
+    // width too wide for the buffer
+    let block_len = 1 << (n.ilog2() / 2);
+    let tag_count = n.div_ceil(block_len);
+    sink.send(Operation::Line(LINE_COLLECT_KEYS))?;
+    let keys = collect_keys(bars, tag_count + block_len, sink)?;
+    sink.send(Operation::Line(LINE_TOO_FEW))?;
+    if keys < tag_count + block_len {
+        sink.send(Operation::Line(LINE_ROTATIONS_ONLY))?;
+        rotation_merge_sort(bars, sink)?;
+        return finish(n, sink);
+    }
+
+    sink.send(Operation::Line(LINE_SPLIT_KEYS))?;
+    sink.send(Operation::Keys(0, tag_count, keys))?;
+    sink.send(Operation::Line(LINE_RUNS))?;
+    for lo in (keys..n).step_by(RUN) {
+        insertion_sort(bars, lo, (lo + RUN).min(n), sink)?;
+    }
+    let mut width = RUN;
+    sink.send(Operation::Line(LINE_WIDTH))?;
+    while width < n - keys {
+        for lo in (keys..n).step_by(2 * width) {
+            let mid = lo + width;
+            if mid >= n {
+                break;
+            }
+            sink.send(Operation::Line(LINE_PAIR))?;
+            merge(
+                bars,
+                lo,
+                mid,
+                (mid + width).min(n),
+                block_len,
+                tag_count,
+                sink,
+            )?;
+        }
+        width *= 2;
+        sink.send(Operation::Line(LINE_WIDTH))?;
+    }
+
+    sink.send(Operation::EnterRange(0, n))?;
+    sink.send(Operation::Note(
+        0,
+        n,
+        "Putting the keys back: sorting them, then merging them in by rotation",
+    ))?;
+    sink.send(Operation::Line(LINE_SORT_KEYS))?;
+    insertion_sort(bars, 0, keys, sink)?;
+    sink.send(Operation::Keys(0, 0, 0))?;
+    sink.send(Operation::Line(LINE_MERGE_KEYS))?;
+    merge_in_place(bars, 0, keys, n, sink)?;
+    sink.send(Operation::ExitRange(0, n))?;
+    finish(n, sink)
+}
+
+/// Final sweep to confirm completion.
+fn finish(n: usize, sink: &Sink) -> Result<(), Cancelled> {
+    for i in 0..n {
+        sink.send(Operation::SetColor(i, Color32::LIGHT_GREEN))?;
+    }
+    for i in 0..n {
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
+    }
+    Ok(())
+}
+
+/// Gather the first occurrences of up to `wanted` distinct values at the
+/// front, in sorted order, leaving the other elements in their order.
+/// Returns how many were found.
+fn collect_keys(bars: &mut [SortBar], wanted: usize, sink: &Sink) -> Result<usize, Cancelled> {
+    let n = bars.len();
+    sink.send(Operation::EnterRange(0, n))?;
+    sink.send(Operation::Note(
+        0,
+        n,
+        "Collecting distinct values to use as keys",
+    ))?;
+    // The keys found so far stay together, sorted, and are rolled along
+    // to each new one
+    let (mut first, mut found) = (0, 1);
+    for i in 1..n {
+        if found == wanted {
+            break;
+        }
+        let pos = lower_bound(bars, first, first + found, i, sink)?;
+        if pos < first + found {
+            sink.send(Operation::Compare(i, pos))?;
+            if bars[i].value == bars[pos].value {
+                continue;
+            }
+        }
+        rotate(bars, first, first + found, i, sink)?;
+        let pos = pos + (i - found - first);
+        first = i - found;
+        rotate(bars, pos, i, i + 1, sink)?;
+        found += 1;
+    }
+    rotate(bars, 0, first, first + found, sink)?;
+    sink.send(Operation::ExitRange(0, n))?;
+    Ok(found)
+}
+
+/// Merge sort with no buffer at all: runs are insertion sorted, then
+/// merged with `merge_in_place`.
+fn rotation_merge_sort(bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+    let n = bars.len();
+    sink.send(Operation::EnterRange(0, n))?;
+    sink.send(Operation::Note(
+        0,
+        n,
+        "Too few distinct values for a buffer: merging by rotation",
+    ))?;
+    for lo in (0..n).step_by(RUN) {
+        insertion_sort(bars, lo, (lo + RUN).min(n), sink)?;
+    }
+    let mut width = RUN;
+    while width < n {
+        for lo in (0..n).step_by(2 * width) {
+            let mid = lo + width;
+            if mid >= n {
+                break;
+            }
+            merge_in_place(bars, lo, mid, (mid + width).min(n), sink)?;
+        }
+        width *= 2;
+    }
+    sink.send(Operation::ExitRange(0, n))
+}
+
+/// Merge the sorted runs `bars[lo..mid]` and `bars[mid..hi]`, through the
+/// buffer at `buf` when the first fits in it and block by block otherwise.
+fn merge(
+    bars: &mut [SortBar],
+    lo: usize,
+    mid: usize,
+    hi: usize,
+    block_len: usize,
+    buf: usize,
+    sink: &Sink,
+) -> Result<(), Cancelled> {
+    sink.send(Operation::Compare(mid - 1, mid))?;
+    if bars[mid - 1].value <= bars[mid].value {
+        return Ok(());
+    }
+    sink.send(Operation::EnterRange(lo, hi))?;
+    sink.send(Operation::Line(LINE_FITS))?;
+    if mid - lo <= block_len {
+        sink.send(Operation::Line(LINE_BUFFER_MERGE))?;
+        merge_forward(bars, lo, mid, hi, buf, true, sink)?;
+    } else {
+        block_merge(bars, lo, mid, hi, block_len, buf, sink)?;
+    }
+    sink.send(Operation::ExitRange(lo, hi))
+}
+
+/// Merge `bars[lo..mid]`, a whole number of blocks, with `bars[mid..hi]`.
+///
+/// Block `i` of the range is tagged with the key at `i`. Sorting the blocks
+/// by their first elements, tags breaking ties, leaves every element at
+/// most a block away from its place, so merging what is left of each block
+/// with the next block from the other run finishes the job.
+fn block_merge(
+    bars: &mut [SortBar],
+    lo: usize,
+    mid: usize,
+    hi: usize,
+    block_len: usize,
+    buf: usize,
+    sink: &Sink,
+) -> Result<(), Cancelled> {
+    let from_left = (mid - lo) / block_len;
+    let count = (hi - lo) / block_len;
+    let block = |i: usize| lo + i * block_len;
+
+    sink.send(Operation::Line(LINE_TAG))?;
+    insertion_sort(bars, 0, count, sink)?;
+    sink.send(Operation::Blocks(lo, mid, hi, block_len))?;
+
+    if count > from_left {
+        // Blocks from the left run have tags below this one's
+        let mut mid_key = from_left;
+
+        sink.send(Operation::Line(LINE_SELECT))?;
+        for i in 0..count {
+            let mut min = i;
+            for j in i + 1..count {
+                if block_before(bars, block(j), block(min), j, min, sink)? {
+                    min = j;
+                }
+            }
+            if min != i {
+                swap_blocks(bars, block(i), block(min), block_len, sink)?;
+                swap(bars, i, min, sink)?;
+                if mid_key == i {
+                    mid_key = min;
+                } else if mid_key == min {
+                    mid_key = i;
+                }
+            }
+        }
+
+        sink.send(Operation::Line(LINE_EACH_BLOCK))?;
+        let mut rest = block(0);
+        let mut rest_left = from_left_run(bars, 0, mid_key, sink)?;
+        for i in 1..count {
+            sink.send(Operation::Line(LINE_OTHER_RUN))?;
+            let left = from_left_run(bars, i, mid_key, sink)?;
+            if left == rest_left {
+                // What is left of the last block is in place
+                rest = block(i);
+            } else {
+                sink.send(Operation::Line(LINE_MERGE_BLOCK))?;
+                let (start, kept) =
+                    merge_forward(bars, rest, block(i), block(i + 1), buf, rest_left, sink)?;
+                rest = start;
+                if !kept {
+                    rest_left = left;
+                }
+            }
+            sink.send(Operation::Line(LINE_EACH_BLOCK))?;
+        }
+    }
+
+    if block(count) < hi {
+        sink.send(Operation::Line(LINE_PARTIAL_BLOCK))?;
+        merge_backward(bars, lo, block(count), hi, buf, sink)?;
+    }
+    Ok(())
+}
+
+/// Whether the block at `a`, tagged by the key at `tag_a`, goes before the
+/// block at `b`: by first element, and by tag between equal ones.
+fn block_before(
+    bars: &[SortBar],
+    a: usize,
+    b: usize,
+    tag_a: usize,
+    tag_b: usize,
+    sink: &Sink,
+) -> Result<bool, Cancelled> {
+    sink.send(Operation::Compare(a, b))?;
+    if bars[a].value != bars[b].value {
+        return Ok(bars[a].value < bars[b].value);
+    }
+    sink.send(Operation::Compare(tag_a, tag_b))?;
+    Ok(bars[tag_a].value < bars[tag_b].value)
+}
+
+/// Whether block `i` came from the left run, going by its tag.
+fn from_left_run(
+    bars: &[SortBar],
+    i: usize,
+    mid_key: usize,
+    sink: &Sink,
+) -> Result<bool, Cancelled> {
+    if i == mid_key {
+        return Ok(false);
+    }
+    sink.send(Operation::Compare(i, mid_key))?;
+    Ok(bars[i].value < bars[mid_key].value)
+}
+
+/// Merge `bars[lo..mid]` with `bars[mid..hi]` by swapping the first into
+/// the buffer at `buf` and back out as the merge reaches its elements, so
+/// the buffer's own elements only move around. Ties go to the first run
+/// when `left_first`.
+///
+/// Stops once either run is used up, and returns where what is left of the
+/// other starts and whether that is the first run.
+fn merge_forward(
+    bars: &mut [SortBar],
+    lo: usize,
+    mid: usize,
+    hi: usize,
+    buf: usize,
+    left_first: bool,
+    sink: &Sink,
+) -> Result<(usize, bool), Cancelled> {
+    let len = mid - lo;
+    swap_blocks(bars, lo, buf, len, sink)?;
+    let (mut x, mut y, mut out) = (buf, mid, lo);
+    while x < buf + len && y < hi {
+        sink.send(Operation::Compare(y, x))?;
+        let take_left = if left_first {
+            bars[x].value <= bars[y].value
+        } else {
+            bars[x].value < bars[y].value
+        };
+        if take_left {
+            swap(bars, out, x, sink)?;
+            x += 1;
+        } else {
+            swap(bars, out, y, sink)?;
+            y += 1;
+        }
+        out += 1;
+    }
+    if x < buf + len {
+        // The second run is used up, so the rest of the first goes last
+        swap_blocks(bars, x, out, buf + len - x, sink)?;
+        Ok((out, true))
+    } else {
+        Ok((y, false))
+    }
+}
+
+/// Merge `bars[lo..mid]` with `bars[mid..hi]`, no longer than the buffer at
+/// `buf`, like `merge_forward` but swapping the second run out and filling
+/// in from the right.
+fn merge_backward(
+    bars: &mut [SortBar],
+    lo: usize,
+    mid: usize,
+    hi: usize,
+    buf: usize,
+    sink: &Sink,
+) -> Result<(), Cancelled> {
+    let len = hi - mid;
+    swap_blocks(bars, mid, buf, len, sink)?;
+    // One past the next element of each run, and of the output
+    let (mut x, mut y, mut out) = (buf + len, mid, hi);
+    while x > buf && y > lo {
+        sink.send(Operation::Compare(y - 1, x - 1))?;
+        if bars[y - 1].value > bars[x - 1].value {
+            swap(bars, out - 1, y - 1, sink)?;
+            y -= 1;
+        } else {
+            swap(bars, out - 1, x - 1, sink)?;
+            x -= 1;
+        }
+        out -= 1;
+    }
+    // The first run is used up, so the rest of the second goes first
+    swap_blocks(bars, buf, lo, x - buf, sink)
+}
+
+/// Merge the sorted runs `bars[lo..mid]` and `bars[mid..hi]` with no
+/// buffer: split the longer run in half, rotate the half of the other that
+/// belongs before its middle across, and merge either side of it.
+fn merge_in_place(
+    bars: &mut [SortBar],
+    lo: usize,
+    mid: usize,
+    hi: usize,
+    sink: &Sink,
+) -> Result<(), Cancelled> {
+    if lo == mid || mid == hi {
+        return Ok(());
+    }
+    sink.send(Operation::Compare(mid - 1, mid))?;
+    if bars[mid - 1].value <= bars[mid].value {
+        return Ok(());
+    }
+    if hi - lo == 2 {
+        return swap(bars, lo, mid, sink);
+    }
+    sink.send(Operation::EnterRange(lo, hi))?;
+    let (cut_left, cut_right) = if mid - lo >= hi - mid {
+        let cut = lo + (mid - lo) / 2;
+        (cut, lower_bound(bars, mid, hi, cut, sink)?)
+    } else {
+        let cut = mid + (hi - mid) / 2;
+        (upper_bound(bars, lo, mid, cut, sink)?, cut)
+    };
+    rotate(bars, cut_left, mid, cut_right, sink)?;
+    let new_mid = cut_left + (cut_right - mid);
+    merge_in_place(bars, lo, cut_left, new_mid, sink)?;
+    merge_in_place(bars, new_mid, cut_right, hi, sink)?;
+    sink.send(Operation::ExitRange(lo, hi))
+}
+
+/// The first position in `bars[lo..hi]` whose element is not less than
+/// `bars[key]`.
+fn lower_bound(
+    bars: &[SortBar],
+    lo: usize,
+    hi: usize,
+    key: usize,
+    sink: &Sink,
+) -> Result<usize, Cancelled> {
+    search(bars, lo, hi, key, false, sink)
+}
+
+/// The first position in `bars[lo..hi]` whose element is greater than
+/// `bars[key]`.
+fn upper_bound(
+    bars: &[SortBar],
+    lo: usize,
+    hi: usize,
+    key: usize,
+    sink: &Sink,
+) -> Result<usize, Cancelled> {
+    search(bars, lo, hi, key, true, sink)
+}
+
+fn search(
+    bars: &[SortBar],
+    mut lo: usize,
+    mut hi: usize,
+    key: usize,
+    past_equal: bool,
+    sink: &Sink,
+) -> Result<usize, Cancelled> {
+    while lo < hi {
+        let m = lo + (hi - lo) / 2;
+        sink.send(Operation::Compare(m, key))?;
+        let before = if past_equal {
+            bars[m].value <= bars[key].value
+        } else {
+            bars[m].value < bars[key].value
+        };
+        if before {
+            lo = m + 1;
+        } else {
+            hi = m;
+        }
+    }
+    Ok(lo)
+}
+
+/// Rotate `bars[lo..hi]` so that `bars[mid..hi]` comes first, by three
+/// reversals.
+fn rotate(
+    bars: &mut [SortBar],
+    lo: usize,
+    mid: usize,
+    hi: usize,
+    sink: &Sink,
+) -> Result<(), Cancelled> {
+    if lo == mid || mid == hi {
+        return Ok(());
+    }
+    sink.send(Operation::Rotate(lo, mid, hi))?;
+    reverse(bars, lo, mid, sink)?;
+    reverse(bars, mid, hi, sink)?;
+    reverse(bars, lo, hi, sink)
+}
+
+fn reverse(bars: &mut [SortBar], lo: usize, hi: usize, sink: &Sink) -> Result<(), Cancelled> {
+    let (mut i, mut j) = (lo, hi);
+    while i + 1 < j {
+        j -= 1;
+        swap(bars, i, j, sink)?;
+        i += 1;
+    }
+    Ok(())
+}
+
+/// Swap `bars[a..a + len]` with `bars[b..b + len]`, element by element.
+fn swap_blocks(
+    bars: &mut [SortBar],
+    a: usize,
+    b: usize,
+    len: usize,
+    sink: &Sink,
+) -> Result<(), Cancelled> {
+    for k in 0..len {
+        swap(bars, a + k, b + k, sink)?;
+    }
+    Ok(())
+}
+
+fn swap(bars: &mut [SortBar], i: usize, j: usize, sink: &Sink) -> Result<(), Cancelled> {
+    if i != j {
+        sink.send(Operation::Swap(i, j))?;
+        bars.swap(i, j);
+    }
+    Ok(())
+}
+
+/// Insertion sort `bars[lo..hi]` by swaps, which keeps it stable.
+fn insertion_sort(
+    bars: &mut [SortBar],
+    lo: usize,
+    hi: usize,
+    sink: &Sink,
+) -> Result<(), Cancelled> {
+    for i in lo + 1..hi {
+        let mut j = i;
+        while j > lo {
+            sink.send(Operation::Compare(j, j - 1))?;
+            if bars[j].value >= bars[j - 1].value {
+                break;
+            }
+            swap(bars, j, j - 1, sink)?;
+            j -= 1;
+        }
+    }
+    Ok(())
+}
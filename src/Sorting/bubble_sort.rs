@@ -1,23 +1,63 @@
 use crate::models::SortBar;
+use crate::sorting::{Cancelled, Category, Complexity, Sink, SortAlgorithm};
 use eframe::egui::Color32;
-use std::sync::mpsc;
+
+pub struct BubbleSort;
+
+impl SortAlgorithm for BubbleSort {
+    fn name(&self) -> &'static str {
+        "Bubble Sort"
+    }
+
+    fn category(&self) -> Category {
+        Category::Educational
+    }
+
+    fn is_stable(&self) -> bool {
+        true
+    }
+
+    fn complexity(&self) -> Complexity {
+        Complexity {
+            best: "O(n)",
+            average: "O(n²)",
+            worst: "O(n²)",
+            space: "O(1)",
+        }
+    }
+
+    fn run(&self, bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+        bubble_sort(bars, sink)
+    }
+
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "for i in 0 .. n:",
+            "    for j in 0 .. n - i - 1:",
+            "        if a[j] > a[j + 1]:",
+            "            swap a[j], a[j + 1]",
+        ]
+    }
+}
 
 /// Visual Bubble Sort implementation
-pub fn bubble_sort(bars: &mut Vec<SortBar>, tx: &mpsc::Sender<crate::sorting::Operation>) {
+pub fn bubble_sort(bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
     let n = bars.len();
     for i in 0..n {
+        sink.send(crate::sorting::Operation::Line(1))?;
         for j in 0..n - i - 1 {
-            let _ = tx.send(crate::sorting::Operation::Compare(j, j + 1));
-            std::thread::sleep(std::time::Duration::from_millis(10));
+            sink.send(crate::sorting::Operation::Line(3))?;
+            sink.send(crate::sorting::Operation::Compare(j, j + 1))?;
 
             if bars[j].value > bars[j + 1].value {
-                let _ = tx.send(crate::sorting::Operation::Swap(j, j + 1));
+                sink.send(crate::sorting::Operation::Line(4))?;
+                sink.send(crate::sorting::Operation::Swap(j, j + 1))?;
                 bars.swap(j, j + 1);
-                std::thread::sleep(std::time::Duration::from_millis(10));
             }
 
-            let _ = tx.send(crate::sorting::Operation::SetColor(j, Color32::WHITE));
-            let _ = tx.send(crate::sorting::Operation::SetColor(j + 1, Color32::WHITE));
+            sink.send(crate::sorting::Operation::SetColor(j, Color32::WHITE))?;
+            sink.send(crate::sorting::Operation::SetColor(j + 1, Color32::WHITE))?;
         }
     }
+    Ok(())
 }
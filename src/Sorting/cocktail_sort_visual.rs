@@ -1,16 +1,56 @@
 use crate::models::SortBar;
-use crate::sorting::Operation;
+use crate::sorting::{Cancelled, Category, Complexity, Operation, Sink, SortAlgorithm};
 use eframe::egui::Color32;
-use std::sync::mpsc;
-use std::thread;
-use std::time::Duration;
 
-pub fn cocktail_sort_visual(bars: &mut Vec<SortBar>, tx: &mpsc::Sender<Operation>) {
+pub struct CocktailSort;
+
+impl SortAlgorithm for CocktailSort {
+    fn name(&self) -> &'static str {
+        "Cocktail Sort"
+    }
+
+    fn category(&self) -> Category {
+        Category::Educational
+    }
+
+    fn is_stable(&self) -> bool {
+        true
+    }
+
+    fn complexity(&self) -> Complexity {
+        Complexity {
+            best: "O(n)",
+            average: "O(n²)",
+            worst: "O(n²)",
+            space: "O(1)",
+        }
+    }
+
+    fn run(&self, bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+        cocktail_sort_visual(bars, sink)
+    }
+
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "lo = 0; hi = n - 1",
+            "loop:",
+            "    for i in lo .. hi:",
+            "        if a[i] > a[i + 1]: swap a[i], a[i + 1]",
+            "    hi = hi - 1; stop if nothing was swapped",
+            "    for i in hi down to lo + 1:",
+            "        if a[i - 1] > a[i]: swap a[i - 1], a[i]",
+            "    lo = lo + 1; stop if nothing was swapped",
+        ]
+    }
+}
+
+pub fn cocktail_sort_visual(bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
     let n = bars.len();
     if n <= 1 {
-        return;
+        return Ok(());
     }
 
+    sink.send(Operation::Line(1))?;
     let mut left = 0;
     let mut right = n - 1;
     let mut swapped;
@@ -19,37 +59,37 @@ pub fn cocktail_sort_visual(bars: &mut Vec<SortBar>, tx: &mpsc::Sender<Operation
         swapped = false;
 
         // Forward pass (left to right) - bubble largest to the right
+        sink.send(Operation::Line(3))?;
         // Highlight the forward direction
         for i in left..right {
-            let _ = tx.send(Operation::SetColor(i, Color32::LIGHT_BLUE));
-            let _ = tx.send(Operation::SetColor(i + 1, Color32::LIGHT_BLUE));
+            sink.send(Operation::SetColor(i, Color32::LIGHT_BLUE))?;
+            sink.send(Operation::SetColor(i + 1, Color32::LIGHT_BLUE))?;
         }
-        thread::sleep(Duration::from_millis(100));
 
         for i in left..right {
             // Highlight elements being compared
-            let _ = tx.send(Operation::SetColor(i, Color32::YELLOW));
-            let _ = tx.send(Operation::SetColor(i + 1, Color32::YELLOW));
-            let _ = tx.send(Operation::Compare(i, i + 1));
-            thread::sleep(Duration::from_millis(60));
+            sink.send(Operation::SetColor(i, Color32::YELLOW))?;
+            sink.send(Operation::SetColor(i + 1, Color32::YELLOW))?;
+            sink.send(Operation::Line(4))?;
+            sink.send(Operation::Compare(i, i + 1))?;
 
             if bars[i].value > bars[i + 1].value {
                 // Show swap with green color
-                let _ = tx.send(Operation::SetColor(i, Color32::GREEN));
-                let _ = tx.send(Operation::SetColor(i + 1, Color32::GREEN));
-                let _ = tx.send(Operation::Swap(i, i + 1));
+                sink.send(Operation::SetColor(i, Color32::GREEN))?;
+                sink.send(Operation::SetColor(i + 1, Color32::GREEN))?;
+                sink.send(Operation::Swap(i, i + 1))?;
                 bars.swap(i, i + 1);
                 swapped = true;
-                thread::sleep(Duration::from_millis(80));
             }
 
             // Reset colors after comparison
-            let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
-            let _ = tx.send(Operation::SetColor(i + 1, Color32::WHITE));
+            sink.send(Operation::SetColor(i, Color32::WHITE))?;
+            sink.send(Operation::SetColor(i + 1, Color32::WHITE))?;
         }
 
         // Mark the rightmost element as sorted (light green)
-        let _ = tx.send(Operation::SetColor(right, Color32::LIGHT_GREEN));
+        sink.send(Operation::Line(5))?;
+        sink.send(Operation::SetColor(right, Color32::LIGHT_GREEN))?;
         right -= 1;
 
         if !swapped {
@@ -59,58 +99,52 @@ pub fn cocktail_sort_visual(bars: &mut Vec<SortBar>, tx: &mpsc::Sender<Operation
         swapped = false;
 
         // Backward pass (right to left) - bubble smallest to the left
+        sink.send(Operation::Line(6))?;
         // Highlight the backward direction with different color
         for i in (left + 1..=right).rev() {
-            let _ = tx.send(Operation::SetColor(i, Color32::LIGHT_YELLOW));
-            let _ = tx.send(Operation::SetColor(i - 1, Color32::LIGHT_YELLOW));
+            sink.send(Operation::SetColor(i, Color32::LIGHT_YELLOW))?;
+            sink.send(Operation::SetColor(i - 1, Color32::LIGHT_YELLOW))?;
         }
-        thread::sleep(Duration::from_millis(100));
 
         for i in (left + 1..=right).rev() {
             // Highlight elements being compared with orange/red tones for backward pass
-            let _ = tx.send(Operation::SetColor(i, Color32::from_rgb(255, 165, 0))); // Orange
-            let _ = tx.send(Operation::SetColor(i - 1, Color32::from_rgb(255, 165, 0)));
-            let _ = tx.send(Operation::Compare(i - 1, i));
-            thread::sleep(Duration::from_millis(60));
+            sink.send(Operation::SetColor(i, Color32::from_rgb(255, 165, 0)))?; // Orange
+            sink.send(Operation::SetColor(i - 1, Color32::from_rgb(255, 165, 0)))?;
+            sink.send(Operation::Line(7))?;
+            sink.send(Operation::Compare(i - 1, i))?;
 
             if bars[i - 1].value > bars[i].value {
                 // Show swap with red color for backward pass
-                let _ = tx.send(Operation::SetColor(i, Color32::RED));
-                let _ = tx.send(Operation::SetColor(i - 1, Color32::RED));
-                let _ = tx.send(Operation::Swap(i - 1, i));
+                sink.send(Operation::SetColor(i, Color32::RED))?;
+                sink.send(Operation::SetColor(i - 1, Color32::RED))?;
+                sink.send(Operation::Swap(i - 1, i))?;
                 bars.swap(i - 1, i);
                 swapped = true;
-                thread::sleep(Duration::from_millis(80));
             }
 
             // Reset colors after comparison
-            let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
-            let _ = tx.send(Operation::SetColor(i - 1, Color32::WHITE));
+            sink.send(Operation::SetColor(i, Color32::WHITE))?;
+            sink.send(Operation::SetColor(i - 1, Color32::WHITE))?;
         }
 
         // Mark the leftmost element as sorted (light green)
-        let _ = tx.send(Operation::SetColor(left, Color32::LIGHT_GREEN));
+        sink.send(Operation::Line(8))?;
+        sink.send(Operation::SetColor(left, Color32::LIGHT_GREEN))?;
         left += 1;
 
         if !swapped {
             break;
         }
-
-        // Brief pause between passes to show the alternating pattern
-        thread::sleep(Duration::from_millis(150));
     }
 
     // Final sweep: show all elements as sorted
     for i in 0..n {
-        let _ = tx.send(Operation::SetColor(i, Color32::LIGHT_GREEN));
-        thread::sleep(Duration::from_millis(30));
+        sink.send(Operation::SetColor(i, Color32::LIGHT_GREEN))?;
     }
 
-    thread::sleep(Duration::from_millis(200));
-
     // Reset all colors to white
     for i in 0..n {
-        let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
-        thread::sleep(Duration::from_millis(20));
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
     }
+    Ok(())
 }
@@ -1,91 +1,156 @@
 use crate::models::SortBar;
-use crate::sorting::Operation;
+use crate::sorting::{Cancelled, Category, Complexity, Operation, Sink, SortAlgorithm};
 use eframe::egui::Color32;
-use std::sync::mpsc;
-use std::thread;
-use std::time::Duration;
 
-pub fn counting_sort_visual(bars: &mut Vec<SortBar>, tx: &mpsc::Sender<Operation>) {
+/// Counting Sort makes a count for every value up to the largest, as long as
+/// that is at most this or the number of elements. Past that, so that a few
+/// huge values can't use up memory, it counts each value at its rank among
+/// the distinct values instead.
+pub const MAX_COUNTED_VALUE: usize = 1_000_000;
+
+pub struct CountingSort;
+
+impl SortAlgorithm for CountingSort {
+    fn name(&self) -> &'static str {
+        "Counting Sort"
+    }
+
+    fn category(&self) -> Category {
+        Category::Specialized
+    }
+
+    fn is_stable(&self) -> bool {
+        true
+    }
+
+    fn complexity(&self) -> Complexity {
+        Complexity {
+            best: "O(n + k)",
+            average: "O(n + k)",
+            worst: "O(n + k)",
+            space: "O(n + k)",
+        }
+    }
+
+    fn run(&self, bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+        counting_sort_visual(bars, sink)
+    }
+
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "max = largest value in a",
+            "count = zeroes, one per value 0 ..= max",
+            "for x in a: count[x] += 1",
+            "for v in 1 ..= max: count[v] += count[v - 1]",
+            "for i in n - 1 down to 0:",
+            "    count[a[i]] -= 1",
+            "    output[count[a[i]]] = a[i]",
+            "copy output back to a",
+        ]
+    }
+}
+
+pub fn counting_sort_visual(bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
     let n = bars.len();
     if n <= 1 {
-        return;
+        return Ok(());
     }
 
     // Find the maximum value with visual feedback
+    sink.send(Operation::Line(1))?;
     let mut max_val = 0;
-    for i in 0..n {
-        let _ = tx.send(Operation::SetColor(i, Color32::LIGHT_BLUE));
-        thread::sleep(Duration::from_millis(30));
+    for (i, bar) in bars.iter().enumerate() {
+        sink.send(Operation::SetColor(i, Color32::LIGHT_BLUE))?;
+        sink.send(Operation::Read(i))?;
 
-        if bars[i].value > max_val {
-            max_val = bars[i].value;
-            let _ = tx.send(Operation::SetColor(i, Color32::RED));
-            thread::sleep(Duration::from_millis(50));
+        if bar.value > max_val {
+            max_val = bar.value;
+            sink.send(Operation::SetColor(i, Color32::RED))?;
         }
 
-        let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
     }
 
-    // Safety check: prevent memory issues with very large max values
-    const MAX_SAFE_SIZE: usize = 10000; // Reasonable limit for visualization
-    if max_val > MAX_SAFE_SIZE {
-        // Fall back to a different sorting algorithm or skip
-        // For now, we'll just reset colors and return
-        for i in 0..n {
-            let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
-        }
-        return;
+    // Values from 0 to n - 1, as every generated input has, are counted as
+    // they are; larger ones by their rank among the distinct values
+    let ranks = (max_val > n.max(MAX_COUNTED_VALUE)).then(|| {
+        let mut values: Vec<usize> = bars.iter().map(|bar| bar.value).collect();
+        values.sort_unstable();
+        values.dedup();
+        values
+    });
+    let key = |value: usize| match &ranks {
+        Some(values) => values.binary_search(&value).unwrap(),
+        None => value,
+    };
+    if ranks.is_some() {
+        sink.send(Operation::Note(0, n, "values too large, counting ranks"))?;
     }
 
     // Create count array
-    let mut count = vec![0; max_val + 1];
+    sink.send(Operation::Line(2))?;
+    let mut count = vec![0; ranks.as_ref().map_or_else(|| max_val + 1, Vec::len)];
+    let count_aux = sink.aux("count", count.len())?;
 
     // Count occurrences with visual feedback
-    for i in 0..n {
-        let _ = tx.send(Operation::SetColor(i, Color32::YELLOW));
-        thread::sleep(Duration::from_millis(60));
+    for (i, bar) in bars.iter().enumerate() {
+        sink.send(Operation::SetColor(i, Color32::YELLOW))?;
+        sink.send(Operation::Read(i))?;
 
-        count[bars[i].value] += 1;
+        sink.send(Operation::Line(3))?;
+        let k = key(bar.value);
+        count[k] += 1;
+        count_aux.write(sink, k, count[k])?;
 
-        let _ = tx.send(Operation::SetColor(i, Color32::LIGHT_GREEN));
-        thread::sleep(Duration::from_millis(40));
+        sink.send(Operation::SetColor(i, Color32::LIGHT_GREEN))?;
     }
 
     // Convert count array to cumulative count
     for i in 1..count.len() {
+        sink.send(Operation::Line(4))?;
+        count_aux.read(sink, i - 1)?;
         count[i] += count[i - 1];
+        count_aux.write(sink, i, count[i])?;
     }
 
     // Create output array
     let mut output = vec![0; n];
+    let output_aux = sink.aux("output", n)?;
 
     // Build the output array from right to left to maintain stability
     for i in (0..n).rev() {
         let val = bars[i].value;
-        let _ = tx.send(Operation::SetColor(i, Color32::BLUE));
-        thread::sleep(Duration::from_millis(50));
-
-        count[val] -= 1;
-        output[count[val]] = val;
-
-        let _ = tx.send(Operation::SetColor(i, Color32::GRAY));
-        thread::sleep(Duration::from_millis(30));
+        let k = key(val);
+        sink.send(Operation::SetColor(i, Color32::BLUE))?;
+        sink.send(Operation::Read(i))?;
+
+        sink.send(Operation::Line(6))?;
+        count[k] -= 1;
+        output[count[k]] = val;
+        count_aux.write(sink, k, count[k])?;
+        sink.send(Operation::Line(7))?;
+        output_aux.write(sink, count[k], val)?;
+
+        sink.send(Operation::SetColor(i, Color32::GRAY))?;
     }
 
     // Copy the sorted elements back to original array with visual feedback
     for i in 0..n {
-        let _ = tx.send(Operation::SetColor(i, Color32::GREEN));
+        sink.send(Operation::Line(8))?;
+        output_aux.read(sink, i)?;
         bars[i].value = output[i];
-        thread::sleep(Duration::from_millis(80));
+        sink.send(Operation::Overwrite(i, bars[i].clone()))?;
+        sink.send(Operation::SetColor(i, Color32::GREEN))?;
 
-        let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
-        thread::sleep(Duration::from_millis(20));
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
     }
+    count_aux.release(sink)?;
+    output_aux.release(sink)?;
 
     // Final pass to show completion
     for i in 0..n {
-        let _ = tx.send(Operation::SetColor(i, Color32::LIGHT_GREEN));
-        thread::sleep(Duration::from_millis(20));
-        let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
+        sink.send(Operation::SetColor(i, Color32::LIGHT_GREEN))?;
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
     }
+    Ok(())
 }
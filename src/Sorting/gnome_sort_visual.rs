@@ -1,86 +1,116 @@
 use crate::models::SortBar;
-use crate::sorting::Operation;
+use crate::sorting::{Cancelled, Category, Complexity, Operation, Sink, SortAlgorithm};
 use eframe::egui::Color32;
-use std::sync::mpsc;
-use std::thread;
-use std::time::Duration;
 
-pub fn gnome_sort_visual(bars: &mut Vec<SortBar>, tx: &mpsc::Sender<Operation>) {
+pub struct GnomeSort;
+
+impl SortAlgorithm for GnomeSort {
+    fn name(&self) -> &'static str {
+        "Gnome Sort"
+    }
+
+    fn category(&self) -> Category {
+        Category::Educational
+    }
+
+    fn is_stable(&self) -> bool {
+        true
+    }
+
+    fn complexity(&self) -> Complexity {
+        Complexity {
+            best: "O(n)",
+            average: "O(n²)",
+            worst: "O(n²)",
+            space: "O(1)",
+        }
+    }
+
+    fn run(&self, bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+        gnome_sort_visual(bars, sink)
+    }
+
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "i = 0",
+            "while i < n:",
+            "    if i == 0 or a[i - 1] <= a[i]:",
+            "        i = i + 1",
+            "    else:",
+            "        swap a[i - 1], a[i]",
+            "        i = i - 1",
+        ]
+    }
+}
+
+pub fn gnome_sort_visual(bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
     let n = bars.len();
     if n <= 1 {
-        return;
+        return Ok(());
     }
 
+    sink.send(Operation::Line(1))?;
     let mut index = 0;
 
     while index < n {
         // Highlight current position being examined (blue)
-        let _ = tx.send(Operation::SetColor(index, Color32::BLUE));
-        thread::sleep(Duration::from_millis(100));
+        sink.send(Operation::SetColor(index, Color32::BLUE))?;
 
         // Show sorted portion (light green) and unsorted portion (gray)
         for i in 0..index {
-            let _ = tx.send(Operation::SetColor(i, Color32::LIGHT_GREEN));
+            sink.send(Operation::SetColor(i, Color32::LIGHT_GREEN))?;
         }
         for i in index + 1..n {
-            let _ = tx.send(Operation::SetColor(i, Color32::GRAY));
+            sink.send(Operation::SetColor(i, Color32::GRAY))?;
         }
 
+        sink.send(Operation::Line(3))?;
         if index == 0 {
             // At the beginning, just move forward
-            let _ = tx.send(Operation::SetColor(index, Color32::LIGHT_GREEN));
+            sink.send(Operation::Line(4))?;
+            sink.send(Operation::SetColor(index, Color32::LIGHT_GREEN))?;
             index += 1;
-            thread::sleep(Duration::from_millis(80));
         } else {
             // Compare current element with previous element
-            let _ = tx.send(Operation::SetColor(index - 1, Color32::YELLOW));
-            let _ = tx.send(Operation::SetColor(index, Color32::YELLOW));
-            let _ = tx.send(Operation::Compare(index - 1, index));
-            thread::sleep(Duration::from_millis(120));
+            sink.send(Operation::SetColor(index - 1, Color32::YELLOW))?;
+            sink.send(Operation::SetColor(index, Color32::YELLOW))?;
+            sink.send(Operation::Compare(index - 1, index))?;
 
             if bars[index - 1].value <= bars[index].value {
                 // Elements are in correct order, move forward
-                let _ = tx.send(Operation::SetColor(index - 1, Color32::LIGHT_GREEN));
-                let _ = tx.send(Operation::SetColor(index, Color32::BLUE));
-                thread::sleep(Duration::from_millis(60));
+                sink.send(Operation::Line(4))?;
+                sink.send(Operation::SetColor(index - 1, Color32::LIGHT_GREEN))?;
+                sink.send(Operation::SetColor(index, Color32::BLUE))?;
                 index += 1;
             } else {
                 // Elements are out of order, swap and move backward
-                let _ = tx.send(Operation::SetColor(index - 1, Color32::RED));
-                let _ = tx.send(Operation::SetColor(index, Color32::RED));
-                thread::sleep(Duration::from_millis(80));
+                sink.send(Operation::SetColor(index - 1, Color32::RED))?;
+                sink.send(Operation::SetColor(index, Color32::RED))?;
 
-                let _ = tx.send(Operation::Swap(index - 1, index));
+                sink.send(Operation::Line(6))?;
+                sink.send(Operation::Swap(index - 1, index))?;
                 bars.swap(index - 1, index);
-                thread::sleep(Duration::from_millis(100));
 
                 // Show the gnome moving backward (characteristic of gnome sort)
-                let _ = tx.send(Operation::SetColor(
+                sink.send(Operation::SetColor(
                     index - 1,
                     Color32::from_rgb(255, 165, 0),
-                )); // Orange
-                let _ = tx.send(Operation::SetColor(index, Color32::GRAY));
-                thread::sleep(Duration::from_millis(80));
+                ))?; // Orange
+                sink.send(Operation::SetColor(index, Color32::GRAY))?;
 
                 index -= 1;
             }
         }
-
-        // Brief pause to show the gnome's movement pattern
-        thread::sleep(Duration::from_millis(40));
     }
 
     // Final sweep: show completion with a wave effect
     for i in 0..n {
-        let _ = tx.send(Operation::SetColor(i, Color32::LIGHT_GREEN));
-        thread::sleep(Duration::from_millis(50));
+        sink.send(Operation::SetColor(i, Color32::LIGHT_GREEN))?;
     }
 
-    thread::sleep(Duration::from_millis(200));
-
     // Reset all colors to white
     for i in 0..n {
-        let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
-        thread::sleep(Duration::from_millis(20));
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
     }
+    Ok(())
 }
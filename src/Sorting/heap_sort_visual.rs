@@ -1,76 +1,123 @@
 use crate::models::SortBar;
-use crate::sorting::Operation;
+use crate::sorting::{Cancelled, Category, Complexity, Operation, Sink, SortAlgorithm};
 use eframe::egui::Color32;
-use std::sync::mpsc;
-use std::thread;
-use std::time::Duration;
 
-pub fn heap_sort_visual(bars: &mut Vec<SortBar>, tx: &mpsc::Sender<Operation>) {
+pub struct HeapSort;
+
+impl SortAlgorithm for HeapSort {
+    fn name(&self) -> &'static str {
+        "Heap Sort"
+    }
+
+    fn category(&self) -> Category {
+        Category::Production
+    }
+
+    fn is_stable(&self) -> bool {
+        false
+    }
+
+    fn complexity(&self) -> Complexity {
+        Complexity {
+            best: "O(n log n)",
+            average: "O(n log n)",
+            worst: "O(n log n)",
+            space: "O(1)",
+        }
+    }
+
+    fn run(&self, bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+        heap_sort_visual(bars, sink)
+    }
+
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "for i in n / 2 - 1 down to 0:",
+            "    sift_down(i, n)",
+            "for end in n - 1 down to 1:",
+            "    swap a[0], a[end]",
+            "    sift_down(0, end)",
+            "",
+            "sift_down(root, size):",
+            "    largest = root",
+            "    for each child of root below size:",
+            "        if a[child] > a[largest]: largest = child",
+            "    if largest != root:",
+            "        swap a[root], a[largest]",
+            "        sift_down(largest, size)",
+        ]
+    }
+}
+
+pub fn heap_sort_visual(bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
     let n = bars.len();
 
     if n <= 1 {
-        return;
+        return Ok(());
     }
 
     // Build max heap
     for i in (0..n / 2).rev() {
         // Highlight the node being heapified
-        let _ = tx.send(Operation::SetColor(i, Color32::BLUE));
-        thread::sleep(Duration::from_millis(100));
+        sink.send(Operation::SetColor(i, Color32::BLUE))?;
+        sink.send(Operation::SiftDown(i, n))?;
+        sink.send(Operation::Line(2))?;
 
-        heapify_visual(bars, n, i, tx);
+        heapify_visual(bars, n, i, sink)?;
 
         // Reset color
-        let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
     }
 
     // Extract elements from heap one by one
     for i in (1..n).rev() {
         // Highlight the elements being swapped (max element to sorted position)
-        let _ = tx.send(Operation::SetColor(0, Color32::RED));
-        let _ = tx.send(Operation::SetColor(i, Color32::GREEN));
-        thread::sleep(Duration::from_millis(150));
+        sink.send(Operation::SetColor(0, Color32::RED))?;
+        sink.send(Operation::SetColor(i, Color32::GREEN))?;
 
         // Move current root (maximum) to end
-        let _ = tx.send(Operation::Swap(0, i));
+        sink.send(Operation::Line(4))?;
+        sink.send(Operation::Swap(0, i))?;
         bars.swap(0, i);
-        thread::sleep(Duration::from_millis(80));
 
         // Mark the sorted element
-        let _ = tx.send(Operation::SetColor(i, Color32::LIGHT_GREEN));
+        sink.send(Operation::SetColor(i, Color32::LIGHT_GREEN))?;
 
         // Reset root color
-        let _ = tx.send(Operation::SetColor(0, Color32::WHITE));
+        sink.send(Operation::SetColor(0, Color32::WHITE))?;
 
         // Call heapify on the reduced heap
-        heapify_visual(bars, i, 0, tx);
+        sink.send(Operation::SiftDown(0, i))?;
+        sink.send(Operation::Line(5))?;
+        heapify_visual(bars, i, 0, sink)?;
     }
 
     // Reset all colors to white at the end
     for i in 0..bars.len() {
-        let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
     }
+    Ok(())
 }
 
 fn heapify_visual(
-    bars: &mut Vec<SortBar>,
+    bars: &mut [SortBar],
     heap_size: usize,
     root: usize,
-    tx: &mpsc::Sender<Operation>,
-) {
+    sink: &Sink,
+) -> Result<(), Cancelled> {
+    sink.send(Operation::Line(8))?;
     let mut largest = root;
     let left_child = 2 * root + 1;
     let right_child = 2 * root + 2;
 
     // Highlight the current root
-    let _ = tx.send(Operation::SetColor(root, Color32::YELLOW));
-    thread::sleep(Duration::from_millis(60));
+    sink.send(Operation::SetColor(root, Color32::YELLOW))?;
 
     // Check if left child exists and is greater than root
     if left_child < heap_size {
-        let _ = tx.send(Operation::SetColor(left_child, Color32::LIGHT_BLUE));
-        let _ = tx.send(Operation::Compare(left_child, largest));
-        thread::sleep(Duration::from_millis(80));
+        sink.send(Operation::SetColor(left_child, Color32::LIGHT_BLUE))?;
+        sink.send(Operation::Line(10))?;
+        sink.send(Operation::Compare(left_child, largest))?;
 
         if bars[left_child].value > bars[largest].value {
             largest = left_child;
@@ -79,9 +126,9 @@ fn heapify_visual(
 
     // Check if right child exists and is greater than largest so far
     if right_child < heap_size {
-        let _ = tx.send(Operation::SetColor(right_child, Color32::LIGHT_BLUE));
-        let _ = tx.send(Operation::Compare(right_child, largest));
-        thread::sleep(Duration::from_millis(80));
+        sink.send(Operation::SetColor(right_child, Color32::LIGHT_BLUE))?;
+        sink.send(Operation::Line(10))?;
+        sink.send(Operation::Compare(right_child, largest))?;
 
         if bars[right_child].value > bars[largest].value {
             largest = right_child;
@@ -91,33 +138,34 @@ fn heapify_visual(
     // If largest is not root, swap and continue heapifying
     if largest != root {
         // Highlight the elements being swapped
-        let _ = tx.send(Operation::SetColor(root, Color32::RED));
-        let _ = tx.send(Operation::SetColor(largest, Color32::RED));
-        thread::sleep(Duration::from_millis(100));
+        sink.send(Operation::SetColor(root, Color32::RED))?;
+        sink.send(Operation::SetColor(largest, Color32::RED))?;
 
-        let _ = tx.send(Operation::Swap(root, largest));
+        sink.send(Operation::Line(12))?;
+        sink.send(Operation::Swap(root, largest))?;
         bars.swap(root, largest);
-        thread::sleep(Duration::from_millis(80));
 
         // Reset colors
-        let _ = tx.send(Operation::SetColor(root, Color32::WHITE));
+        sink.send(Operation::SetColor(root, Color32::WHITE))?;
         if left_child < heap_size {
-            let _ = tx.send(Operation::SetColor(left_child, Color32::WHITE));
+            sink.send(Operation::SetColor(left_child, Color32::WHITE))?;
         }
         if right_child < heap_size {
-            let _ = tx.send(Operation::SetColor(right_child, Color32::WHITE));
+            sink.send(Operation::SetColor(right_child, Color32::WHITE))?;
         }
 
         // Recursively heapify the affected sub-tree
-        heapify_visual(bars, heap_size, largest, tx);
+        sink.send(Operation::Line(13))?;
+        heapify_visual(bars, heap_size, largest, sink)?;
     } else {
         // Reset colors if no swap occurred
-        let _ = tx.send(Operation::SetColor(root, Color32::WHITE));
+        sink.send(Operation::SetColor(root, Color32::WHITE))?;
         if left_child < heap_size {
-            let _ = tx.send(Operation::SetColor(left_child, Color32::WHITE));
+            sink.send(Operation::SetColor(left_child, Color32::WHITE))?;
         }
         if right_child < heap_size {
-            let _ = tx.send(Operation::SetColor(right_child, Color32::WHITE));
+            sink.send(Operation::SetColor(right_child, Color32::WHITE))?;
         }
     }
+    Ok(())
 }
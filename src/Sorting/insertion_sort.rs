@@ -1,68 +1,103 @@
 use crate::models::SortBar;
+use crate::sorting::{Cancelled, Category, Complexity, Sink, SortAlgorithm};
 use eframe::egui::Color32;
-use std::sync::mpsc;
-use std::time::Duration;
+
+pub struct InsertionSort;
+
+impl SortAlgorithm for InsertionSort {
+    fn name(&self) -> &'static str {
+        "Insertion Sort"
+    }
+
+    fn category(&self) -> Category {
+        Category::Educational
+    }
+
+    fn is_stable(&self) -> bool {
+        true
+    }
+
+    fn complexity(&self) -> Complexity {
+        Complexity {
+            best: "O(n)",
+            average: "O(n²)",
+            worst: "O(n²)",
+            space: "O(1)",
+        }
+    }
+
+    fn run(&self, bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+        insertion_sort(bars, sink)
+    }
+
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "for i in 1 .. n:",
+            "    j = i",
+            "    while j > 0 and a[j - 1] > a[j]:",
+            "        swap a[j - 1], a[j]",
+            "        j = j - 1",
+        ]
+    }
+}
 
 /// Enhanced Visual Insertion Sort implementation
-pub fn insertion_sort(bars: &mut Vec<SortBar>, tx: &mpsc::Sender<crate::sorting::Operation>) {
+pub fn insertion_sort(bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
     let n = bars.len();
 
     // Mark the first element as sorted (green)
-    let _ = tx.send(crate::sorting::Operation::SetColor(0, Color32::LIGHT_GREEN));
-    std::thread::sleep(Duration::from_millis(100));
+    sink.send(crate::sorting::Operation::SetColor(0, Color32::LIGHT_GREEN))?;
 
     for i in 1..n {
+        sink.send(crate::sorting::Operation::Line(1))?;
+
         // Highlight the current element being inserted (red)
-        let _ = tx.send(crate::sorting::Operation::SetColor(i, Color32::RED));
-        std::thread::sleep(Duration::from_millis(150));
+        sink.send(crate::sorting::Operation::SetColor(i, Color32::RED))?;
 
         let mut j = i;
 
         // Show the sorted portion (light green) and unsorted portion (gray)
         for k in 0..i {
-            let _ = tx.send(crate::sorting::Operation::SetColor(k, Color32::LIGHT_GREEN));
+            sink.send(crate::sorting::Operation::SetColor(k, Color32::LIGHT_GREEN))?;
         }
         for k in i + 1..n {
-            let _ = tx.send(crate::sorting::Operation::SetColor(k, Color32::GRAY));
+            sink.send(crate::sorting::Operation::SetColor(k, Color32::GRAY))?;
         }
-        std::thread::sleep(Duration::from_millis(100));
 
         // Find the correct position for the current element
         while j > 0 {
             // Highlight comparison elements
-            let _ = tx.send(crate::sorting::Operation::SetColor(j - 1, Color32::YELLOW));
-            let _ = tx.send(crate::sorting::Operation::Compare(j - 1, j));
-            std::thread::sleep(Duration::from_millis(80));
+            sink.send(crate::sorting::Operation::SetColor(j - 1, Color32::YELLOW))?;
+            sink.send(crate::sorting::Operation::Line(3))?;
+            sink.send(crate::sorting::Operation::Compare(j - 1, j))?;
 
             if bars[j - 1].value > bars[j].value {
                 // Show the swap with distinct colors
-                let _ = tx.send(crate::sorting::Operation::SetColor(j - 1, Color32::BLUE));
-                let _ = tx.send(crate::sorting::Operation::SetColor(j, Color32::BLUE));
-                std::thread::sleep(Duration::from_millis(60));
+                sink.send(crate::sorting::Operation::SetColor(j - 1, Color32::BLUE))?;
+                sink.send(crate::sorting::Operation::SetColor(j, Color32::BLUE))?;
 
-                let _ = tx.send(crate::sorting::Operation::Swap(j - 1, j));
+                sink.send(crate::sorting::Operation::Line(4))?;
+                sink.send(crate::sorting::Operation::Swap(j - 1, j))?;
                 bars.swap(j - 1, j);
-                std::thread::sleep(Duration::from_millis(80));
 
                 j -= 1;
             } else {
                 // Found correct position, reset comparison color
-                let _ = tx.send(crate::sorting::Operation::SetColor(
+                sink.send(crate::sorting::Operation::SetColor(
                     j - 1,
                     Color32::LIGHT_GREEN,
-                ));
+                ))?;
                 break;
             }
         }
 
         // Mark the inserted element as part of sorted array
-        let _ = tx.send(crate::sorting::Operation::SetColor(j, Color32::LIGHT_GREEN));
-        std::thread::sleep(Duration::from_millis(100));
+        sink.send(crate::sorting::Operation::SetColor(j, Color32::LIGHT_GREEN))?;
     }
 
     // Final pass: mark all elements as sorted (white)
     for i in 0..n {
-        let _ = tx.send(crate::sorting::Operation::SetColor(i, Color32::WHITE));
-        std::thread::sleep(Duration::from_millis(30));
+        sink.send(crate::sorting::Operation::SetColor(i, Color32::WHITE))?;
     }
+    Ok(())
 }
@@ -1,70 +1,131 @@
 use crate::models::SortBar;
-use crate::sorting::Operation;
+use crate::sorting::{Cancelled, Category, Complexity, Operation, Sink, SortAlgorithm};
 use eframe::egui::Color32;
-use std::sync::mpsc;
-use std::thread;
-use std::time::Duration;
 
-pub fn merge_sort_visual(bars: &mut Vec<SortBar>, tx: &mpsc::Sender<Operation>) {
+pub struct MergeSort;
+
+impl SortAlgorithm for MergeSort {
+    fn name(&self) -> &'static str {
+        "Merge Sort"
+    }
+
+    fn category(&self) -> Category {
+        Category::Production
+    }
+
+    fn is_stable(&self) -> bool {
+        true
+    }
+
+    fn complexity(&self) -> Complexity {
+        Complexity {
+            best: "O(n log n)",
+            average: "O(n log n)",
+            worst: "O(n log n)",
+            space: "O(n)",
+        }
+    }
+
+    fn run(&self, bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+        merge_sort_visual(bars, sink)
+    }
+
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "mergesort(lo, hi):",
+            "    if lo < hi:",
+            "        mid = (lo + hi) / 2",
+            "        mergesort(lo, mid)",
+            "        mergesort(mid + 1, hi)",
+            "        merge(lo, mid, hi)",
+            "",
+            "merge(lo, mid, hi):",
+            "    copy a[lo ..= mid] to L and a[mid + 1 ..= hi] to R",
+            "    while L and R both have elements left:",
+            "        if L[i] <= R[j]: a[k] = L[i]; i = i + 1",
+            "        else:            a[k] = R[j]; j = j + 1",
+            "    copy what is left of L or R to the rest of a",
+        ]
+    }
+}
+
+pub fn merge_sort_visual(bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
     let len = bars.len();
     if len > 1 {
-        merge_sort_recursive(bars, 0, len - 1, tx);
+        merge_sort_recursive(bars, 0, len - 1, sink)?;
     }
 
     // Reset all colors to white at the end
     for i in 0..bars.len() {
-        let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
     }
+    Ok(())
 }
 
 fn merge_sort_recursive(
-    bars: &mut Vec<SortBar>,
+    bars: &mut [SortBar],
     left: usize,
     right: usize,
-    tx: &mpsc::Sender<Operation>,
-) {
+    sink: &Sink,
+) -> Result<(), Cancelled> {
+    sink.send(Operation::EnterRange(left, right + 1))?;
+    sink.send(Operation::Line(2))?;
     if left < right {
+        sink.send(Operation::Line(3))?;
         let mid = left + (right - left) / 2;
 
         // Highlight the current section being divided
         for i in left..=right {
-            let _ = tx.send(Operation::SetColor(i, Color32::LIGHT_BLUE));
+            sink.send(Operation::SetColor(i, Color32::LIGHT_BLUE))?;
         }
-        thread::sleep(Duration::from_millis(100));
 
         // Recursively sort left half
-        merge_sort_recursive(bars, left, mid, tx);
+        sink.send(Operation::Line(4))?;
+        merge_sort_recursive(bars, left, mid, sink)?;
 
         // Recursively sort right half
-        merge_sort_recursive(bars, mid + 1, right, tx);
+        sink.send(Operation::Line(5))?;
+        merge_sort_recursive(bars, mid + 1, right, sink)?;
 
         // Merge the sorted halves
-        merge_visual(bars, left, mid, right, tx);
+        sink.send(Operation::Line(6))?;
+        merge_visual(bars, left, mid, right, sink)?;
     }
+    sink.send(Operation::ExitRange(left, right + 1))
 }
 
 fn merge_visual(
-    bars: &mut Vec<SortBar>,
+    bars: &mut [SortBar],
     left: usize,
     mid: usize,
     right: usize,
-    tx: &mpsc::Sender<Operation>,
-) {
+    sink: &Sink,
+) -> Result<(), Cancelled> {
     // Create temporary arrays for left and right subarrays
     let left_arr: Vec<usize> = bars[left..=mid].iter().map(|b| b.value).collect();
     let right_arr: Vec<usize> = bars[mid + 1..=right].iter().map(|b| b.value).collect();
 
     let left_size = left_arr.len();
     let right_size = right_arr.len();
+    sink.send(Operation::Line(9))?;
+    let left_aux = sink.aux("left", left_size)?;
+    let right_aux = sink.aux("right", right_size)?;
+    for (i, &value) in left_arr.iter().enumerate() {
+        sink.send(Operation::Read(left + i))?;
+        left_aux.write(sink, i, value)?;
+    }
+    for (j, &value) in right_arr.iter().enumerate() {
+        sink.send(Operation::Read(mid + 1 + j))?;
+        right_aux.write(sink, j, value)?;
+    }
 
     // Highlight the sections being merged
     for i in left..=mid {
-        let _ = tx.send(Operation::SetColor(i, Color32::GREEN));
+        sink.send(Operation::SetColor(i, Color32::GREEN))?;
     }
     for i in mid + 1..=right {
-        let _ = tx.send(Operation::SetColor(i, Color32::YELLOW));
+        sink.send(Operation::SetColor(i, Color32::YELLOW))?;
     }
-    thread::sleep(Duration::from_millis(100));
 
     let mut i = 0; // Index for left subarray
     let mut j = 0; // Index for right subarray
@@ -76,46 +137,57 @@ fn merge_visual(
         let left_idx = left + i;
         let right_idx = mid + 1 + j;
 
-        let _ = tx.send(Operation::Compare(left_idx, right_idx));
-        thread::sleep(Duration::from_millis(80));
+        sink.send(Operation::Line(10))?;
+        sink.send(Operation::Compare(left_idx, right_idx))?;
 
         if left_arr[i] <= right_arr[j] {
             // Take from left array
-            let _ = tx.send(Operation::SetColor(k, Color32::LIGHT_GREEN));
+            sink.send(Operation::Line(11))?;
+            left_aux.read(sink, i)?;
             bars[k].value = left_arr[i];
+            sink.send(Operation::Overwrite(k, bars[k].clone()))?;
+            sink.send(Operation::SetColor(k, Color32::LIGHT_GREEN))?;
             i += 1;
         } else {
             // Take from right array
-            let _ = tx.send(Operation::SetColor(k, Color32::LIGHT_YELLOW));
+            sink.send(Operation::Line(12))?;
+            right_aux.read(sink, j)?;
             bars[k].value = right_arr[j];
+            sink.send(Operation::Overwrite(k, bars[k].clone()))?;
+            sink.send(Operation::SetColor(k, Color32::LIGHT_YELLOW))?;
             j += 1;
         }
 
-        thread::sleep(Duration::from_millis(60));
         k += 1;
     }
 
     // Copy remaining elements from left array
+    sink.send(Operation::Line(13))?;
     while i < left_size {
-        let _ = tx.send(Operation::SetColor(k, Color32::LIGHT_GREEN));
+        left_aux.read(sink, i)?;
         bars[k].value = left_arr[i];
-        thread::sleep(Duration::from_millis(40));
+        sink.send(Operation::Overwrite(k, bars[k].clone()))?;
+        sink.send(Operation::SetColor(k, Color32::LIGHT_GREEN))?;
         i += 1;
         k += 1;
     }
 
     // Copy remaining elements from right array
     while j < right_size {
-        let _ = tx.send(Operation::SetColor(k, Color32::LIGHT_YELLOW));
+        right_aux.read(sink, j)?;
         bars[k].value = right_arr[j];
-        thread::sleep(Duration::from_millis(40));
+        sink.send(Operation::Overwrite(k, bars[k].clone()))?;
+        sink.send(Operation::SetColor(k, Color32::LIGHT_YELLOW))?;
         j += 1;
         k += 1;
     }
 
+    left_aux.release(sink)?;
+    right_aux.release(sink)?;
+
     // Reset colors for the merged section
     for idx in left..=right {
-        let _ = tx.send(Operation::SetColor(idx, Color32::WHITE));
+        sink.send(Operation::SetColor(idx, Color32::WHITE))?;
     }
-    thread::sleep(Duration::from_millis(50));
+    Ok(())
 }
@@ -0,0 +1,91 @@
+use super::sort_utils::run_network;
+use crate::models::SortBar;
+use crate::sorting::{Cancelled, Category, Complexity, NetworkLayer, Sink, SortAlgorithm};
+
+pub struct OddEvenMergeSort;
+
+impl SortAlgorithm for OddEvenMergeSort {
+    fn name(&self) -> &'static str {
+        "Odd-Even Merge Sort"
+    }
+
+    fn category(&self) -> Category {
+        Category::Specialized
+    }
+
+    fn is_stable(&self) -> bool {
+        false
+    }
+
+    fn complexity(&self) -> Complexity {
+        Complexity {
+            best: "O(n log² n)",
+            average: "O(n log² n)",
+            worst: "O(n log² n)",
+            space: "O(1)",
+        }
+    }
+
+    fn run(&self, bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+        odd_even_merge_sort(bars, sink)
+    }
+
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "for p = 1, 2, 4, ... while p < n:",
+            "    for k = p, p/2, ..., 1:",
+            "        one layer: for j = k mod p, k mod p + 2k, ...:",
+            "            for i = 0 .. k - 1:",
+            "                if i + j and i + j + k are in one block of 2p:",
+            "                    compare a[i + j] with a[i + j + k]",
+            "every comparator puts the smaller value first;",
+            "ones reaching past the end of the array are left out",
+        ]
+    }
+
+    fn network(&self, n: usize) -> Option<Vec<NetworkLayer>> {
+        Some(odd_even_merge_network(n))
+    }
+}
+
+// Line of the listing in `OddEvenMergeSort::pseudocode` every layer runs
+const LINE_COMPARE: usize = 6;
+
+/// Batcher's odd-even merge sort, as a sorting network of
+/// `odd_even_merge_network`.
+pub fn odd_even_merge_sort(bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+    run_network(bars, &odd_even_merge_network(bars.len()), sink)
+}
+
+/// Batcher's odd-even merge sorting network on `n` elements. Merging two
+/// sorted blocks of `p` merges their even and their odd elements, then
+/// fixes up neighbours; unrolled, each `(p, k)` step is one layer.
+///
+/// Like `bitonic_network`, it is built as if `n` were padded to a power of
+/// two with values greater than any in the array, leaving out the
+/// comparators that would reach into the padding. It has the same depth
+/// but fewer comparators.
+pub fn odd_even_merge_network(n: usize) -> Vec<NetworkLayer> {
+    let mut network = Vec::new();
+    let mut p = 1;
+    while p < n {
+        let mut k = p;
+        while k >= 1 {
+            let mut comparators = Vec::new();
+            for j in (k % p..n.saturating_sub(k)).step_by(2 * k) {
+                for i in 0..k.min(n - j - k) {
+                    if (i + j) / (2 * p) == (i + j + k) / (2 * p) {
+                        comparators.push((i + j, i + j + k));
+                    }
+                }
+            }
+            network.push(NetworkLayer {
+                comparators,
+                line: LINE_COMPARE,
+            });
+            k /= 2;
+        }
+        p *= 2;
+    }
+    network
+}
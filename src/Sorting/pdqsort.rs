@@ -0,0 +1,429 @@
+use crate::models::SortBar;
+use crate::sorting::{Cancelled, Category, Complexity, Operation, Sink, SortAlgorithm};
+use eframe::egui::Color32;
+
+pub struct PdqSort;
+
+impl SortAlgorithm for PdqSort {
+    fn name(&self) -> &'static str {
+        "Pdqsort"
+    }
+
+    fn category(&self) -> Category {
+        Category::Production
+    }
+
+    fn is_stable(&self) -> bool {
+        false
+    }
+
+    fn complexity(&self) -> Complexity {
+        Complexity {
+            best: "O(n)",
+            average: "O(n log n)",
+            worst: "O(n log n)",
+            space: "O(log n)",
+        }
+    }
+
+    fn run(&self, bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+        pdq_sort(bars, sink)
+    }
+
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "pdqsort(a): pdq(0, n, depth = 2 * floor(log2 n))",
+            "",
+            "pdq(lo, hi, depth):",
+            "    if hi - lo < 24:",
+            "        insertion sort a[lo..hi]; return",
+            "    if depth == 0:",
+            "        heap sort a[lo..hi]; return",
+            "    move the median of 3 (of 9 when large) to a[lo]",
+            "    if a[lo - 1] equals the pivot:",
+            "        move everything equal to the pivot left",
+            "        pdq(past the equal ones, hi, depth - 1); return",
+            "    p = partition(lo, hi) around a[lo]",
+            "    if one side has under 1/8 of the elements:",
+            "        swap a few elements to break up the pattern",
+            "    else if partitioning swapped nothing:",
+            "        insertion sort both sides, giving up past 8 moves",
+            "        if neither gave up: return",
+            "    pdq(lo, p, depth - 1)",
+            "    pdq(p + 1, hi, depth - 1)",
+        ]
+    }
+}
+
+/// Partitions smaller than this are insertion sorted.
+const INSERTION_THRESHOLD: usize = 24;
+
+/// Partitions larger than this take the pivot from nine elements.
+const NINTHER_THRESHOLD: usize = 128;
+
+/// Moves a partial insertion sort makes before giving up.
+const PARTIAL_INSERTION_LIMIT: usize = 8;
+
+const PIVOT_COLOR: Color32 = Color32::RED;
+
+/// Pattern-defeating quicksort with an introsort depth limit.
+///
+/// A quicksort that takes its pivot from the median of three or nine
+/// elements, insertion sorts small partitions, heap sorts any partition
+/// reached below `2 * floor(log2 n)` levels of recursion, and looks for
+/// patterns: runs of equal elements, input that is already sorted, and
+/// partitions so unbalanced they suggest an adversarial layout. Each
+/// fallback is announced with `Operation::Note`.
+pub fn pdq_sort(bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+    let depth_limit = match bars.len() {
+        0 => 0,
+        n => 2 * n.ilog2() as usize,
+    };
+    pdq_sort_with_depth_limit(bars, depth_limit, sink)
+}
+
+/// `pdq_sort` with partitions heap sorted once `depth_limit` levels of
+/// recursion are reached, rather than `2 * floor(log2 n)`.
+pub fn pdq_sort_with_depth_limit(
+    bars: &mut [SortBar],
+    depth_limit: usize,
+    sink: &Sink,
+) -> Result<(), Cancelled> {
+    sink.send(Operation::Line(1))?;
+    pdq_recursive(bars, 0, bars.len(), depth_limit, true, sink)?;
+
+    // Reset all colors to white at the end
+    for i in 0..bars.len() {
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
+    }
+    Ok(())
+}
+
+/// Sort `bars[lo..hi]`. `leftmost` is false when an element no greater than
+/// any in the range sits just before it, at `lo - 1`.
+fn pdq_recursive(
+    bars: &mut [SortBar],
+    lo: usize,
+    hi: usize,
+    depth: usize,
+    leftmost: bool,
+    sink: &Sink,
+) -> Result<(), Cancelled> {
+    if hi - lo <= 1 {
+        return Ok(());
+    }
+    sink.send(Operation::EnterRange(lo, hi))?;
+    pdq_body(bars, lo, hi, depth, leftmost, sink)?;
+    sink.send(Operation::ExitRange(lo, hi))
+}
+
+fn pdq_body(
+    bars: &mut [SortBar],
+    lo: usize,
+    hi: usize,
+    depth: usize,
+    leftmost: bool,
+    sink: &Sink,
+) -> Result<(), Cancelled> {
+    let size = hi - lo;
+    sink.send(Operation::Line(4))?;
+    if size < INSERTION_THRESHOLD {
+        sink.send(Operation::Note(lo, hi, "Insertion sort: small partition"))?;
+        sink.send(Operation::Line(5))?;
+        insertion_sort(bars, lo, hi, usize::MAX, sink)?;
+        return Ok(());
+    }
+    sink.send(Operation::Line(6))?;
+    if depth == 0 {
+        sink.send(Operation::Note(lo, hi, "Heap sort: depth limit reached"))?;
+        sink.send(Operation::Line(7))?;
+        return heap_sort_range(bars, lo, hi, sink);
+    }
+
+    sink.send(Operation::Line(8))?;
+    choose_pivot(bars, lo, hi, sink)?;
+
+    // The pivot can only equal the element before the range if that element
+    // was an earlier pivot; everything equal to it is then already in place
+    // once moved left
+    if !leftmost {
+        sink.send(Operation::Line(9))?;
+        sink.send(Operation::Compare(lo - 1, lo))?;
+        if bars[lo - 1].value >= bars[lo].value {
+            sink.send(Operation::Note(
+                lo,
+                hi,
+                "Many equal elements: pivot equals its predecessor",
+            ))?;
+            sink.send(Operation::Line(10))?;
+            let mid = partition_left(bars, lo, hi, sink)?;
+            sink.send(Operation::Line(11))?;
+            return pdq_recursive(bars, mid + 1, hi, depth - 1, false, sink);
+        }
+    }
+
+    sink.send(Operation::Line(12))?;
+    let (pivot, already_partitioned) = partition_right(bars, lo, hi, sink)?;
+    let (left, right) = (pivot - lo, hi - pivot - 1);
+
+    sink.send(Operation::Line(13))?;
+    if left < size / 8 || right < size / 8 {
+        sink.send(Operation::Note(
+            lo,
+            hi,
+            "Unbalanced partition: breaking up the pattern",
+        ))?;
+        sink.send(Operation::Line(14))?;
+        if left >= INSERTION_THRESHOLD {
+            swap(bars, lo, lo + left / 4, sink)?;
+            swap(bars, pivot - 1, pivot - left / 4, sink)?;
+        }
+        if right >= INSERTION_THRESHOLD {
+            swap(bars, pivot + 1, pivot + 1 + right / 4, sink)?;
+            swap(bars, hi - 1, hi - right / 4, sink)?;
+        }
+    } else {
+        sink.send(Operation::Line(15))?;
+        if already_partitioned {
+            sink.send(Operation::Note(
+                lo,
+                hi,
+                "Looks sorted: trying insertion sort",
+            ))?;
+            sink.send(Operation::Line(16))?;
+            let done = insertion_sort(bars, lo, pivot, PARTIAL_INSERTION_LIMIT, sink)?
+                && insertion_sort(bars, pivot + 1, hi, PARTIAL_INSERTION_LIMIT, sink)?;
+            sink.send(Operation::Line(17))?;
+            if done {
+                return Ok(());
+            }
+        }
+    }
+
+    sink.send(Operation::Line(18))?;
+    pdq_recursive(bars, lo, pivot, depth - 1, leftmost, sink)?;
+    sink.send(Operation::Line(19))?;
+    pdq_recursive(bars, pivot + 1, hi, depth - 1, false, sink)
+}
+
+fn swap(bars: &mut [SortBar], i: usize, j: usize, sink: &Sink) -> Result<(), Cancelled> {
+    if i != j {
+        sink.send(Operation::Swap(i, j))?;
+        bars.swap(i, j);
+    }
+    Ok(())
+}
+
+/// Order `bars[a]`, `bars[b]` and `bars[c]`, leaving the median at `b`.
+fn sort3(bars: &mut [SortBar], a: usize, b: usize, c: usize, sink: &Sink) -> Result<(), Cancelled> {
+    for (i, j) in [(a, b), (b, c), (a, b)] {
+        sink.send(Operation::Compare(j, i))?;
+        if bars[j].value < bars[i].value {
+            swap(bars, i, j, sink)?;
+        }
+    }
+    Ok(())
+}
+
+/// Move the median of three elements, or for large ranges the median of
+/// three medians of three, to `lo`.
+fn choose_pivot(bars: &mut [SortBar], lo: usize, hi: usize, sink: &Sink) -> Result<(), Cancelled> {
+    let mid = lo + (hi - lo) / 2;
+    if hi - lo > NINTHER_THRESHOLD {
+        sort3(bars, lo, mid, hi - 1, sink)?;
+        sort3(bars, lo + 1, mid - 1, hi - 2, sink)?;
+        sort3(bars, lo + 2, mid + 1, hi - 3, sink)?;
+        sort3(bars, mid - 1, mid, mid + 1, sink)?;
+    } else {
+        sort3(bars, lo, mid, hi - 1, sink)?;
+    }
+    swap(bars, lo, mid, sink)?;
+    sink.send(Operation::SetColor(lo, PIVOT_COLOR))
+}
+
+/// Partition `bars[lo..hi]` around the pivot at `lo`, smaller elements
+/// left and the rest right, and put the pivot between them. Returns where
+/// the pivot ended up and whether the range was already partitioned.
+///
+/// The median-of-three pivot guarantees an element no smaller than it on
+/// the right, which stops the first scan.
+fn partition_right(
+    bars: &mut [SortBar],
+    lo: usize,
+    hi: usize,
+    sink: &Sink,
+) -> Result<(usize, bool), Cancelled> {
+    let pivot = bars[lo].value;
+    let less = |bars: &[SortBar], i: usize| -> Result<bool, Cancelled> {
+        sink.send(Operation::Compare(i, lo))?;
+        Ok(bars[i].value < pivot)
+    };
+
+    let mut first = lo + 1;
+    while less(bars, first)? {
+        first += 1;
+    }
+    let mut last = hi;
+    if first == lo + 1 {
+        // Nothing smaller found yet, so the scan from the right needs a guard
+        while first < last {
+            last -= 1;
+            if less(bars, last)? {
+                break;
+            }
+        }
+    } else {
+        last -= 1;
+        while !less(bars, last)? {
+            last -= 1;
+        }
+    }
+
+    let already_partitioned = first >= last;
+    while first < last {
+        swap(bars, first, last, sink)?;
+        first += 1;
+        while less(bars, first)? {
+            first += 1;
+        }
+        last -= 1;
+        while !less(bars, last)? {
+            last -= 1;
+        }
+    }
+
+    let pivot_pos = first - 1;
+    swap(bars, lo, pivot_pos, sink)?;
+    sink.send(Operation::SetColor(lo, Color32::WHITE))?;
+    Ok((pivot_pos, already_partitioned))
+}
+
+/// Partition `bars[lo..hi]` around the pivot at `lo` with elements equal to
+/// it on the left, and return where the pivot ended up. Used when the
+/// pivot is known to be the smallest value in the range.
+fn partition_left(
+    bars: &mut [SortBar],
+    lo: usize,
+    hi: usize,
+    sink: &Sink,
+) -> Result<usize, Cancelled> {
+    let pivot = bars[lo].value;
+    let greater = |bars: &[SortBar], i: usize| -> Result<bool, Cancelled> {
+        sink.send(Operation::Compare(lo, i))?;
+        Ok(pivot < bars[i].value)
+    };
+
+    // The pivot itself stops this scan
+    let mut last = hi - 1;
+    while greater(bars, last)? {
+        last -= 1;
+    }
+    let mut first = lo;
+    if last + 1 == hi {
+        while first < last {
+            first += 1;
+            if greater(bars, first)? {
+                break;
+            }
+        }
+    } else {
+        first += 1;
+        while !greater(bars, first)? {
+            first += 1;
+        }
+    }
+
+    while first < last {
+        swap(bars, first, last, sink)?;
+        last -= 1;
+        while greater(bars, last)? {
+            last -= 1;
+        }
+        first += 1;
+        while !greater(bars, first)? {
+            first += 1;
+        }
+    }
+
+    swap(bars, lo, last, sink)?;
+    sink.send(Operation::SetColor(lo, Color32::WHITE))?;
+    Ok(last)
+}
+
+/// Insertion sort `bars[lo..hi]`, giving up once more than `limit` moves
+/// have been made. Returns whether the range got sorted.
+fn insertion_sort(
+    bars: &mut [SortBar],
+    lo: usize,
+    hi: usize,
+    limit: usize,
+    sink: &Sink,
+) -> Result<bool, Cancelled> {
+    let mut moves = 0;
+    for i in lo + 1..hi {
+        if moves > limit {
+            return Ok(false);
+        }
+        let mut j = i;
+        sink.send(Operation::SetColor(i, Color32::YELLOW))?;
+        while j > lo {
+            sink.send(Operation::Compare(j, j - 1))?;
+            if bars[j].value >= bars[j - 1].value {
+                break;
+            }
+            swap(bars, j, j - 1, sink)?;
+            j -= 1;
+        }
+        moves += i - j;
+        sink.send(Operation::SetColor(j, Color32::WHITE))?;
+    }
+    Ok(true)
+}
+
+/// Heap sort `bars[lo..hi]`, the heap rooted at `lo`.
+fn heap_sort_range(
+    bars: &mut [SortBar],
+    lo: usize,
+    hi: usize,
+    sink: &Sink,
+) -> Result<(), Cancelled> {
+    let n = hi - lo;
+    for root in (0..n / 2).rev() {
+        sift_down(bars, lo, root, n, sink)?;
+    }
+    for end in (1..n).rev() {
+        swap(bars, lo, lo + end, sink)?;
+        sink.send(Operation::SetColor(lo + end, Color32::LIGHT_GREEN))?;
+        sift_down(bars, lo, 0, end, sink)?;
+    }
+    for i in lo..hi {
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
+    }
+    Ok(())
+}
+
+/// Sift node `root` of the heap held in `bars[lo..lo + len]` down into place.
+fn sift_down(
+    bars: &mut [SortBar],
+    lo: usize,
+    mut root: usize,
+    len: usize,
+    sink: &Sink,
+) -> Result<(), Cancelled> {
+    loop {
+        let mut largest = root;
+        for child in [2 * root + 1, 2 * root + 2] {
+            if child < len {
+                sink.send(Operation::Compare(lo + child, lo + largest))?;
+                if bars[lo + child].value > bars[lo + largest].value {
+                    largest = child;
+                }
+            }
+        }
+        if largest == root {
+            return Ok(());
+        }
+        swap(bars, lo + root, lo + largest, sink)?;
+        root = largest;
+    }
+}
@@ -0,0 +1,200 @@
+use super::timsort::{min_run_length, MergeLines, RunMerger};
+use crate::models::SortBar;
+use crate::sorting::{
+    record_trace, CancelToken, Cancelled, Category, Complexity, Operation, Sink, SortAlgorithm,
+};
+use crate::stats::Stats;
+use eframe::egui::Color32;
+use std::ops::Range;
+
+pub struct PowerSort;
+
+impl SortAlgorithm for PowerSort {
+    fn name(&self) -> &'static str {
+        "Powersort"
+    }
+
+    fn category(&self) -> Category {
+        Category::Production
+    }
+
+    fn is_stable(&self) -> bool {
+        true
+    }
+
+    fn complexity(&self) -> Complexity {
+        Complexity {
+            best: "O(n)",
+            average: "O(n log n)",
+            worst: "O(n log n)",
+            space: "O(n)",
+        }
+    }
+
+    fn run(&self, bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+        power_sort(bars, sink)
+    }
+
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "minrun = min_run_length(n)",
+            "while elements remain:",
+            "    find the next natural run B, reversing it if descending",
+            "    if it is shorter than minrun:",
+            "        extend it to minrun with binary insertion sort",
+            "    if a run A is on top of the stack:",
+            "        power = first binary digit in which the midpoints",
+            "                of A and B, as fractions of n, differ",
+            "        while the boundary under A has a higher power:",
+            "            merge the top two runs",
+            "        give the boundary between A and B this power",
+            "    push B on the run stack",
+            "merge what is left on the stack, top down",
+            "",
+            "merge(X, Y):",
+            "    skip the parts of X and Y already in place",
+            "    copy the shorter of X and Y to a buffer",
+            "    merge pair by pair, counting wins in a row",
+            "    after min_gallop wins in a row, gallop:",
+            "        search ahead for how many more the winner takes",
+            "        move them all at once",
+            "    stop galloping once it stops paying off",
+        ]
+    }
+}
+
+// Lines of the listing in `PowerSort::pseudocode`
+const LINE_MINRUN: usize = 1;
+const LINE_POWER: usize = 7;
+const LINE_MERGE_WHILE: usize = 9;
+const LINE_MERGE_TOP: usize = 10;
+const LINE_GIVE_POWER: usize = 11;
+const LINE_FORCE_COLLAPSE: usize = 13;
+const LINES: MergeLines = MergeLines {
+    find_run: 3,
+    extend_run: 5,
+    push_run: 12,
+    trim: 16,
+    copy: 17,
+    pairwise: 18,
+    gallop: 20,
+    gallop_move: 21,
+    leave_gallop: 22,
+};
+
+/// Powersort, the merge policy CPython's `listsort` uses since 3.11: runs
+/// are found and merged exactly as in TimSort, but when to merge is decided
+/// by the power of each boundary between neighbouring runs, which places
+/// the runs in a nearly optimal merge tree.
+///
+/// Every power computed is announced with `Operation::NodePower`.
+pub fn power_sort(bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+    let n = bars.len();
+    if n <= 1 {
+        return Ok(()); // Already sorted.
+    }
+
+    sink.send(Operation::Line(LINE_MINRUN))?;
+    let min_run = min_run_length(n);
+    let mut merger = RunMerger::new(LINES);
+    // Power of the boundary above each pending run; the top one's is not
+    // known until the next run is found
+    let mut powers: Vec<usize> = Vec::new();
+    let mut lo = 0;
+    while lo < n {
+        let run = lo..lo + merger.find_run(bars, lo, min_run, sink)?;
+        if let Some(top) = merger.runs.last().cloned() {
+            sink.send(Operation::Line(LINE_POWER))?;
+            let power = node_power(top.clone(), run.clone(), n);
+            sink.send(Operation::NodePower(top.start, run.start, run.end, power))?;
+
+            sink.send(Operation::Line(LINE_MERGE_WHILE))?;
+            while merger.runs.len() > 1 && powers[merger.runs.len() - 2] > power {
+                sink.send(Operation::Line(LINE_MERGE_TOP))?;
+                merger.merge_at(bars, merger.runs.len() - 2, sink)?;
+                powers.pop();
+                sink.send(Operation::Line(LINE_MERGE_WHILE))?;
+            }
+            sink.send(Operation::Line(LINE_GIVE_POWER))?;
+            *powers.last_mut().expect("a run is pending") = power;
+        }
+        lo = run.end;
+        merger.push(run, sink)?;
+        powers.push(0);
+    }
+
+    sink.send(Operation::Line(LINE_FORCE_COLLAPSE))?;
+    merger.force_collapse(bars, sink)?;
+
+    // Final sweep to confirm completion.
+    for i in 0..n {
+        sink.send(Operation::SetColor(i, Color32::LIGHT_GREEN))?;
+    }
+    for i in 0..n {
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
+    }
+    Ok(())
+}
+
+/// The power of the boundary between neighbouring runs `a` and `b` of an
+/// array of `n` elements: the first binary digit in which the midpoints of
+/// the two runs, as fractions of `n`, differ. It is the depth at which a
+/// perfectly balanced merge tree over `0..n` would split the two midpoints.
+pub fn node_power(a: Range<usize>, b: Range<usize>, n: usize) -> usize {
+    // Twice the midpoints, so they stay whole; compared against n instead
+    // of 2n, one binary digit per loop
+    let mut x = a.start + a.end;
+    let mut y = b.start + b.end;
+    let mut power = 0;
+    loop {
+        power += 1;
+        if x >= n {
+            x -= n;
+            y -= n;
+        } else if y >= n {
+            return power;
+        }
+        x <<= 1;
+        y <<= 1;
+    }
+}
+
+/// How a run-merging sort did on one input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeReport {
+    pub algorithm: &'static str,
+    pub stats: Stats,
+    /// Number of merges of two runs.
+    pub merges: usize,
+    /// Total length of the runs merged, the cost a merge policy minimises.
+    pub merge_cost: usize,
+}
+
+/// Run `algorithm` on a copy of `bars` and count what it did, merges
+/// included, for putting merge policies side by side.
+pub fn merge_report(algorithm: &dyn SortAlgorithm, bars: &[SortBar]) -> MergeReport {
+    let trace =
+        record_trace(algorithm, bars, CancelToken::new()).expect("nothing cancels this run");
+    let mut stats = Stats::default();
+    let mut runs: Vec<usize> = Vec::new();
+    let (mut merges, mut merge_cost) = (0, 0);
+    for op in &trace {
+        stats.record(op);
+        match *op {
+            Operation::PushRun(start, end) => runs.push(end - start),
+            Operation::MergeRuns(i, _) => {
+                let len = runs.remove(i + 1);
+                runs[i] += len;
+                merges += 1;
+                merge_cost += runs[i];
+            }
+            _ => {}
+        }
+    }
+    MergeReport {
+        algorithm: algorithm.name(),
+        stats,
+        merges,
+        merge_cost,
+    }
+}
@@ -1,91 +1,140 @@
 use crate::models::SortBar;
-use crate::sorting::Operation;
+use crate::sorting::{Cancelled, Category, Complexity, Operation, Sink, SortAlgorithm};
 use eframe::egui::Color32;
-use std::sync::mpsc;
-use std::thread;
-use std::time::Duration;
 
-pub fn quick_sort_visual(bars: &mut Vec<SortBar>, tx: &mpsc::Sender<Operation>) {
+pub struct QuickSort;
+
+impl SortAlgorithm for QuickSort {
+    fn name(&self) -> &'static str {
+        "Quick Sort"
+    }
+
+    fn category(&self) -> Category {
+        Category::Production
+    }
+
+    fn is_stable(&self) -> bool {
+        false
+    }
+
+    fn complexity(&self) -> Complexity {
+        Complexity {
+            best: "O(n log n)",
+            average: "O(n log n)",
+            worst: "O(n²)",
+            space: "O(log n)",
+        }
+    }
+
+    fn run(&self, bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+        quick_sort_visual(bars, sink)
+    }
+
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "quicksort(lo, hi):",
+            "    if lo < hi:",
+            "        p = partition(lo, hi)",
+            "        quicksort(lo, p - 1)",
+            "        quicksort(p + 1, hi)",
+            "",
+            "partition(lo, hi):",
+            "    pivot = a[hi]; i = lo",
+            "    for j in lo .. hi:",
+            "        if a[j] < pivot:",
+            "            swap a[i], a[j]; i = i + 1",
+            "    swap a[i], a[hi]",
+            "    return i",
+        ]
+    }
+}
+
+pub fn quick_sort_visual(bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
     if !bars.is_empty() {
-        quick_sort_recursive(bars, 0, bars.len() - 1, tx);
+        quick_sort_recursive(bars, 0, bars.len() - 1, sink)?;
     }
 
     // Reset all colors to white at the end
     for i in 0..bars.len() {
-        let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
     }
+    Ok(())
 }
 
 fn quick_sort_recursive(
-    bars: &mut Vec<SortBar>,
+    bars: &mut [SortBar],
     low: usize,
     high: usize,
-    tx: &mpsc::Sender<Operation>,
-) {
+    sink: &Sink,
+) -> Result<(), Cancelled> {
+    sink.send(Operation::EnterRange(low, high + 1))?;
+    sink.send(Operation::Line(2))?;
     if low < high {
-        let pi = partition_visual(bars, low, high, tx);
+        sink.send(Operation::Line(3))?;
+        let pi = partition_visual(bars, low, high, sink)?;
 
         if pi > 0 {
-            quick_sort_recursive(bars, low, pi - 1, tx);
+            sink.send(Operation::Line(4))?;
+            quick_sort_recursive(bars, low, pi - 1, sink)?;
         }
-        if pi + 1 <= high {
-            quick_sort_recursive(bars, pi + 1, high, tx);
+        if pi < high {
+            sink.send(Operation::Line(5))?;
+            quick_sort_recursive(bars, pi + 1, high, sink)?;
         }
     }
+    sink.send(Operation::ExitRange(low, high + 1))
 }
 
 fn partition_visual(
-    bars: &mut Vec<SortBar>,
+    bars: &mut [SortBar],
     low: usize,
     high: usize,
-    tx: &mpsc::Sender<Operation>,
-) -> usize {
+    sink: &Sink,
+) -> Result<usize, Cancelled> {
+    sink.send(Operation::Line(8))?;
     let pivot_value = bars[high].value;
     let mut i = low;
 
     // Highlight pivot in red
-    let _ = tx.send(Operation::SetColor(high, Color32::RED));
-    thread::sleep(Duration::from_millis(30));
+    sink.send(Operation::SetColor(high, Color32::RED))?;
 
     for j in low..high {
         // Highlight current element being compared in yellow
-        let _ = tx.send(Operation::SetColor(j, Color32::YELLOW));
-        let _ = tx.send(Operation::Compare(j, high));
-        thread::sleep(Duration::from_millis(50));
+        sink.send(Operation::SetColor(j, Color32::YELLOW))?;
+        sink.send(Operation::Line(10))?;
+        sink.send(Operation::Compare(j, high))?;
 
         if bars[j].value < pivot_value {
+            sink.send(Operation::Line(11))?;
             if i != j {
                 // Highlight the element to swap with in green
-                let _ = tx.send(Operation::SetColor(i, Color32::GREEN));
-                thread::sleep(Duration::from_millis(20));
+                sink.send(Operation::SetColor(i, Color32::GREEN))?;
 
-                let _ = tx.send(Operation::Swap(i, j));
+                sink.send(Operation::Swap(i, j))?;
                 bars.swap(i, j);
-                thread::sleep(Duration::from_millis(60));
 
                 // Reset color of swapped element
-                let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
+                sink.send(Operation::SetColor(i, Color32::WHITE))?;
             }
             i += 1;
         }
 
         // Reset color of compared element
-        let _ = tx.send(Operation::SetColor(j, Color32::WHITE));
+        sink.send(Operation::SetColor(j, Color32::WHITE))?;
     }
 
     // Place pivot in correct position
+    sink.send(Operation::Line(12))?;
     if i != high {
-        let _ = tx.send(Operation::SetColor(i, Color32::GREEN));
-        thread::sleep(Duration::from_millis(20));
+        sink.send(Operation::SetColor(i, Color32::GREEN))?;
 
-        let _ = tx.send(Operation::Swap(i, high));
+        sink.send(Operation::Swap(i, high))?;
         bars.swap(i, high);
-        thread::sleep(Duration::from_millis(60));
     }
 
     // Reset colors
-    let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
-    let _ = tx.send(Operation::SetColor(high, Color32::WHITE));
+    sink.send(Operation::SetColor(i, Color32::WHITE))?;
+    sink.send(Operation::SetColor(high, Color32::WHITE))?;
 
-    i
+    Ok(i)
 }
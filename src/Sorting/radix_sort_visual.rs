@@ -1,47 +1,76 @@
 use crate::models::SortBar;
-use crate::sorting::Operation;
+use crate::sorting::{Cancelled, Category, Complexity, Operation, Sink, SortAlgorithm};
 use eframe::egui::Color32;
-use std::sync::mpsc;
-use std::thread;
-use std::time::Duration;
 
-pub fn radix_sort_visual(bars: &mut Vec<SortBar>, tx: &mpsc::Sender<Operation>) {
+pub struct RadixSort;
+
+impl SortAlgorithm for RadixSort {
+    fn name(&self) -> &'static str {
+        "Radix Sort"
+    }
+
+    fn category(&self) -> Category {
+        Category::Specialized
+    }
+
+    fn is_stable(&self) -> bool {
+        true
+    }
+
+    fn complexity(&self) -> Complexity {
+        Complexity {
+            best: "O(d × (n + k))",
+            average: "O(d × (n + k))",
+            worst: "O(d × (n + k))",
+            space: "O(n + k)",
+        }
+    }
+
+    fn run(&self, bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+        radix_sort_visual(bars, sink)
+    }
+
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "max = largest value in a",
+            "for exp = 1, 10, 100, ... while max / exp > 0:",
+            "    count each digit (a[i] / exp) % 10",
+            "    add up the counts into end positions",
+            "    for i in n - 1 down to 0:",
+            "        place a[i] in output by its digit",
+            "    copy output back to a",
+        ]
+    }
+}
+
+pub fn radix_sort_visual(bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
     let n = bars.len();
     if n <= 1 {
-        return;
+        return Ok(());
     }
 
     // Find the maximum value to determine the number of digits
+    sink.send(Operation::Line(1))?;
     let mut max_val = 0;
-    for i in 0..n {
-        let _ = tx.send(Operation::SetColor(i, Color32::LIGHT_BLUE));
-        thread::sleep(Duration::from_millis(20));
+    for (i, bar) in bars.iter().enumerate() {
+        sink.send(Operation::SetColor(i, Color32::LIGHT_BLUE))?;
+        sink.send(Operation::Read(i))?;
 
-        if bars[i].value > max_val {
-            max_val = bars[i].value;
-            let _ = tx.send(Operation::SetColor(i, Color32::RED));
-            thread::sleep(Duration::from_millis(50));
+        if bar.value > max_val {
+            max_val = bar.value;
+            sink.send(Operation::SetColor(i, Color32::RED))?;
         }
 
-        let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
-    }
-
-    // Safety check: prevent excessive iterations with very large max values
-    const MAX_SAFE_VALUE: usize = 100000; // Reasonable limit for radix sort visualization
-    if max_val > MAX_SAFE_VALUE {
-        // Fall back by resetting colors and returning early
-        for i in 0..n {
-            let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
-        }
-        return;
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
     }
 
     // Perform counting sort for every digit (from least significant to most significant)
     let mut exp = 1;
     while max_val / exp > 0 {
+        sink.send(Operation::Line(2))?;
         // Highlight the current digit position being processed
-        for i in 0..n {
-            let digit = (bars[i].value / exp) % 10;
+        for (i, bar) in bars.iter().enumerate() {
+            let digit = (bar.value / exp) % 10;
             let color = match digit {
                 0 => Color32::from_rgb(255, 200, 200),
                 1 => Color32::from_rgb(255, 220, 200),
@@ -54,76 +83,90 @@ pub fn radix_sort_visual(bars: &mut Vec<SortBar>, tx: &mpsc::Sender<Operation>)
                 8 => Color32::from_rgb(200, 200, 255),
                 _ => Color32::from_rgb(220, 200, 255),
             };
-            let _ = tx.send(Operation::SetColor(i, color));
+            sink.send(Operation::SetColor(i, color))?;
         }
-        thread::sleep(Duration::from_millis(200));
 
-        counting_sort_by_digit(bars, exp, tx);
+        counting_sort_by_digit(bars, exp, sink)?;
 
-        // Brief pause between digit positions
-        thread::sleep(Duration::from_millis(100));
-        exp *= 10;
+        // usize has at most 20 digits, so this is the most passes there are
+        match exp.checked_mul(10) {
+            Some(next) => exp = next,
+            None => break,
+        }
     }
 
     // Final sweep to show completion
     for i in 0..n {
-        let _ = tx.send(Operation::SetColor(i, Color32::LIGHT_GREEN));
-        thread::sleep(Duration::from_millis(30));
-        let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
+        sink.send(Operation::SetColor(i, Color32::LIGHT_GREEN))?;
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
     }
+    Ok(())
 }
 
-fn counting_sort_by_digit(bars: &mut Vec<SortBar>, exp: usize, tx: &mpsc::Sender<Operation>) {
+fn counting_sort_by_digit(bars: &mut [SortBar], exp: usize, sink: &Sink) -> Result<(), Cancelled> {
     let n = bars.len();
     let mut output = vec![0; n];
     let mut count = [0; 10]; // Count array for digits 0-9
+    let count_aux = sink.aux("digit counts", count.len())?;
+    let output_aux = sink.aux("output", n)?;
 
     // Count occurrences of each digit
-    for i in 0..n {
-        let digit = (bars[i].value / exp) % 10;
+    for (i, bar) in bars.iter().enumerate() {
+        let digit = (bar.value / exp) % 10;
+        sink.send(Operation::Line(3))?;
         count[digit] += 1;
+        count_aux.write(sink, digit, count[digit])?;
 
         // Highlight the element being counted
-        let _ = tx.send(Operation::SetColor(i, Color32::YELLOW));
-        thread::sleep(Duration::from_millis(30));
+        sink.send(Operation::Read(i))?;
+        sink.send(Operation::SetColor(i, Color32::YELLOW))?;
     }
 
     // Convert count to cumulative count
     for i in 1..10 {
+        sink.send(Operation::Line(4))?;
+        count_aux.read(sink, i - 1)?;
         count[i] += count[i - 1];
+        count_aux.write(sink, i, count[i])?;
     }
 
     // Build output array from right to left to maintain stability
     for i in (0..n).rev() {
         let digit = (bars[i].value / exp) % 10;
-        let _ = tx.send(Operation::SetColor(i, Color32::BLUE));
-        thread::sleep(Duration::from_millis(40));
+        sink.send(Operation::SetColor(i, Color32::BLUE))?;
+        sink.send(Operation::Read(i))?;
 
+        sink.send(Operation::Line(6))?;
         count[digit] -= 1;
         output[count[digit]] = bars[i].value;
+        count_aux.write(sink, digit, count[digit])?;
+        output_aux.write(sink, count[digit], bars[i].value)?;
 
-        let _ = tx.send(Operation::SetColor(i, Color32::GRAY));
-        thread::sleep(Duration::from_millis(20));
+        sink.send(Operation::SetColor(i, Color32::GRAY))?;
     }
 
     // Copy the sorted elements back to original array
     for i in 0..n {
+        sink.send(Operation::Line(7))?;
+        output_aux.read(sink, i)?;
         let old_val = bars[i].value;
         bars[i].value = output[i];
 
         // Show the movement visually
         if old_val != output[i] {
-            let _ = tx.send(Operation::SetColor(i, Color32::GREEN));
-            thread::sleep(Duration::from_millis(60));
+            sink.send(Operation::Overwrite(i, bars[i].clone()))?;
+            sink.send(Operation::SetColor(i, Color32::GREEN))?;
         } else {
-            let _ = tx.send(Operation::SetColor(i, Color32::LIGHT_GRAY));
-            thread::sleep(Duration::from_millis(30));
+            sink.send(Operation::SetColor(i, Color32::LIGHT_GRAY))?;
         }
     }
 
+    count_aux.release(sink)?;
+    output_aux.release(sink)?;
+
     // Reset colors after this digit pass
     for i in 0..n {
-        let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
     }
-    thread::sleep(Duration::from_millis(50));
+    Ok(())
 }
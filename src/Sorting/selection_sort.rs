@@ -1,71 +1,109 @@
 use crate::models::SortBar;
+use crate::sorting::{Cancelled, Category, Complexity, Sink, SortAlgorithm};
 use eframe::egui::Color32;
-use std::sync::mpsc;
-use std::time::Duration;
+
+pub struct SelectionSort;
+
+impl SortAlgorithm for SelectionSort {
+    fn name(&self) -> &'static str {
+        "Selection Sort"
+    }
+
+    fn category(&self) -> Category {
+        Category::Educational
+    }
+
+    fn is_stable(&self) -> bool {
+        false
+    }
+
+    fn complexity(&self) -> Complexity {
+        Complexity {
+            best: "O(n²)",
+            average: "O(n²)",
+            worst: "O(n²)",
+            space: "O(1)",
+        }
+    }
+
+    fn run(&self, bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+        selection_sort(bars, sink)
+    }
+
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "for i in 0 .. n:",
+            "    min = i",
+            "    for j in i + 1 .. n:",
+            "        if a[j] < a[min]:",
+            "            min = j",
+            "    if min != i:",
+            "        swap a[i], a[min]",
+        ]
+    }
+}
 
 /// Enhanced Visual Selection Sort implementation
-pub fn selection_sort(bars: &mut Vec<SortBar>, tx: &mpsc::Sender<crate::sorting::Operation>) {
+pub fn selection_sort(bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
     let n = bars.len();
 
     for i in 0..n {
+        sink.send(crate::sorting::Operation::Line(1))?;
+
         // Show sorted portion (light green) and unsorted portion (gray)
         for k in 0..i {
-            let _ = tx.send(crate::sorting::Operation::SetColor(k, Color32::LIGHT_GREEN));
+            sink.send(crate::sorting::Operation::SetColor(k, Color32::LIGHT_GREEN))?;
         }
         for k in i..n {
-            let _ = tx.send(crate::sorting::Operation::SetColor(k, Color32::GRAY));
+            sink.send(crate::sorting::Operation::SetColor(k, Color32::GRAY))?;
         }
-        std::thread::sleep(Duration::from_millis(100));
 
         // Highlight current position being filled (blue)
-        let _ = tx.send(crate::sorting::Operation::SetColor(i, Color32::BLUE));
-        std::thread::sleep(Duration::from_millis(150));
+        sink.send(crate::sorting::Operation::SetColor(i, Color32::BLUE))?;
 
         let mut min_idx = i;
 
         // Find the minimum element in the remaining unsorted array
         for j in i + 1..n {
             // Highlight current element being examined (yellow)
-            let _ = tx.send(crate::sorting::Operation::SetColor(j, Color32::YELLOW));
+            sink.send(crate::sorting::Operation::SetColor(j, Color32::YELLOW))?;
 
             // Highlight current minimum candidate (red)
-            let _ = tx.send(crate::sorting::Operation::SetColor(min_idx, Color32::RED));
+            sink.send(crate::sorting::Operation::SetColor(min_idx, Color32::RED))?;
 
-            let _ = tx.send(crate::sorting::Operation::Compare(min_idx, j));
-            std::thread::sleep(Duration::from_millis(80));
+            sink.send(crate::sorting::Operation::Line(4))?;
+            sink.send(crate::sorting::Operation::Compare(min_idx, j))?;
 
             if bars[j].value < bars[min_idx].value {
+                sink.send(crate::sorting::Operation::Line(5))?;
                 // Reset previous minimum
-                let _ = tx.send(crate::sorting::Operation::SetColor(min_idx, Color32::GRAY));
+                sink.send(crate::sorting::Operation::SetColor(min_idx, Color32::GRAY))?;
                 // New minimum found
                 min_idx = j;
-                let _ = tx.send(crate::sorting::Operation::SetColor(min_idx, Color32::RED));
-                std::thread::sleep(Duration::from_millis(60));
+                sink.send(crate::sorting::Operation::SetColor(min_idx, Color32::RED))?;
             }
 
             // Reset examined element color
-            let _ = tx.send(crate::sorting::Operation::SetColor(j, Color32::GRAY));
+            sink.send(crate::sorting::Operation::SetColor(j, Color32::GRAY))?;
         }
 
         // Show the final selection
         if min_idx != i {
-            let _ = tx.send(crate::sorting::Operation::SetColor(i, Color32::GREEN));
-            let _ = tx.send(crate::sorting::Operation::SetColor(min_idx, Color32::GREEN));
-            std::thread::sleep(Duration::from_millis(100));
+            sink.send(crate::sorting::Operation::SetColor(i, Color32::GREEN))?;
+            sink.send(crate::sorting::Operation::SetColor(min_idx, Color32::GREEN))?;
 
-            let _ = tx.send(crate::sorting::Operation::Swap(i, min_idx));
+            sink.send(crate::sorting::Operation::Line(7))?;
+            sink.send(crate::sorting::Operation::Swap(i, min_idx))?;
             bars.swap(i, min_idx);
-            std::thread::sleep(Duration::from_millis(120));
         }
 
         // Mark the element as sorted
-        let _ = tx.send(crate::sorting::Operation::SetColor(i, Color32::LIGHT_GREEN));
-        std::thread::sleep(Duration::from_millis(80));
+        sink.send(crate::sorting::Operation::SetColor(i, Color32::LIGHT_GREEN))?;
     }
 
     // Final sweep: mark all elements as sorted (white)
     for i in 0..n {
-        let _ = tx.send(crate::sorting::Operation::SetColor(i, Color32::WHITE));
-        std::thread::sleep(Duration::from_millis(30));
+        sink.send(crate::sorting::Operation::SetColor(i, Color32::WHITE))?;
     }
+    Ok(())
 }
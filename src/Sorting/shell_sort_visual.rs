@@ -1,51 +1,92 @@
 use crate::models::SortBar;
-use crate::sorting::Operation;
+use crate::sorting::{Cancelled, Category, Complexity, Operation, Sink, SortAlgorithm};
 use eframe::egui::Color32;
-use std::sync::mpsc;
-use std::thread;
-use std::time::Duration;
 
-pub fn shell_sort_visual(bars: &mut Vec<SortBar>, tx: &mpsc::Sender<Operation>) {
+pub struct ShellSort;
+
+impl SortAlgorithm for ShellSort {
+    fn name(&self) -> &'static str {
+        "Shell Sort"
+    }
+
+    fn category(&self) -> Category {
+        Category::Specialized
+    }
+
+    fn is_stable(&self) -> bool {
+        false
+    }
+
+    fn complexity(&self) -> Complexity {
+        Complexity {
+            best: "O(n log n)",
+            average: "O(n^1.25)",
+            worst: "O(n²)",
+            space: "O(1)",
+        }
+    }
+
+    fn run(&self, bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+        shell_sort_visual(bars, sink)
+    }
+
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "gap = n / 2",
+            "while gap > 0:",
+            "    for i in gap .. n:",
+            "        x = a[i]; j = i",
+            "        while j >= gap and a[j - gap] > x:",
+            "            a[j] = a[j - gap]; j = j - gap",
+            "        a[j] = x",
+            "    gap = gap / 2",
+        ]
+    }
+}
+
+pub fn shell_sort_visual(bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
     let n = bars.len();
     if n <= 1 {
-        return;
+        return Ok(());
     }
 
     // Start with a big gap, then reduce the gap
+    sink.send(Operation::Line(1))?;
     let mut gap = n / 2;
 
     while gap > 0 {
+        sink.send(Operation::Line(2))?;
         // Highlight the current gap size by showing which elements will be compared
-        show_gap_groups(bars, gap, tx);
+        show_gap_groups(bars, gap, sink)?;
 
         // Do a gapped insertion sort for this gap size
         // The first gap elements bars[0..gap] are already in gapped order
         for i in gap..n {
             // Save bars[i] in temp and make a hole at position i
+            sink.send(Operation::Line(4))?;
             let temp = bars[i].value;
 
             // Highlight the element being inserted
-            let _ = tx.send(Operation::SetColor(i, Color32::RED));
-            thread::sleep(Duration::from_millis(100));
+            sink.send(Operation::SetColor(i, Color32::RED))?;
 
             let mut j = i;
 
             // Shift earlier gap-sorted elements up until the correct location for bars[i] is found
             while j >= gap {
                 // Highlight the elements being compared
-                let _ = tx.send(Operation::SetColor(j - gap, Color32::YELLOW));
-                let _ = tx.send(Operation::SetColor(j, Color32::YELLOW));
-                let _ = tx.send(Operation::Compare(j - gap, j));
-                thread::sleep(Duration::from_millis(80));
+                sink.send(Operation::SetColor(j - gap, Color32::YELLOW))?;
+                sink.send(Operation::SetColor(j, Color32::YELLOW))?;
+                sink.send(Operation::Line(5))?;
+                sink.send(Operation::Compare(j - gap, j))?;
 
                 if bars[j - gap].value > temp {
                     // Show the shift operation
-                    let _ = tx.send(Operation::SetColor(j - gap, Color32::BLUE));
-                    let _ = tx.send(Operation::SetColor(j, Color32::BLUE));
+                    sink.send(Operation::SetColor(j - gap, Color32::BLUE))?;
+                    sink.send(Operation::SetColor(j, Color32::BLUE))?;
 
+                    sink.send(Operation::Line(6))?;
                     bars[j].value = bars[j - gap].value;
-                    let _ = tx.send(Operation::Swap(j - gap, j));
-                    thread::sleep(Duration::from_millis(100));
+                    sink.send(Operation::Swap(j - gap, j))?;
 
                     j -= gap;
                 } else {
@@ -53,41 +94,40 @@ pub fn shell_sort_visual(bars: &mut Vec<SortBar>, tx: &mpsc::Sender<Operation>)
                 }
 
                 // Reset colors after comparison
-                let _ = tx.send(Operation::SetColor(j + gap, Color32::WHITE));
+                sink.send(Operation::SetColor(j + gap, Color32::WHITE))?;
                 if j >= gap {
-                    let _ = tx.send(Operation::SetColor(j - gap, Color32::WHITE));
+                    sink.send(Operation::SetColor(j - gap, Color32::WHITE))?;
                 }
             }
 
             // Put temp (the original bars[i]) in its correct location
+            sink.send(Operation::Line(7))?;
             bars[j].value = temp;
-            let _ = tx.send(Operation::SetColor(j, Color32::GREEN));
-            thread::sleep(Duration::from_millis(60));
+            sink.send(Operation::SetColor(j, Color32::GREEN))?;
 
             // Reset the inserted element color
-            let _ = tx.send(Operation::SetColor(j, Color32::WHITE));
+            sink.send(Operation::SetColor(j, Color32::WHITE))?;
         }
 
         // Show completion of this gap phase
         for i in 0..n {
-            let _ = tx.send(Operation::SetColor(i, Color32::LIGHT_GREEN));
-            thread::sleep(Duration::from_millis(20));
-            let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
+            sink.send(Operation::SetColor(i, Color32::LIGHT_GREEN))?;
+            sink.send(Operation::SetColor(i, Color32::WHITE))?;
         }
 
-        thread::sleep(Duration::from_millis(200));
+        sink.send(Operation::Line(8))?;
         gap /= 2;
     }
 
     // Final sweep to show completion
     for i in 0..n {
-        let _ = tx.send(Operation::SetColor(i, Color32::LIGHT_GREEN));
-        thread::sleep(Duration::from_millis(40));
-        let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
+        sink.send(Operation::SetColor(i, Color32::LIGHT_GREEN))?;
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
     }
+    Ok(())
 }
 
-fn show_gap_groups(bars: &Vec<SortBar>, gap: usize, tx: &mpsc::Sender<Operation>) {
+fn show_gap_groups(bars: &[SortBar], gap: usize, sink: &Sink) -> Result<(), Cancelled> {
     let n = bars.len();
 
     // Color different gap groups with different colors
@@ -106,13 +146,12 @@ fn show_gap_groups(bars: &Vec<SortBar>, gap: usize, tx: &mpsc::Sender<Operation>
     for i in 0..n {
         let group = i % gap;
         let color = colors[group % colors.len()];
-        let _ = tx.send(Operation::SetColor(i, color));
+        sink.send(Operation::SetColor(i, color))?;
     }
 
-    thread::sleep(Duration::from_millis(300));
-
     // Reset all colors
     for i in 0..n {
-        let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
     }
+    Ok(())
 }
@@ -1,38 +1,118 @@
 use crate::models::SortBar;
-use crate::sorting::Operation;
+use crate::sorting::{Cancelled, NetworkLayer, Operation, Sink};
 use eframe::egui::Color32;
-use std::sync::mpsc;
-use std::time::Duration;
 
-/// Visual insertion sort for SortBar with range support
-pub fn insertion_sort_range_visual(
-    bars: &mut Vec<SortBar>,
+/// Length of the natural run starting at `lo` and ending before `hi`: the
+/// longest non-descending prefix, or the longest strictly descending one,
+/// which is reversed in place so every run comes back ascending. Reversing
+/// only strictly descending runs keeps the sort stable.
+pub fn count_run_and_make_ascending(
+    bars: &mut [SortBar],
+    lo: usize,
+    hi: usize,
+    sink: &Sink,
+) -> Result<usize, Cancelled> {
+    let mut run_hi = lo + 1;
+    if run_hi == hi {
+        return Ok(1);
+    }
+
+    sink.send(Operation::SetColor(lo, Color32::LIGHT_BLUE))?;
+    sink.send(Operation::Compare(run_hi, lo))?;
+    let descending = bars[run_hi].value < bars[lo].value;
+    sink.send(Operation::SetColor(run_hi, Color32::LIGHT_BLUE))?;
+    run_hi += 1;
+    while run_hi < hi {
+        sink.send(Operation::Compare(run_hi, run_hi - 1))?;
+        let continues = if descending {
+            bars[run_hi].value < bars[run_hi - 1].value
+        } else {
+            bars[run_hi].value >= bars[run_hi - 1].value
+        };
+        if !continues {
+            break;
+        }
+        sink.send(Operation::SetColor(run_hi, Color32::LIGHT_BLUE))?;
+        run_hi += 1;
+    }
+
+    if descending {
+        let (mut i, mut j) = (lo, run_hi - 1);
+        while i < j {
+            sink.send(Operation::Swap(i, j))?;
+            bars.swap(i, j);
+            i += 1;
+            j -= 1;
+        }
+    }
+    for i in lo..run_hi {
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
+    }
+    Ok(run_hi - lo)
+}
+
+/// Sort `bars[lo..hi]` by binary insertion, given that `bars[lo..start]` is
+/// already sorted. Elements equal to the one being inserted stay in front
+/// of it, so the sort is stable.
+pub fn binary_insertion_sort_visual(
+    bars: &mut [SortBar],
+    lo: usize,
+    hi: usize,
     start: usize,
-    end: usize,
-    tx: &mpsc::Sender<Operation>,
-) {
-    for i in (start + 1)..end {
-        let mut j = i;
-
-        // Highlight the element being inserted
-        let _ = tx.send(Operation::SetColor(i, Color32::LIGHT_BLUE));
-        std::thread::sleep(Duration::from_millis(50));
-
-        while j > start {
-            let _ = tx.send(Operation::Compare(j - 1, j));
-            std::thread::sleep(Duration::from_millis(30));
-
-            if bars[j - 1].value > bars[j].value {
-                let _ = tx.send(Operation::Swap(j - 1, j));
-                bars.swap(j - 1, j);
-                std::thread::sleep(Duration::from_millis(40));
-                j -= 1;
+    sink: &Sink,
+) -> Result<(), Cancelled> {
+    for i in start.max(lo + 1)..hi {
+        let pivot = bars[i].clone();
+        sink.send(Operation::SetColor(i, Color32::RED))?;
+
+        // First position in lo..i holding something greater than the pivot
+        let (mut left, mut right) = (lo, i);
+        while left < right {
+            let mid = left + (right - left) / 2;
+            sink.send(Operation::Compare(mid, i))?;
+            if pivot.value < bars[mid].value {
+                right = mid;
             } else {
-                break;
+                left = mid + 1;
             }
         }
 
-        // Reset color
-        let _ = tx.send(Operation::SetColor(j, Color32::WHITE));
+        // Shift the greater elements up one and drop the pivot in the gap
+        sink.send(Operation::Read(i))?;
+        for j in (left..i).rev() {
+            bars[j + 1] = bars[j].clone();
+            sink.send(Operation::Overwrite(j + 1, bars[j + 1].clone()))?;
+        }
+        bars[left] = pivot;
+        sink.send(Operation::Overwrite(left, bars[left].clone()))?;
+        sink.send(Operation::SetColor(left, Color32::WHITE))?;
+    }
+    Ok(())
+}
+
+/// Run a sorting network over `bars`, one layer after another, each
+/// comparator `(i, j)` swapping the two elements if `bars[i]` is the
+/// greater. Which elements get compared never depends on their values.
+pub fn run_network(
+    bars: &mut [SortBar],
+    network: &[NetworkLayer],
+    sink: &Sink,
+) -> Result<(), Cancelled> {
+    for (k, layer) in network.iter().enumerate() {
+        sink.send(Operation::Layer(k))?;
+        sink.send(Operation::Line(layer.line))?;
+        for &(i, j) in &layer.comparators {
+            sink.send(Operation::Compare(i, j))?;
+            if bars[i].value > bars[j].value {
+                sink.send(Operation::Swap(i, j))?;
+                bars.swap(i, j);
+            }
+        }
+    }
+
+    // Reset all colors to white at the end
+    for i in 0..bars.len() {
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
     }
+    Ok(())
 }
@@ -1,120 +1,668 @@
-use super::sort_utils::insertion_sort_range_visual;
+use super::sort_utils::{binary_insertion_sort_visual, count_run_and_make_ascending};
 use crate::models::SortBar;
-use crate::sorting::Operation;
+use crate::sorting::{Cancelled, Category, Complexity, Operation, Sink, SortAlgorithm};
 use eframe::egui::Color32;
-use std::sync::mpsc;
-use std::thread;
-use std::time::Duration;
+use std::ops::Range;
 
-// A fixed run size simplifies the logic and is more reliable for visualization.
-const RUN_SIZE: usize = 32;
+pub struct TimSort;
 
-/// A corrected and robust hybrid merge sort inspired by Timsort's principles.
-pub fn tim_sort(bars: &mut Vec<SortBar>, tx: &mpsc::Sender<Operation>) {
-    let n = bars.len();
-    if n <= 1 {
-        return; // Already sorted.
+impl SortAlgorithm for TimSort {
+    fn name(&self) -> &'static str {
+        "Tim Sort"
     }
 
-    // Step 1: Sort individual chunks (runs) of the array using Insertion Sort.
-    for i in (0..n).step_by(RUN_SIZE) {
-        let end = std::cmp::min(i + RUN_SIZE, n);
-        insertion_sort_range_visual(bars, i, end, tx);
+    fn category(&self) -> Category {
+        Category::Production
     }
 
-    // Short pause to visually show the initial sorted runs.
-    thread::sleep(Duration::from_millis(250));
+    fn is_stable(&self) -> bool {
+        true
+    }
 
-    // Step 2: Iteratively merge the sorted runs in a bottom-up fashion.
-    let mut size = RUN_SIZE;
-    while size < n {
-        for left_start in (0..n).step_by(2 * size) {
-            let mid = std::cmp::min(left_start + size, n);
-            let right_end = std::cmp::min(left_start + 2 * size, n);
+    fn complexity(&self) -> Complexity {
+        Complexity {
+            best: "O(n)",
+            average: "O(n log n)",
+            worst: "O(n log n)",
+            space: "O(n)",
+        }
+    }
 
-            if mid < right_end {
-                merge_visual(bars, left_start, mid, right_end, tx);
-            }
+    fn run(&self, bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+        tim_sort(bars, sink)
+    }
+
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "minrun = min_run_length(n)",
+            "while elements remain:",
+            "    find the next natural run, reversing it if descending",
+            "    if it is shorter than minrun:",
+            "        extend it to minrun with binary insertion sort",
+            "    push it on the run stack",
+            "    while the top runs A, B, C (C on top) have",
+            "          |A| <= |B| + |C|  or  |B| <= |C|:",
+            "        merge B with the smaller of A and C",
+            "merge what is left on the stack, top down",
+            "",
+            "merge(X, Y):",
+            "    skip the parts of X and Y already in place",
+            "    copy the shorter of X and Y to a buffer",
+            "    merge pair by pair, counting wins in a row",
+            "    after min_gallop wins in a row, gallop:",
+            "        search ahead for how many more the winner takes",
+            "        move them all at once",
+            "    stop galloping once it stops paying off",
+        ]
+    }
+}
+
+/// Arrays shorter than this are sorted by binary insertion alone.
+const MIN_MERGE: usize = 64;
+
+/// Wins in a row before a merge switches to galloping.
+const MIN_GALLOP: usize = 7;
+
+/// Colour of the blocks a gallop moves in one go.
+const GALLOP_COLOR: Color32 = Color32::from_rgb(200, 80, 255);
+
+// Lines of the listing in `TimSort::pseudocode`
+const LINE_MINRUN: usize = 1;
+const LINE_MERGE_COLLAPSE: usize = 9;
+const LINE_FORCE_COLLAPSE: usize = 10;
+const LINES: MergeLines = MergeLines {
+    find_run: 3,
+    extend_run: 5,
+    push_run: 6,
+    trim: 13,
+    copy: 14,
+    pairwise: 15,
+    gallop: 17,
+    gallop_move: 18,
+    leave_gallop: 19,
+};
+
+/// TimSort as in CPython's `listsort`: natural runs extended to `minrun`
+/// with binary insertion sort, a stack of pending runs kept balanced by
+/// merging, and merges that switch to galloping when one run keeps winning.
+///
+/// Besides the usual operations it emits `PushRun`/`MergeRuns` for the run
+/// stack and marks gallops on the pseudocode listing.
+pub fn tim_sort(bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+    let n = bars.len();
+    if n <= 1 {
+        return Ok(()); // Already sorted.
+    }
+
+    sink.send(Operation::Line(LINE_MINRUN))?;
+    let min_run = min_run_length(n);
+    let mut merger = RunMerger::new(LINES);
+    let mut lo = 0;
+    while lo < n {
+        let len = merger.find_run(bars, lo, min_run, sink)?;
+        merger.push(lo..lo + len, sink)?;
+
+        sink.send(Operation::Line(LINE_MERGE_COLLAPSE))?;
+        while let Some(i) = collapse_index(&merger.runs) {
+            sink.send(Operation::Line(LINE_MERGE_COLLAPSE))?;
+            merger.merge_at(bars, i, sink)?;
         }
-        size *= 2;
+        lo += len;
     }
 
+    sink.send(Operation::Line(LINE_FORCE_COLLAPSE))?;
+    merger.force_collapse(bars, sink)?;
+
     // Final sweep to confirm completion.
     for i in 0..n {
-        let _ = tx.send(Operation::SetColor(i, Color32::LIGHT_GREEN));
-        thread::sleep(Duration::from_millis(5));
+        sink.send(Operation::SetColor(i, Color32::LIGHT_GREEN))?;
+    }
+    for i in 0..n {
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
     }
+    Ok(())
 }
 
-/// Merges two adjacent sorted subarrays: `bars[start..mid)` and `bars[mid..end)`.
-fn merge_visual(
-    bars: &mut Vec<SortBar>,
-    start: usize,
-    mid: usize,
-    end: usize,
-    tx: &mpsc::Sender<Operation>,
-) {
-    let left_len = mid - start;
-    let right_len = end - mid;
-
-    // Highlight the two ranges being merged.
-    for x in start..mid {
-        let _ = tx.send(Operation::SetColor(x, Color32::from_rgb(100, 100, 255)));
-    }
-    for x in mid..end {
-        let _ = tx.send(Operation::SetColor(x, Color32::from_rgb(255, 100, 100)));
-    }
-    thread::sleep(Duration::from_millis(150));
-
-    let temp = bars[start..end].to_vec();
-    let (left, right) = temp.split_at(left_len);
-
-    let mut i = 0;
-    let mut j = 0;
-    let mut k = start;
-
-    while i < left_len && j < right_len {
-        let _ = tx.send(Operation::Compare(start + i, mid + j));
-        thread::sleep(Duration::from_millis(10));
-
-        if left[i].value <= right[j].value {
-            // THE FIX: Update the local `bars` vector AND send the message.
-            bars[k] = left[i].clone();
-            let _ = tx.send(Operation::Overwrite(k, left[i].clone()));
-            i += 1;
+/// The shortest run worth merging for an array of `n` elements: `n` itself
+/// below `MIN_MERGE`, otherwise a number in `MIN_MERGE / 2..=MIN_MERGE` such
+/// that `n / minrun` is a power of two or just under one, so the final
+/// merges stay balanced.
+pub(crate) fn min_run_length(mut n: usize) -> usize {
+    let mut r = 0;
+    while n >= MIN_MERGE {
+        r |= n & 1;
+        n >>= 1;
+    }
+    n + r
+}
+
+/// Which pair of pending runs to merge next to restore TimSort's
+/// invariants, or `None` if they hold. `runs[i]` merges with `runs[i + 1]`.
+///
+/// For the top runs A, B, C (C on top) the invariants are |A| > |B| + |C|
+/// and |B| > |C|; the check also looks one run deeper, which closes the hole
+/// found in the original formulation.
+fn collapse_index(runs: &[Range<usize>]) -> Option<usize> {
+    if runs.len() < 2 {
+        return None;
+    }
+    let len = |i: usize| runs[i].len();
+    let n = runs.len() - 2;
+    if (n > 0 && len(n - 1) <= len(n) + len(n + 1)) || (n > 1 && len(n - 2) <= len(n - 1) + len(n))
+    {
+        if n > 0 && len(n - 1) < len(n + 1) {
+            Some(n - 1)
         } else {
-            // THE FIX: Update the local `bars` vector AND send the message.
-            bars[k] = right[j].clone();
-            let _ = tx.send(Operation::Overwrite(k, right[j].clone()));
-            j += 1;
+            Some(n)
         }
-        let _ = tx.send(Operation::SetColor(k, Color32::GREEN));
-        thread::sleep(Duration::from_millis(10));
-        k += 1;
+    } else if len(n) <= len(n + 1) {
+        Some(n)
+    } else {
+        None
     }
+}
 
-    // Copy remaining elements from the left side.
-    while i < left_len {
-        bars[k] = left[i].clone();
-        let _ = tx.send(Operation::Overwrite(k, left[i].clone()));
-        let _ = tx.send(Operation::SetColor(k, Color32::LIGHT_BLUE));
-        thread::sleep(Duration::from_millis(5));
-        i += 1;
-        k += 1;
+/// Where the steps `RunMerger` takes sit in a sort's pseudocode listing.
+pub(crate) struct MergeLines {
+    pub find_run: usize,
+    pub extend_run: usize,
+    pub push_run: usize,
+    pub trim: usize,
+    pub copy: usize,
+    pub pairwise: usize,
+    pub gallop: usize,
+    pub gallop_move: usize,
+    pub leave_gallop: usize,
+}
+
+/// The stack of pending runs and the merge state shared by every merge of
+/// one sort. TimSort and Powersort differ only in when they call `merge_at`.
+pub(crate) struct RunMerger {
+    pub runs: Vec<Range<usize>>,
+    lines: MergeLines,
+    // Wins in a row before galloping; adapts to how well galloping pays off
+    min_gallop: usize,
+}
+
+impl RunMerger {
+    pub fn new(lines: MergeLines) -> Self {
+        Self {
+            runs: Vec::new(),
+            lines,
+            min_gallop: MIN_GALLOP,
+        }
     }
 
-    // Copy remaining elements from the right side.
-    while j < right_len {
-        bars[k] = right[j].clone();
-        let _ = tx.send(Operation::Overwrite(k, right[j].clone()));
-        let _ = tx.send(Operation::SetColor(k, Color32::LIGHT_RED));
-        thread::sleep(Duration::from_millis(5));
-        j += 1;
-        k += 1;
+    /// Find the natural run at `lo`, extend it with binary insertion sort if
+    /// it is shorter than `min_run`, and return its length.
+    pub fn find_run(
+        &self,
+        bars: &mut [SortBar],
+        lo: usize,
+        min_run: usize,
+        sink: &Sink,
+    ) -> Result<usize, Cancelled> {
+        let n = bars.len();
+        sink.send(Operation::Line(self.lines.find_run))?;
+        let mut len = count_run_and_make_ascending(bars, lo, n, sink)?;
+        if len < min_run {
+            sink.send(Operation::Line(self.lines.extend_run))?;
+            let forced = min_run.min(n - lo);
+            binary_insertion_sort_visual(bars, lo, lo + forced, lo + len, sink)?;
+            len = forced;
+        }
+        Ok(len)
     }
 
-    // Reset colors for the newly merged section.
-    for x in start..end {
-        let _ = tx.send(Operation::SetColor(x, Color32::WHITE));
+    /// Push a sorted run onto the stack.
+    pub fn push(&mut self, run: Range<usize>, sink: &Sink) -> Result<(), Cancelled> {
+        sink.send(Operation::Line(self.lines.push_run))?;
+        sink.send(Operation::PushRun(run.start, run.end))?;
+        self.runs.push(run);
+        Ok(())
+    }
+
+    /// Merge the runs left at the end of a sort, top down, always merging
+    /// the middle of the top three runs with the shorter of its neighbours.
+    pub fn force_collapse(&mut self, bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+        while self.runs.len() > 1 {
+            let mut i = self.runs.len() - 2;
+            if i > 0 && self.runs[i - 1].len() < self.runs[i + 1].len() {
+                i -= 1;
+            }
+            self.merge_at(bars, i, sink)?;
+        }
+        Ok(())
+    }
+
+    /// Merge `runs[i]` with `runs[i + 1]`.
+    pub fn merge_at(
+        &mut self,
+        bars: &mut [SortBar],
+        i: usize,
+        sink: &Sink,
+    ) -> Result<(), Cancelled> {
+        let (a, b) = (self.runs[i].clone(), self.runs[i + 1].clone());
+        sink.send(Operation::MergeRuns(i, b.start))?;
+        self.runs[i] = a.start..b.end;
+        self.runs.remove(i + 1);
+
+        for x in a.clone() {
+            sink.send(Operation::SetColor(x, Color32::from_rgb(100, 100, 255)))?;
+        }
+        for x in b.clone() {
+            sink.send(Operation::SetColor(x, Color32::from_rgb(255, 100, 100)))?;
+        }
+
+        // Elements of A no greater than B's first, and elements of B no
+        // smaller than A's last, are already where they belong
+        sink.send(Operation::Line(self.lines.trim))?;
+        let first_b = bars[b.start].value;
+        let skip = gallop_right(
+            first_b,
+            b.start,
+            a.len(),
+            0,
+            |k| bars[a.start + k].value,
+            a.start,
+            sink,
+        )?;
+        let a = a.start + skip..a.end;
+        if !a.is_empty() {
+            let last_a = bars[a.end - 1].value;
+            let keep = gallop_left(
+                last_a,
+                a.end - 1,
+                b.len(),
+                b.len() - 1,
+                |k| bars[b.start + k].value,
+                b.start,
+                sink,
+            )?;
+            let b = b.start..b.start + keep;
+            if !b.is_empty() {
+                if a.len() <= b.len() {
+                    self.merge_lo(bars, a, b, sink)?;
+                } else {
+                    self.merge_hi(bars, a, b, sink)?;
+                }
+            }
+        }
+
+        for x in self.runs[i].clone() {
+            sink.send(Operation::SetColor(x, Color32::WHITE))?;
+        }
+        Ok(())
+    }
+
+    /// Merge adjacent runs `a` and `b` with `a` the shorter, copying `a`
+    /// aside and filling from the left.
+    fn merge_lo(
+        &mut self,
+        bars: &mut [SortBar],
+        a: Range<usize>,
+        b: Range<usize>,
+        sink: &Sink,
+    ) -> Result<(), Cancelled> {
+        sink.send(Operation::Line(self.lines.copy))?;
+        let tmp: Vec<SortBar> = bars[a.clone()].to_vec();
+        let buffer = sink.aux("merge buffer", tmp.len())?;
+        for (k, bar) in tmp.iter().enumerate() {
+            sink.send(Operation::Read(a.start + k))?;
+            buffer.write(sink, k, bar.value)?;
+        }
+
+        // The rest of `tmp` belongs in the gap between `dest` and `j`
+        let (mut i, mut j, mut dest) = (0, b.start, a.start);
+        'merge: while i < tmp.len() && j < b.end {
+            sink.send(Operation::Line(self.lines.pairwise))?;
+            let (mut wins_a, mut wins_b) = (0, 0);
+            loop {
+                sink.send(Operation::Compare(dest, j))?;
+                if bars[j].value < tmp[i].value {
+                    move_bar(bars, j, dest, Color32::LIGHT_YELLOW, sink)?;
+                    j += 1;
+                    wins_b += 1;
+                    wins_a = 0;
+                } else {
+                    buffer.read(sink, i)?;
+                    place(bars, dest, tmp[i].clone(), Color32::LIGHT_GREEN, sink)?;
+                    i += 1;
+                    wins_a += 1;
+                    wins_b = 0;
+                }
+                dest += 1;
+                if i == tmp.len() || j == b.end {
+                    break 'merge;
+                }
+                if wins_a.max(wins_b) >= self.min_gallop {
+                    break;
+                }
+            }
+
+            // Every round of galloping makes the next one easier to enter
+            self.min_gallop += 1;
+            loop {
+                self.min_gallop = (self.min_gallop - 1).max(1);
+                sink.send(Operation::Line(self.lines.gallop))?;
+                let key = bars[j].value;
+                let run = tmp.len() - i;
+                wins_a = gallop_right(key, j, run, 0, |k| tmp[i + k].value, dest, sink)?;
+                if wins_a > 0 {
+                    sink.send(Operation::Line(self.lines.gallop_move))?;
+                    for _ in 0..wins_a {
+                        buffer.read(sink, i)?;
+                        place(bars, dest, tmp[i].clone(), GALLOP_COLOR, sink)?;
+                        i += 1;
+                        dest += 1;
+                    }
+                    if i == tmp.len() {
+                        break 'merge;
+                    }
+                }
+                move_bar(bars, j, dest, Color32::LIGHT_YELLOW, sink)?;
+                j += 1;
+                dest += 1;
+                if j == b.end {
+                    break 'merge;
+                }
+
+                sink.send(Operation::Line(self.lines.gallop))?;
+                let key = tmp[i].value;
+                wins_b = gallop_left(key, dest, b.end - j, 0, |k| bars[j + k].value, j, sink)?;
+                if wins_b > 0 {
+                    sink.send(Operation::Line(self.lines.gallop_move))?;
+                    for _ in 0..wins_b {
+                        move_bar(bars, j, dest, GALLOP_COLOR, sink)?;
+                        j += 1;
+                        dest += 1;
+                    }
+                    if j == b.end {
+                        break 'merge;
+                    }
+                }
+                buffer.read(sink, i)?;
+                place(bars, dest, tmp[i].clone(), Color32::LIGHT_GREEN, sink)?;
+                i += 1;
+                dest += 1;
+                if i == tmp.len() {
+                    break 'merge;
+                }
+
+                if wins_a < MIN_GALLOP && wins_b < MIN_GALLOP {
+                    break;
+                }
+            }
+            // Galloping stopped paying off; make it harder to get back into
+            sink.send(Operation::Line(self.lines.leave_gallop))?;
+            self.min_gallop += 1;
+        }
+
+        // Whatever is left of `tmp` fills the gap; leftovers of `b` are
+        // already in place
+        while i < tmp.len() {
+            buffer.read(sink, i)?;
+            place(bars, dest, tmp[i].clone(), Color32::LIGHT_GREEN, sink)?;
+            i += 1;
+            dest += 1;
+        }
+        buffer.release(sink)
+    }
+
+    /// Merge adjacent runs `a` and `b` with `b` the shorter, copying `b`
+    /// aside and filling from the right.
+    fn merge_hi(
+        &mut self,
+        bars: &mut [SortBar],
+        a: Range<usize>,
+        b: Range<usize>,
+        sink: &Sink,
+    ) -> Result<(), Cancelled> {
+        sink.send(Operation::Line(self.lines.copy))?;
+        let tmp: Vec<SortBar> = bars[b.clone()].to_vec();
+        let buffer = sink.aux("merge buffer", tmp.len())?;
+        for (k, bar) in tmp.iter().enumerate() {
+            sink.send(Operation::Read(b.start + k))?;
+            buffer.write(sink, k, bar.value)?;
+        }
+
+        // `tmp[..j]` and `bars[a.start..i]` are left to merge, into the
+        // slots before `dest`; the rest of `tmp` belongs just before `dest`
+        let (mut i, mut j, mut dest) = (a.end, tmp.len(), b.end);
+        'merge: while i > a.start && j > 0 {
+            sink.send(Operation::Line(self.lines.pairwise))?;
+            let (mut wins_a, mut wins_b) = (0, 0);
+            loop {
+                sink.send(Operation::Compare(i - 1, dest - 1))?;
+                if tmp[j - 1].value < bars[i - 1].value {
+                    move_bar(bars, i - 1, dest - 1, Color32::LIGHT_GREEN, sink)?;
+                    i -= 1;
+                    wins_a += 1;
+                    wins_b = 0;
+                } else {
+                    buffer.read(sink, j - 1)?;
+                    place(
+                        bars,
+                        dest - 1,
+                        tmp[j - 1].clone(),
+                        Color32::LIGHT_YELLOW,
+                        sink,
+                    )?;
+                    j -= 1;
+                    wins_b += 1;
+                    wins_a = 0;
+                }
+                dest -= 1;
+                if i == a.start || j == 0 {
+                    break 'merge;
+                }
+                if wins_a.max(wins_b) >= self.min_gallop {
+                    break;
+                }
+            }
+
+            self.min_gallop += 1;
+            loop {
+                self.min_gallop = (self.min_gallop - 1).max(1);
+                // Elements of A greater than B's last remaining one go last
+                sink.send(Operation::Line(self.lines.gallop))?;
+                let key = tmp[j - 1].value;
+                let run = i - a.start;
+                let stay = gallop_right(
+                    key,
+                    dest - 1,
+                    run,
+                    run - 1,
+                    |k| bars[a.start + k].value,
+                    a.start,
+                    sink,
+                )?;
+                wins_a = run - stay;
+                if wins_a > 0 {
+                    sink.send(Operation::Line(self.lines.gallop_move))?;
+                    for _ in 0..wins_a {
+                        move_bar(bars, i - 1, dest - 1, GALLOP_COLOR, sink)?;
+                        i -= 1;
+                        dest -= 1;
+                    }
+                    if i == a.start {
+                        break 'merge;
+                    }
+                }
+                buffer.read(sink, j - 1)?;
+                place(
+                    bars,
+                    dest - 1,
+                    tmp[j - 1].clone(),
+                    Color32::LIGHT_YELLOW,
+                    sink,
+                )?;
+                j -= 1;
+                dest -= 1;
+                if j == 0 {
+                    break 'merge;
+                }
+
+                // Elements of B no smaller than A's last remaining one go
+                // next; remaining B sits just before `dest`
+                sink.send(Operation::Line(self.lines.gallop))?;
+                let key = bars[i - 1].value;
+                let stay = gallop_left(key, i - 1, j, j - 1, |k| tmp[k].value, dest - j, sink)?;
+                wins_b = j - stay;
+                if wins_b > 0 {
+                    sink.send(Operation::Line(self.lines.gallop_move))?;
+                    for _ in 0..wins_b {
+                        buffer.read(sink, j - 1)?;
+                        place(bars, dest - 1, tmp[j - 1].clone(), GALLOP_COLOR, sink)?;
+                        j -= 1;
+                        dest -= 1;
+                    }
+                    if j == 0 {
+                        break 'merge;
+                    }
+                }
+                move_bar(bars, i - 1, dest - 1, Color32::LIGHT_GREEN, sink)?;
+                i -= 1;
+                dest -= 1;
+                if i == a.start {
+                    break 'merge;
+                }
+
+                if wins_a < MIN_GALLOP && wins_b < MIN_GALLOP {
+                    break;
+                }
+            }
+            sink.send(Operation::Line(self.lines.leave_gallop))?;
+            self.min_gallop += 1;
+        }
+
+        // What is left of `tmp` goes in front; leftovers of `a` are already
+        // in place
+        while j > 0 {
+            buffer.read(sink, j - 1)?;
+            place(
+                bars,
+                dest - 1,
+                tmp[j - 1].clone(),
+                Color32::LIGHT_YELLOW,
+                sink,
+            )?;
+            j -= 1;
+            dest -= 1;
+        }
+        buffer.release(sink)
+    }
+}
+
+/// Put `bar` at `dest` and mark it with `color`.
+fn place(
+    bars: &mut [SortBar],
+    dest: usize,
+    bar: SortBar,
+    color: Color32,
+    sink: &Sink,
+) -> Result<(), Cancelled> {
+    bars[dest] = bar;
+    sink.send(Operation::Overwrite(dest, bars[dest].clone()))?;
+    sink.send(Operation::SetColor(dest, color))
+}
+
+/// Copy the bar at `from` to `dest` and mark it with `color`.
+fn move_bar(
+    bars: &mut [SortBar],
+    from: usize,
+    dest: usize,
+    color: Color32,
+    sink: &Sink,
+) -> Result<(), Cancelled> {
+    let bar = bars[from].clone();
+    place(bars, dest, bar, color, sink)
+}
+
+/// Where `key` goes in the sorted sequence `value(0..len)`, before any
+/// equal elements: the number of elements less than `key`.
+///
+/// The search starts at `hint` and probes 1, 3, 7, ... elements away before
+/// a binary search, so it is fast when the answer is near the hint. Each
+/// probe is emitted as a comparison between element `offset + k` and
+/// `key_at`, the slot the key is shown in.
+fn gallop_left(
+    key: usize,
+    key_at: usize,
+    len: usize,
+    hint: usize,
+    value: impl Fn(usize) -> usize,
+    offset: usize,
+    sink: &Sink,
+) -> Result<usize, Cancelled> {
+    gallop(key, key_at, len, hint, value, offset, sink, |key, v| {
+        key > v
+    })
+}
+
+/// Where `key` goes in the sorted sequence `value(0..len)`, after any equal
+/// elements: the number of elements no greater than `key`. See
+/// `gallop_left` for how it searches.
+fn gallop_right(
+    key: usize,
+    key_at: usize,
+    len: usize,
+    hint: usize,
+    value: impl Fn(usize) -> usize,
+    offset: usize,
+    sink: &Sink,
+) -> Result<usize, Cancelled> {
+    gallop(key, key_at, len, hint, value, offset, sink, |key, v| {
+        key >= v
+    })
+}
+
+/// Exponential then binary search for the number of leading elements `v`
+/// with `goes_after(key, v)`, which holds for a prefix of the sequence.
+#[allow(clippy::too_many_arguments)]
+fn gallop(
+    key: usize,
+    key_at: usize,
+    len: usize,
+    hint: usize,
+    value: impl Fn(usize) -> usize,
+    offset: usize,
+    sink: &Sink,
+    goes_after: impl Fn(usize, usize) -> bool,
+) -> Result<usize, Cancelled> {
+    let after = |k: usize| -> Result<bool, Cancelled> {
+        sink.send(Operation::Compare(offset + k, key_at))?;
+        Ok(goes_after(key, value(k)))
+    };
+
+    // Bracket the answer in lo..=hi, then binary search it
+    let (mut lo, mut hi);
+    if after(hint)? {
+        // Gallop right from the hint
+        let (mut last, mut ofs) = (0, 1);
+        while hint + ofs < len && after(hint + ofs)? {
+            last = ofs;
+            ofs = 2 * ofs + 1;
+        }
+        lo = hint + last + 1;
+        hi = (hint + ofs).min(len);
+    } else {
+        // Gallop left from the hint
+        let (mut last, mut ofs) = (0, 1);
+        while ofs <= hint && !after(hint - ofs)? {
+            last = ofs;
+            ofs = 2 * ofs + 1;
+        }
+        lo = if ofs <= hint { hint - ofs + 1 } else { 0 };
+        hi = hint - last;
+    }
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if after(mid)? {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
     }
+    Ok(lo)
 }
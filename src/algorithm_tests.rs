@@ -1,11 +1,23 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod algorithm_tests {
+    use crate::bench::{self, BenchConfig, Status};
+    use crate::input::Distribution;
     use crate::models::SortBar;
+    use crate::playback::{apply, HeapView, NetworkView, Playback, PlaybackSpeed};
     use crate::sorting::{
-        bogo_sort, counting_sort_visual, radix_sort_visual, shell_sort_visual, Operation,
+        bitonic_network, bitonic_sort, block_merge_sort, bogo_sort, counting_sort_visual,
+        heap_sort_visual, merge_report, merge_sort_visual, node_power, odd_even_merge_network,
+        odd_even_merge_sort, pdq_sort, pdq_sort_with_depth_limit, power_sort, quick_sort_visual,
+        radix_sort_visual, record_trace, run_algorithm, shell_sort_visual, start_sort, tim_sort,
+        BogoSort, BubbleSort, CancelToken, Cancelled, InsertionSort, MergeSort, NetworkLayer,
+        Operation, PowerSort, Registry, Sink, TimSort, MAX_COUNTED_VALUE, OP_CHANNEL_CAPACITY,
     };
-    use std::sync::mpsc;
+    use crate::stats::Stats;
+    use eframe::egui::Color32;
+    use std::sync::{mpsc, Arc};
     use std::thread;
+    use std::time::Duration;
 
     /// Helper function to create a vector of SortBars from values
     fn create_bars(values: Vec<usize>) -> Vec<SortBar> {
@@ -17,13 +29,26 @@ mod algorithm_tests {
         bars.iter().map(|b| b.value).collect()
     }
 
-    /// Check if a slice is sorted in non-decreasing order
-
     /// Check if a slice is sorted in non-decreasing order (usize version)
     fn is_sorted_usize(values: &[usize]) -> bool {
         values.windows(2).all(|w| w[0] <= w[1])
     }
 
+    /// Run `sort` on `values` while another thread drains the operation
+    /// channel, returning the sorted values and the recorded trace
+    fn record<F>(values: Vec<usize>, sort: F) -> (Vec<usize>, Vec<Operation>)
+    where
+        F: FnOnce(&mut [SortBar], &Sink) -> Result<(), Cancelled>,
+    {
+        let (tx, rx) = mpsc::sync_channel(OP_CHANNEL_CAPACITY);
+        let collector = thread::spawn(move || rx.iter().collect::<Vec<_>>());
+        let mut bars = create_bars(values);
+        let sink = Sink::new(tx, CancelToken::new());
+        sort(&mut bars, &sink).expect("sort should not be cancelled");
+        drop(sink);
+        (extract_values(&bars), collector.join().unwrap())
+    }
+
     /// Test cases for all algorithms
 
     #[test]
@@ -44,11 +69,7 @@ mod algorithm_tests {
 
         for test_case in test_cases_usize {
             let original = test_case.clone();
-            let mut bars = create_bars(test_case);
-            let (tx, _rx) = mpsc::channel::<Operation>();
-
-            counting_sort_visual(&mut bars, &tx);
-            let result = extract_values(&bars);
+            let (result, _) = record(test_case, counting_sort_visual);
 
             assert!(
                 is_sorted_usize(&result),
@@ -68,6 +89,18 @@ mod algorithm_tests {
                 original
             );
         }
+
+        // Values up to the array's length always fit, however long it is
+        let (result, _) = record((0..20_000).rev().collect(), counting_sort_visual);
+        assert!(is_sorted_usize(&result));
+        // Larger ones are counted by rank, and the trace says so
+        let huge = vec![usize::MAX, MAX_COUNTED_VALUE + 1, 1, usize::MAX, 0];
+        let (result, trace) = record(huge, counting_sort_visual);
+        assert_eq!(
+            result,
+            [0, 1, MAX_COUNTED_VALUE + 1, usize::MAX, usize::MAX]
+        );
+        assert!(trace.iter().any(|op| matches!(op, Operation::Note(..))));
     }
 
     #[test]
@@ -75,6 +108,7 @@ mod algorithm_tests {
         println!("Testing Radix Sort...");
         let test_cases_usize = vec![
             vec![5, 2, 8, 1, 9, 3, 7, 4, 6],
+            vec![1_000_000, 123_456, 7, 100_001],
             vec![9, 8, 7, 6, 5, 4, 3, 2, 1],
             vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
             vec![5, 5, 5, 5, 5],
@@ -88,11 +122,7 @@ mod algorithm_tests {
 
         for test_case in test_cases_usize {
             let original = test_case.clone();
-            let mut bars = create_bars(test_case);
-            let (tx, _rx) = mpsc::channel::<Operation>();
-
-            radix_sort_visual(&mut bars, &tx);
-            let result = extract_values(&bars);
+            let (result, _) = record(test_case, radix_sort_visual);
 
             assert!(
                 is_sorted_usize(&result),
@@ -132,11 +162,7 @@ mod algorithm_tests {
 
         for test_case in test_cases_usize {
             let original = test_case.clone();
-            let mut bars = create_bars(test_case);
-            let (tx, _rx) = mpsc::channel::<Operation>();
-
-            shell_sort_visual(&mut bars, &tx);
-            let result = extract_values(&bars);
+            let (result, _) = record(test_case, shell_sort_visual);
 
             assert!(
                 is_sorted_usize(&result),
@@ -167,21 +193,11 @@ mod algorithm_tests {
         for test_case in small_test_cases {
             let original = test_case.clone();
 
-            // Test Bogo Sort with timeout
-            let bars = create_bars(test_case.clone());
-            let (tx, _rx) = mpsc::channel::<Operation>();
+            // Use a separate thread for Bogo Sort
+            let handle = thread::spawn(move || record(test_case, bogo_sort).0);
 
-            // Use a separate thread with timeout for Bogo Sort
-            let bars_clone = bars.clone();
-            let handle = thread::spawn(move || {
-                let mut bars = bars_clone;
-                bogo_sort(&mut bars, &tx);
-                bars
-            });
-
-            // Wait for completion with timeout
-            if let Ok(result_bars) = handle.join() {
-                let result = extract_values(&result_bars);
+            // Wait for completion
+            if let Ok(result) = handle.join() {
                 if !result.is_empty() {
                     assert!(
                         is_sorted_usize(&result),
@@ -194,6 +210,882 @@ mod algorithm_tests {
         }
     }
 
+    /// Replaying every algorithm's recorded trace over its input must land on
+    /// the same sorted array the algorithm produced
+    #[test]
+    fn test_trace_replays_to_sorted_output() {
+        let input: Vec<usize> = vec![
+            17, 3, 29, 8, 3, 0, 22, 14, 31, 5, 11, 26, 8, 19, 1, 30, 12, 7, 24, 3, 16, 9, 27, 2,
+            20, 13, 6, 28, 10, 25, 4, 18, 15, 23, 21, 11,
+        ];
+
+        for algorithm in Registry::with_builtins().iter() {
+            let name = algorithm.name();
+            if name == "Bogo Sort" {
+                continue;
+            }
+            let (result, trace) = record(input.clone(), |bars, sink| {
+                run_algorithm(algorithm.as_ref(), bars, sink)
+            });
+            assert!(is_sorted_usize(&result), "{} did not sort", name);
+
+            let mut replayed = create_bars(input.clone());
+            for op in &trace {
+                apply(op, &mut replayed, Color32::WHITE);
+            }
+            assert_eq!(
+                extract_values(&replayed),
+                result,
+                "{} trace does not replay to its output",
+                name
+            );
+        }
+    }
+
+    /// Stepping a whole trace forward and then all the way back must restore
+    /// the input exactly, colours included
+    #[test]
+    fn test_trace_steps_back_to_input() {
+        let input: Vec<usize> = vec![9, 4, 7, 1, 8, 2, 2, 6, 0, 5, 3, 11, 10];
+
+        for algorithm in Registry::with_builtins().iter() {
+            let name = algorithm.name();
+            if name == "Bogo Sort" {
+                continue;
+            }
+            let (result, trace) = record(input.clone(), |bars, sink| {
+                run_algorithm(algorithm.as_ref(), bars, sink)
+            });
+
+            let mut playback = Playback::new(PlaybackSpeed::OpsPerFrame(1));
+            let stats = Stats::from_trace(&trace);
+            for op in trace {
+                playback.push(op);
+            }
+            let original = create_bars(input.clone());
+            let mut bars = original.clone();
+            while playback.step_forward(&mut bars, Color32::WHITE) {}
+            assert_eq!(extract_values(&bars), result, "{} forward", name);
+            assert_eq!(*playback.stats(), stats, "{} forward stats", name);
+            assert!(
+                playback.aux_arrays().is_empty(),
+                "{} left auxiliary arrays behind",
+                name
+            );
+            assert!(
+                playback.call_stack().is_empty(),
+                "{} left ranges open",
+                name
+            );
+
+            while playback.step_backward(&mut bars) {}
+            assert_eq!(playback.cursor(), 0);
+            assert_eq!(
+                *playback.stats(),
+                Stats::default(),
+                "{} backward stats",
+                name
+            );
+            assert_eq!(extract_values(&bars), input, "{} backward", name);
+            assert!(
+                bars.iter().zip(&original).all(|(a, b)| a.color == b.color),
+                "{} did not restore colours",
+                name
+            );
+        }
+    }
+
+    /// Buffer-based sorts report their auxiliary memory and release all of it
+    #[test]
+    fn test_aux_memory_stats() {
+        let input: Vec<usize> = vec![9, 4, 7, 1, 8, 2, 2, 6, 0, 5, 3, 11, 10];
+        let n = input.len();
+
+        let (_, trace) = record(input.clone(), counting_sort_visual);
+        let stats = Stats::from_trace(&trace);
+        // count array over 0..=11 plus an n-element output buffer
+        assert_eq!(stats.peak_aux, 12 + n);
+        assert_eq!(stats.aux, 0);
+        assert!(stats.reads >= 2 * n);
+
+        let (_, trace) = record(input.clone(), merge_sort_visual);
+        let stats = Stats::from_trace(&trace);
+        // The final merge copies the whole array
+        assert_eq!(stats.peak_aux, n);
+        assert_eq!(stats.aux, 0);
+        assert!(stats.comparisons > 0 && stats.writes > 0);
+
+        let (_, trace) = record(input, |bars, sink| run_algorithm(&BubbleSort, bars, sink));
+        let stats = Stats::from_trace(&trace);
+        assert_eq!(stats.peak_aux, 0, "in-place sorts allocate nothing");
+        assert!(stats.swaps > 0);
+    }
+
+    /// Playback's heap view follows heap sort through its trace: sift-down
+    /// paths run parent to child, and once extraction starts the suffix past
+    /// the heap is sorted and no smaller than anything left in the heap.
+    /// Stepping back brings every earlier view back.
+    #[test]
+    fn test_heap_view_follows_heap_sort() {
+        let input: Vec<usize> = vec![5, 9, 1, 7, 3, 8, 2, 6, 0, 4];
+        let n = input.len();
+        let (_, trace) = record(input.clone(), heap_sort_visual);
+        let mut playback = Playback::default();
+        for op in trace.iter().cloned() {
+            playback.push(op);
+        }
+        let mut bars = create_bars(input);
+        assert_eq!(playback.heap(), None);
+
+        let mut views: Vec<Option<HeapView>> = vec![None];
+        let mut sift_downs = 0;
+        while playback.step_forward(&mut bars, Color32::WHITE) {
+            views.push(playback.heap().cloned());
+            let Some(view) = playback.heap() else {
+                continue;
+            };
+            for edge in view.path.windows(2) {
+                assert_eq!((edge[1] - 1) / 2, edge[0], "path must go parent to child");
+            }
+            assert!(view.path.iter().all(|&k| k < view.heap_len));
+            if matches!(trace[playback.cursor() - 1], Operation::SiftDown(..)) {
+                sift_downs += 1;
+                if view.heap_len < n {
+                    let values = extract_values(&bars);
+                    let heap_max = values[..view.heap_len].iter().max().unwrap();
+                    assert!(values[view.heap_len..].windows(2).all(|w| w[0] <= w[1]));
+                    assert!(values[view.heap_len..].iter().all(|v| v >= heap_max));
+                }
+            }
+        }
+        // One per node with children while building, one per extraction
+        assert_eq!(sift_downs, n / 2 + n - 1);
+        assert_eq!(playback.heap().unwrap().heap_len, 1);
+        assert!(views
+            .iter()
+            .any(|view| view.as_ref().is_some_and(|v| v.path.len() > 2)));
+
+        while playback.step_backward(&mut bars) {
+            assert_eq!(playback.heap(), views[playback.cursor()].as_ref());
+        }
+        assert_eq!(playback.cursor(), 0);
+    }
+
+    /// Every builtin ships a listing and marks a line of it before each
+    /// comparison and swap; playback follows the marks both ways
+    #[test]
+    fn test_pseudocode_lines() {
+        let input: Vec<usize> = vec![9, 4, 7, 1, 8, 2, 2, 6, 0, 5, 3, 11, 10];
+
+        for algorithm in Registry::with_builtins().iter() {
+            let name = algorithm.name();
+            let listing = algorithm.pseudocode();
+            assert!(!listing.is_empty(), "{} has no pseudocode", name);
+            // Bogo Sort only finishes on very short arrays
+            let input = if name == "Bogo Sort" {
+                input[..4].to_vec()
+            } else {
+                input.clone()
+            };
+            let (_, trace) = record(input.clone(), |bars, sink| {
+                run_algorithm(algorithm.as_ref(), bars, sink)
+            });
+
+            let mut line = None;
+            for op in &trace {
+                match *op {
+                    Operation::Line(l) => {
+                        assert!((1..=listing.len()).contains(&l), "{} line {}", name, l);
+                        line = Some(l);
+                    }
+                    Operation::Compare(..) | Operation::Swap(..) => {
+                        assert!(line.is_some(), "{} {:?} before any line", name, op);
+                    }
+                    _ => {}
+                }
+            }
+
+            let mut playback = Playback::new(PlaybackSpeed::OpsPerFrame(1));
+            for op in trace {
+                playback.push(op);
+            }
+            let mut bars = create_bars(input.clone());
+            let mut lines = vec![playback.current_line()];
+            while playback.step_forward(&mut bars, Color32::WHITE) {
+                lines.push(playback.current_line());
+            }
+            while playback.step_backward(&mut bars) {
+                assert_eq!(
+                    playback.current_line(),
+                    lines[playback.cursor()],
+                    "{}",
+                    name
+                );
+            }
+        }
+    }
+
+    /// Markers cost no playback time: a frame applies one real operation
+    /// along with any markers in front of it
+    #[test]
+    fn test_markers_are_free_in_playback() {
+        let (_, trace) = record(vec![3, 1, 2], |bars, sink| {
+            run_algorithm(&BubbleSort, bars, sink)
+        });
+        assert!(trace.iter().any(|op| op.is_marker()));
+
+        let mut playback = Playback::new(PlaybackSpeed::OpsPerFrame(1));
+        for op in trace {
+            playback.push(op);
+        }
+        playback.push(Operation::Done);
+        let real_ops = |playback: &Playback| {
+            playback.trace()[..playback.cursor()]
+                .iter()
+                .filter(|op| !op.is_marker())
+                .count()
+        };
+        let mut bars = create_bars(vec![3, 1, 2]);
+        let mut finished = false;
+        while !finished {
+            let before = real_ops(&playback);
+            finished = playback.advance(0.016, &mut bars, Color32::WHITE);
+            assert_eq!(real_ops(&playback), before + 1);
+        }
+        assert_eq!(playback.buffered(), 0);
+        assert_eq!(extract_values(&bars), [1, 2, 3]);
+    }
+
+    /// Recursive sorts open and close their ranges like a call stack, and
+    /// the deepest recursion shows quicksort's worst case on sorted input
+    #[test]
+    fn test_recursion_ranges() {
+        let sorted: Vec<usize> = (0..16).collect();
+        let (_, trace) = record(sorted.clone(), quick_sort_visual);
+        let stats = Stats::from_trace(&trace);
+        assert_eq!(
+            stats.max_depth, 16,
+            "a last-element pivot peels one per call"
+        );
+        assert_eq!(stats.depth, 0);
+
+        let (_, trace) = record(sorted.into_iter().rev().collect(), merge_sort_visual);
+        assert_eq!(Stats::from_trace(&trace).max_depth, 5, "log2(16) + 1");
+
+        // Every exit matches the innermost open range, and children nest
+        // inside their parent
+        let mut stack: Vec<(usize, usize)> = Vec::new();
+        for op in &trace {
+            match *op {
+                Operation::EnterRange(start, end) => {
+                    if let Some(&(outer_start, outer_end)) = stack.last() {
+                        assert!(outer_start <= start && end <= outer_end);
+                    }
+                    stack.push((start, end));
+                }
+                Operation::ExitRange(start, end) => {
+                    assert_eq!(stack.pop(), Some((start, end)));
+                }
+                _ => {}
+            }
+        }
+        assert!(stack.is_empty());
+
+        // Playback keeps the same stack and rewinds it
+        let mut playback = Playback::new(PlaybackSpeed::OpsPerFrame(1));
+        for op in trace {
+            playback.push(op);
+        }
+        let mut bars = create_bars((0..16).rev().collect());
+        while playback.call_stack().len() < 3 {
+            playback.step_forward(&mut bars, Color32::WHITE);
+        }
+        assert_eq!(playback.call_stack(), [0..16, 0..8, 0..4]);
+        let third_call = playback.cursor();
+        while playback.step_forward(&mut bars, Color32::WHITE) {}
+        assert!(playback.call_stack().is_empty());
+        while playback.step_backward(&mut bars) {
+            if playback.cursor() == third_call {
+                assert_eq!(playback.call_stack(), [0..16, 0..8, 0..4]);
+            }
+        }
+        assert!(playback.call_stack().is_empty());
+        assert_eq!(playback.stats().max_depth, 0);
+    }
+
+    /// Auxiliary arrays replay with their names and contents, and stepping
+    /// back through a release brings the array back as it was
+    #[test]
+    fn test_aux_arrays_replay() {
+        let input: Vec<usize> = vec![3, 0, 2, 1, 2];
+        let (_, trace) = record(input.clone(), counting_sort_visual);
+        let mut playback = Playback::new(PlaybackSpeed::OpsPerFrame(1));
+        for op in trace {
+            playback.push(op);
+        }
+        let mut bars = create_bars(input);
+
+        // Just before the output buffer is released it holds the sorted values
+        let mut names = Vec::new();
+        while playback.step_forward(&mut bars, Color32::WHITE) {
+            for array in playback.aux_arrays() {
+                if !names.contains(&array.name) {
+                    names.push(array.name);
+                }
+            }
+            if playback.aux_arrays().len() == 1 && playback.aux_arrays()[0].name == "output" {
+                break;
+            }
+        }
+        assert_eq!(names, ["count", "output"]);
+        let output = playback.aux_arrays()[0].clone();
+        assert_eq!(output.values, [0, 1, 2, 2, 3]);
+
+        playback.step_forward(&mut bars, Color32::WHITE);
+        assert!(playback.aux_arrays().is_empty());
+        playback.step_backward(&mut bars);
+        assert_eq!(playback.aux_arrays(), [output]);
+
+        // Merge sort names its two halves
+        let (_, trace) = record(vec![2, 1], merge_sort_visual);
+        let created: Vec<_> = trace
+            .iter()
+            .filter_map(|op| match op {
+                Operation::AuxCreate(_, name, len) => Some((*name, *len)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(created, [("left", 1), ("right", 1)]);
+    }
+
+    /// A counting sink tallies exactly what the recorded trace contains, and
+    /// a discarding sink still lets the algorithm sort
+    #[test]
+    fn test_headless_sinks() {
+        let input: Vec<usize> = vec![9, 4, 7, 1, 8, 2, 2, 6, 0, 5, 3, 11, 10];
+
+        for algorithm in Registry::with_builtins().iter() {
+            let name = algorithm.name();
+            if name == "Bogo Sort" {
+                continue;
+            }
+            let (result, trace) = record(input.clone(), |bars, sink| {
+                run_algorithm(algorithm.as_ref(), bars, sink)
+            });
+
+            let mut bars = create_bars(input.clone());
+            let sink = Sink::counting();
+            run_algorithm(algorithm.as_ref(), &mut bars, &sink).unwrap();
+            assert_eq!(extract_values(&bars), result, "{} counting", name);
+            assert_eq!(sink.stats(), Some(Stats::from_trace(&trace)), "{}", name);
+
+            let mut bars = create_bars(input.clone());
+            let sink = Sink::discard();
+            run_algorithm(algorithm.as_ref(), &mut bars, &sink).unwrap();
+            assert_eq!(extract_values(&bars), result, "{} discard", name);
+            assert_eq!(sink.stats(), None);
+        }
+    }
+
+    /// The benchmark covers the whole grid, and an algorithm that hits the
+    /// time limit is cancelled and skipped at larger sizes
+    #[test]
+    fn test_bench_grid_and_time_limit() {
+        let mut config = BenchConfig::new(vec![Arc::new(InsertionSort), Arc::new(BogoSort)]);
+        config.sizes = vec![4, 64, 128];
+        config.distributions = vec![Distribution::Reversed];
+        config.trials = 2;
+        config.time_limit = Duration::from_millis(200);
+
+        let results = bench::run(&config, |_| {});
+        assert_eq!(results.len(), 6);
+        for r in results.iter().filter(|r| r.algorithm == "Insertion Sort") {
+            assert_eq!(r.status, Status::Complete);
+            assert_eq!(r.trials, 2);
+            assert!(r.sorted);
+            // Reversed input is the worst case: every pair is compared
+            assert_eq!(r.comparisons.mean, (r.size * (r.size - 1) / 2) as f64);
+            assert_eq!(r.comparisons.ci95, 0.0, "same input, same count");
+        }
+        let bogo: Vec<_> = results
+            .iter()
+            .filter(|r| r.algorithm == "Bogo Sort")
+            .collect();
+        assert_eq!(bogo[1].status, Status::TimedOut);
+        assert_eq!(bogo[2].status, Status::Skipped);
+
+        let mut csv = Vec::new();
+        bench::write_csv(&results, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), results.len() + 1);
+        let columns = csv.lines().next().unwrap().split(',').count();
+        assert!(csv.lines().all(|line| line.split(',').count() == columns));
+    }
+
+    /// Recording without a channel yields the same trace the visualizer
+    /// would receive from a worker
+    #[test]
+    fn test_record_trace_matches_worker() {
+        let input = create_bars(vec![9, 4, 7, 1, 8, 2, 2, 6, 0, 5, 3]);
+        let trace = record_trace(&MergeSort, &input, CancelToken::new()).unwrap();
+
+        let (tx, rx) = mpsc::sync_channel(OP_CHANNEL_CAPACITY);
+        let worker = start_sort(Arc::new(MergeSort), input, tx);
+        let received: Vec<Operation> = rx.iter().collect();
+        assert_eq!(worker.join(), Ok(()));
+        assert_eq!(format!("{:?}", trace), format!("{:?}", received));
+        assert!(matches!(trace.last(), Some(Operation::Done)));
+    }
+
+    /// Cancelling a worker stops even a sort that would never finish, and
+    /// joining reports that it was cancelled
+    #[test]
+    fn test_cancel_stops_worker() {
+        let (tx, rx) = mpsc::sync_channel(OP_CHANNEL_CAPACITY);
+        // Bogo Sort on 50 reversed bars will not finish within the test
+        let worker = start_sort(Arc::new(BogoSort), create_bars((0..50).rev().collect()), tx);
+        assert!(rx.recv().is_ok(), "worker should start emitting operations");
+
+        worker.cancel();
+        // Dropping the receiver wakes a worker blocked on a full channel
+        drop(rx);
+        assert_eq!(worker.join(), Err(Cancelled));
+    }
+
+    /// A worker left to run ends its trace with Done and joins cleanly
+    #[test]
+    fn test_worker_finishes_with_done() {
+        let (tx, rx) = mpsc::sync_channel(OP_CHANNEL_CAPACITY);
+        let worker = start_sort(Arc::new(InsertionSort), create_bars(vec![3, 1, 2]), tx);
+        let trace: Vec<Operation> = rx.iter().collect();
+        assert!(matches!(trace.last(), Some(Operation::Done)));
+        assert_eq!(worker.join(), Ok(()));
+    }
+
+    /// A token cancelled up front stops an algorithm before its first operation
+    #[test]
+    fn test_cancelled_sink_rejects_operations() {
+        let (tx, rx) = mpsc::sync_channel(OP_CHANNEL_CAPACITY);
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let sink = Sink::new(tx, cancel);
+        let mut bars = create_bars(vec![2, 1]);
+        assert_eq!(run_algorithm(&BubbleSort, &mut bars, &sink), Err(Cancelled));
+        drop(sink);
+        assert_eq!(rx.iter().count(), 0);
+    }
+
+    /// Count comparisons, swaps and writes in a trace
+    fn trace_stats(trace: &[Operation]) -> Stats {
+        let mut stats = Stats::default();
+        for op in trace {
+            stats.record(op);
+        }
+        stats
+    }
+
+    /// TimSort finds presorted input in one pass of n - 1 comparisons,
+    /// reverses descending input in place, and stays stable elsewhere
+    #[test]
+    fn test_timsort_natural_runs() {
+        let n = 1000;
+        let (values, trace) = record((0..n).collect(), tim_sort);
+        assert!(is_sorted_usize(&values));
+        let stats = trace_stats(&trace);
+        assert_eq!(stats.comparisons, n - 1);
+        assert_eq!((stats.swaps, stats.writes), (0, 0));
+        let runs: Vec<_> = trace
+            .iter()
+            .filter(|op| matches!(op, Operation::PushRun(..)))
+            .collect();
+        assert!(matches!(runs[..], [Operation::PushRun(0, end)] if *end == n));
+
+        let (values, trace) = record((0..n).rev().collect(), tim_sort);
+        assert_eq!(values, (0..n).collect::<Vec<_>>());
+        let stats = trace_stats(&trace);
+        assert_eq!(stats.comparisons, n - 1);
+        assert_eq!((stats.swaps, stats.writes), (n / 2, 0));
+
+        // Tag every bar with its starting index to check that equal values
+        // keep their order
+        let mut bars: Vec<SortBar> = (0..n)
+            .map(|i| SortBar {
+                value: (i * 7919) % 13,
+                color: Color32::from_rgb((i >> 8) as u8, i as u8, 0),
+            })
+            .collect();
+        tim_sort(&mut bars, &Sink::discard()).unwrap();
+        let tag = |b: &SortBar| ((b.color.r() as usize) << 8) | b.color.g() as usize;
+        assert!(bars
+            .windows(2)
+            .all(|w| w[0].value < w[1].value
+                || (w[0].value == w[1].value && tag(&w[0]) < tag(&w[1]))));
+    }
+
+    /// Between pushes the pending runs keep TimSort's invariants, long
+    /// stretches won by one run are galloped over, and playback can rewind
+    /// the run stack
+    #[test]
+    fn test_timsort_run_stack() {
+        // Two ascending runs that take turns winning blocks of 50
+        let half =
+            |offset: usize| (0..10).flat_map(move |b| 100 * b + offset..100 * b + offset + 50);
+        let input: Vec<usize> = half(0).chain(half(50)).collect();
+        let (values, trace) = record(input.clone(), tim_sort);
+        assert!(is_sorted_usize(&values));
+        assert!(trace.iter().any(|op| matches!(op, Operation::Line(17))));
+
+        let mut rng_input: Vec<usize> = (0..2000).map(|i| (i * 7919 + 13) % 2003).collect();
+        rng_input.extend(0..300);
+        let (values, trace) = record(rng_input.clone(), tim_sort);
+        assert!(is_sorted_usize(&values));
+        let mut runs: Vec<usize> = Vec::new();
+        for op in &trace {
+            match *op {
+                Operation::PushRun(start, end) => {
+                    for i in 0..runs.len() {
+                        let rest: usize = runs[i + 1..].iter().take(2).sum();
+                        assert!(runs[i] > rest, "invariant broken: {:?}", runs);
+                    }
+                    runs.push(end - start);
+                }
+                Operation::MergeRuns(i, _) => {
+                    let len = runs.remove(i + 1);
+                    runs[i] += len;
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(runs, vec![rng_input.len()]);
+
+        let mut bars = create_bars(rng_input);
+        let mut playback = Playback::new(PlaybackSpeed::OpsPerFrame(1));
+        for op in trace {
+            playback.push(op);
+        }
+        let mut snapshots = vec![playback.run_stack().to_vec()];
+        while playback.step_forward(&mut bars, Color32::WHITE) {
+            snapshots.push(playback.run_stack().to_vec());
+        }
+        let whole = 0..bars.len();
+        assert_eq!(playback.run_stack(), std::slice::from_ref(&whole));
+        while playback.step_backward(&mut bars) {
+            snapshots.pop();
+            assert_eq!(playback.run_stack(), snapshots.last().unwrap().as_slice());
+        }
+    }
+
+    /// Powersort weighs every boundary between runs and keeps the powers on
+    /// its stack rising towards the top. A long run in the middle of short
+    /// ones costs TimSort more merging than it costs Powersort
+    #[test]
+    fn test_powersort_node_powers() {
+        // Midpoints 1/8 and 3/8 first differ in the second binary digit
+        assert_eq!(node_power(0..2, 2..6, 16), 2);
+        assert_eq!(node_power(0..8, 8..16, 16), 1);
+        assert_eq!(node_power(12..14, 14..16, 16), 3);
+
+        let lengths = [64, 64, 64, 64, 64, 1000, 64, 64, 64];
+        let input: Vec<usize> = lengths.iter().flat_map(|&len| 0..len).collect();
+        let n = input.len();
+        let (values, trace) = record(input.clone(), power_sort);
+        assert!(is_sorted_usize(&values));
+
+        let mut runs: Vec<std::ops::Range<usize>> = Vec::new();
+        let mut powers: Vec<usize> = Vec::new();
+        let mut nodes = 0;
+        for op in &trace {
+            match *op {
+                Operation::NodePower(start, mid, end, power) => {
+                    assert_eq!(runs.last(), Some(&(start..mid)));
+                    assert_eq!(power, node_power(start..mid, mid..end, n));
+                    nodes += 1;
+                }
+                Operation::PushRun(start, end) => {
+                    // Powers of the boundaries between pending runs
+                    if let Some(top) = runs.last() {
+                        powers.push(node_power(top.clone(), start..end, n));
+                    }
+                    assert!(powers.windows(2).all(|w| w[0] < w[1]), "{:?}", powers);
+                    runs.push(start..end);
+                }
+                Operation::MergeRuns(i, _) => {
+                    let right = runs.remove(i + 1);
+                    runs[i].end = right.end;
+                    powers.remove(i);
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(nodes, lengths.len() - 1);
+        assert_eq!(runs, vec![0..n]);
+
+        let bars = create_bars(input);
+        let power = merge_report(&PowerSort, &bars);
+        let tim = merge_report(&TimSort, &bars);
+        assert_eq!(power.merges, lengths.len() - 1);
+        assert_eq!(tim.merges, lengths.len() - 1);
+        assert!(power.merge_cost < tim.merge_cost, "{:?} {:?}", power, tim);
+
+        let mut playback = Playback::new(PlaybackSpeed::OpsPerFrame(1));
+        for op in trace {
+            playback.push(op);
+        }
+        let mut bars = create_bars(vec![0; n]);
+        while playback.step_forward(&mut bars, Color32::WHITE) {}
+        assert_eq!(playback.power_nodes().len(), nodes);
+        assert_eq!(playback.power_at(64), Some(node_power(0..64, 64..128, n)));
+        while playback.step_backward(&mut bars) {}
+        assert!(playback.power_nodes().is_empty());
+    }
+
+    /// Pdqsort sorts every shape of input, takes its shortcuts on sorted
+    /// and duplicate-heavy input, and announces each fallback with a note
+    /// that holds until the call that made it returns.
+    #[test]
+    fn test_pdqsort_fallbacks() {
+        let n = 1000;
+        let shuffled: Vec<usize> = (0..n).map(|i| (i * 7919) % n).collect();
+        let organ_pipe: Vec<usize> = (0..n / 2).chain((0..n / 2).rev()).collect();
+        let inputs = [
+            shuffled.clone(),
+            (0..n).collect(),
+            (0..n).rev().collect(),
+            (0..n).map(|i| i % 4).collect(),
+            organ_pipe,
+        ];
+        for input in inputs {
+            let (values, trace) = record(input, pdq_sort);
+            assert!(is_sorted_usize(&values));
+            let stats = trace_stats(&trace);
+            assert!(stats.max_depth <= 2 * n.ilog2() as usize + 1);
+            assert_eq!(stats.depth, 0);
+        }
+
+        // Sorted input is one partition and two short insertion sorts
+        let (_, trace) = record((0..n).collect(), pdq_sort);
+        assert!(trace.iter().any(|op| matches!(
+            op,
+            Operation::Note(0, end, "Looks sorted: trying insertion sort") if *end == n
+        )));
+        assert!(trace_stats(&trace).comparisons < 3 * n);
+
+        let (_, trace) = record((0..n).map(|i| i % 4).collect(), pdq_sort);
+        assert!(trace.iter().any(|op| matches!(
+            op,
+            Operation::Note(_, _, "Many equal elements: pivot equals its predecessor")
+        )));
+
+        // No recursion allowed: the whole array goes to heap sort
+        let mut expected = shuffled[..100].to_vec();
+        expected.sort();
+        let (values, trace) = record(shuffled[..100].to_vec(), |bars, sink| {
+            pdq_sort_with_depth_limit(bars, 0, sink)
+        });
+        assert_eq!(values, expected);
+        assert!(trace.iter().any(|op| matches!(
+            op,
+            Operation::Note(0, 100, "Heap sort: depth limit reached")
+        )));
+        let (values, trace) = record(shuffled.clone(), |bars, sink| {
+            pdq_sort_with_depth_limit(bars, 1, sink)
+        });
+        assert!(is_sorted_usize(&values));
+        let heap_sorts: Vec<_> = trace
+            .iter()
+            .filter_map(|op| match *op {
+                Operation::Note(lo, hi, "Heap sort: depth limit reached") => Some(lo..hi),
+                _ => None,
+            })
+            .collect();
+        assert!(!heap_sorts.is_empty());
+        assert!(heap_sorts.iter().all(|range| range.len() < n));
+
+        let mut playback = Playback::new(PlaybackSpeed::OpsPerFrame(1));
+        for op in trace {
+            playback.push(op);
+        }
+        let mut bars = create_bars(vec![0; n]);
+        let mut seen = 0;
+        while playback.step_forward(&mut bars, Color32::WHITE) {
+            if let Some(note) = playback.note() {
+                if note.text == "Heap sort: depth limit reached" && note.range == heap_sorts[0] {
+                    seen += 1;
+                }
+            }
+        }
+        assert!(seen > 0);
+        // Every note ends with the call that made it
+        assert!(playback.note().is_none());
+        while playback.step_backward(&mut bars) {
+            if let Some(note) = playback.note() {
+                assert!(note.range.len() > 1);
+            }
+        }
+        assert!(playback.note().is_none());
+    }
+
+    /// Block merge sort is stable with no auxiliary memory. It sets keys
+    /// aside as tags and a buffer when there are enough distinct values and
+    /// merges by rotation when there aren't, and playback follows its keys,
+    /// blocks and rotations
+    #[test]
+    fn test_block_merge_sort() {
+        let n = 1000;
+        for distinct in [3, 50, 4 * n] {
+            let mut bars: Vec<SortBar> = (0..n)
+                .map(|i| SortBar {
+                    value: (i * 7919) % distinct,
+                    color: Color32::from_rgb((i >> 8) as u8, i as u8, 0),
+                })
+                .collect();
+            block_merge_sort(&mut bars, &Sink::discard()).unwrap();
+            let tag = |b: &SortBar| ((b.color.r() as usize) << 8) | b.color.g() as usize;
+            assert!(bars.windows(2).all(|w| w[0].value < w[1].value
+                || (w[0].value == w[1].value && tag(&w[0]) < tag(&w[1]))));
+        }
+
+        let (_, trace) = record((0..n).collect(), block_merge_sort);
+        assert!(trace_stats(&trace).comparisons < 2 * n);
+
+        // Blocks of 16, tagged by 63 keys, with 16 more as the buffer
+        let input: Vec<usize> = (0..n).map(|i| (i * 7919) % n).collect();
+        let (values, trace) = record(input.clone(), block_merge_sort);
+        assert!(is_sorted_usize(&values));
+        let stats = trace_stats(&trace);
+        assert_eq!((stats.writes, stats.peak_aux), (0, 0));
+        let keys: Vec<_> = trace
+            .iter()
+            .filter_map(|op| match *op {
+                Operation::Keys(start, mid, end) => Some((start, mid, end)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(keys, [(0, 63, 79), (0, 0, 0)]);
+        assert!(trace
+            .iter()
+            .any(|op| matches!(op, Operation::Blocks(_, _, _, 16))));
+
+        let (values, few) = record((0..n).map(|i| i % 10).collect(), block_merge_sort);
+        assert!(is_sorted_usize(&values));
+        assert!(!few.iter().any(|op| matches!(op, Operation::Keys(..))));
+        assert!(few.iter().any(|op| matches!(op, Operation::Rotate(..))));
+
+        // Tagging leaves the left run's blocks first, and swapping blocks
+        // with their tags keeps the count of each
+        let mut playback = Playback::new(PlaybackSpeed::OpsPerFrame(1));
+        for op in trace {
+            playback.push(op);
+        }
+        let mut bars = create_bars(input);
+        let mut merges = 0;
+        while playback.step_forward(&mut bars, Color32::WHITE) {
+            let Some(merge) = playback.block_merge() else {
+                continue;
+            };
+            let from_left = merge.left.len() / merge.block_len;
+            let origins = playback.block_origins(&bars);
+            assert_eq!(origins.len(), merge.blocks().count());
+            assert_eq!(origins.iter().filter(|&&left| left).count(), from_left);
+            if matches!(
+                playback.trace().get(playback.cursor() - 1),
+                Some(Operation::Blocks(..))
+            ) {
+                assert!(origins[..from_left].iter().all(|&left| left));
+                merges += 1;
+            }
+        }
+        assert!(merges > 0);
+        assert!(playback.keys().is_none() && playback.block_merge().is_none());
+        while playback.step_backward(&mut bars) {}
+        assert!(playback.keys().is_none());
+        assert!(playback.rotation().is_none());
+    }
+
+    #[test]
+    fn test_sorting_networks() {
+        type Sort = fn(&mut [SortBar], &Sink) -> Result<(), Cancelled>;
+        type Network = fn(usize) -> Vec<NetworkLayer>;
+        let networks: [(Network, Sort); 2] = [
+            (bitonic_network, bitonic_sort),
+            (odd_even_merge_network, odd_even_merge_sort),
+        ];
+        for (network, sort) in networks {
+            for n in 0..=10 {
+                let layers = network(n);
+                for layer in &layers {
+                    let mut wires: Vec<usize> = layer
+                        .comparators
+                        .iter()
+                        .flat_map(|&(i, j)| {
+                            assert!(i < j && j < n);
+                            [i, j]
+                        })
+                        .collect();
+                    let len = wires.len();
+                    wires.sort();
+                    wires.dedup();
+                    assert_eq!(wires.len(), len, "a wire is used twice in one layer");
+                }
+                // By the 0-1 principle, sorting every 0-1 input is enough
+                for bits in 0..1usize << n {
+                    let mut values: Vec<usize> = (0..n).map(|i| bits >> i & 1).collect();
+                    for &(i, j) in layers.iter().flat_map(|layer| &layer.comparators) {
+                        if values[i] > values[j] {
+                            values.swap(i, j);
+                        }
+                    }
+                    assert!(is_sorted_usize(&values), "n = {n}, input {bits:b}");
+                }
+            }
+
+            // Data-oblivious: the same comparisons whatever the input
+            let n = 100;
+            let (sorted, ascending) = record((0..n).collect(), sort);
+            let (reversed, descending) = record((0..n).rev().collect(), sort);
+            assert!(is_sorted_usize(&sorted) && is_sorted_usize(&reversed));
+            assert_eq!(
+                trace_stats(&ascending).comparisons,
+                trace_stats(&descending).comparisons
+            );
+            let layers = ascending
+                .iter()
+                .filter(|op| matches!(op, Operation::Layer(_)))
+                .count();
+            assert_eq!(layers, network(n).len());
+            let (values, _) = record((0..n).map(|i| (i * 37) % 11).collect(), sort);
+            assert!(is_sorted_usize(&values));
+        }
+
+        let size = |layers: Vec<NetworkLayer>| {
+            let total: usize = layers.iter().map(|layer| layer.comparators.len()).sum();
+            (layers.len(), total)
+        };
+        assert_eq!(size(bitonic_network(16)), (10, 80));
+        assert_eq!(size(odd_even_merge_network(16)), (10, 63));
+
+        // Playback counts off each layer's comparators, both ways
+        let (_, trace) = record((0..16).rev().collect(), bitonic_sort);
+        let second = trace
+            .iter()
+            .position(|op| matches!(op, Operation::Layer(1)))
+            .unwrap();
+        let mut playback = Playback::default();
+        for op in trace.iter().cloned() {
+            playback.push(op);
+        }
+        let mut bars = create_bars((0..16).rev().collect());
+        assert_eq!(playback.network(), None);
+        while playback.cursor() < second + 1 {
+            playback.step_forward(&mut bars, Color32::WHITE);
+        }
+        assert_eq!(playback.network(), Some(NetworkView { layer: 1, done: 0 }));
+        playback.step_backward(&mut bars);
+        assert_eq!(playback.network(), Some(NetworkView { layer: 0, done: 8 }));
+        while playback.step_backward(&mut bars) {}
+        assert_eq!(playback.network(), None);
+    }
+
     // Removed test_algorithm_stability: referenced non-existent algorithms
 
     // Removed test_performance_characteristics: referenced non-existent algorithms
@@ -0,0 +1,505 @@
+//! Headless runner for the sorthos algorithms.
+//!
+//! Runs one algorithm on a generated input and prints the result and its
+//! operation counts, without opening a window:
+//!
+//! ```text
+//! sorthos-cli quick --size 1000 --distribution reversed --seed 7 --format json
+//! ```
+//!
+//! Exits with status 1 if the output is not a sorted permutation of the
+//! input, so it can be used as a check in scripts.
+//!
+//! With `--wav` the run is also sonified into a WAV file, timed like the
+//! GUI's video exports, and with `--history` drawn as a PNG with one row of
+//! pixels per array state.
+//!
+//! `sorthos-cli bench` runs the benchmark grid from `sorthos::bench` and
+//! writes CSV or JSON. Build with `--release` for meaningful timings.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use sorthos::bench::{self, BenchConfig};
+use sorthos::export::{export_run, history, work_len, ExportFormat, ExportOptions};
+use sorthos::input::Distribution;
+use sorthos::json;
+use sorthos::models::SortBar;
+use sorthos::sorting::{record_trace, run_algorithm, CancelToken, Category, Registry, Sink};
+use sorthos::stats::Stats;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "\
+Usage: sorthos-cli [OPTIONS] <ALGORITHM>
+       sorthos-cli bench [BENCH OPTIONS]
+
+Options:
+  -n, --size <N>            number of elements (default 100)
+  -d, --distribution <D>    input shape (default shuffled)
+  -s, --seed <SEED>         random seed (default: random, printed)
+  -f, --format <text|json>  output format (default text)
+      --sink <count|none>   count operations, or discard them for timing (default count)
+      --print-array         include the sorted values in the output
+      --wav <FILE>          also write the run as sound to FILE
+      --stride <N>          operations per audio frame (default 10)
+      --frame-ms <MS>       length of an audio frame (default 40)
+      --history <FILE>      also write the run as a space-time PNG to FILE
+  -l, --list                list algorithms and distributions
+  -h, --help                show this help
+
+Bench options:
+  -a, --algorithms <A,..>   algorithms to run (default: all but probabilistic)
+      --sizes <N,..>        array sizes (default 16,256,4096,65536,1000000)
+  -d, --distributions <D,..>  input shapes (default: all)
+  -t, --trials <N>          timed trials per cell (default 5)
+  -w, --warmup <N>          untimed runs per cell (default 1)
+  -s, --seed <SEED>         random seed (default 0)
+      --time-limit <SECS>   give up on a trial after this long (default 10)
+  -f, --format <csv|json>   output format (default csv)
+  -o, --output <FILE>       write results to FILE instead of stdout";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Text,
+    Json,
+}
+
+struct Args {
+    algorithm: String,
+    size: usize,
+    distribution: Distribution,
+    seed: Option<u64>,
+    format: Format,
+    count: bool,
+    print_array: bool,
+    wav: Option<String>,
+    history: Option<String>,
+    // Only `stride` and `delay_ms` are used
+    sound: ExportOptions,
+}
+
+struct BenchArgs {
+    algorithms: Option<Vec<String>>,
+    sizes: Option<Vec<usize>>,
+    distributions: Option<Vec<Distribution>>,
+    trials: Option<usize>,
+    warmup: Option<usize>,
+    seed: Option<u64>,
+    time_limit: Option<f64>,
+    json: bool,
+    output: Option<String>,
+}
+
+enum Command {
+    Run(Args),
+    Bench(BenchArgs),
+    List,
+    Help,
+}
+
+/// Parse a comma-separated list, naming `what` in the error.
+fn parse_list<T: std::str::FromStr>(value: &str, what: &str) -> Result<Vec<T>, String> {
+    value
+        .split(',')
+        .map(|item| {
+            item.trim()
+                .parse()
+                .map_err(|_| format!("invalid {} '{}'", what, item))
+        })
+        .collect()
+}
+
+/// Parse a single value, naming `what` in the error.
+fn parse_value<T: std::str::FromStr>(value: &str, what: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid {} '{}'", what, value))
+}
+
+fn parse_bench_args(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut bench = BenchArgs {
+        algorithms: None,
+        sizes: None,
+        distributions: None,
+        trials: None,
+        warmup: None,
+        seed: None,
+        time_limit: None,
+        json: false,
+        output: None,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-a" | "--algorithms" => {
+                bench.algorithms = Some(value(&arg)?.split(',').map(str::to_string).collect())
+            }
+            "--sizes" => bench.sizes = Some(parse_list(&value(&arg)?, "size")?),
+            "-d" | "--distributions" => {
+                bench.distributions = Some(parse_list(&value(&arg)?, "distribution")?)
+            }
+            "-t" | "--trials" => bench.trials = Some(parse_value(&value(&arg)?, "trial count")?),
+            "-w" | "--warmup" => bench.warmup = Some(parse_value(&value(&arg)?, "warmup count")?),
+            "-s" | "--seed" => bench.seed = Some(parse_value(&value(&arg)?, "seed")?),
+            "--time-limit" => {
+                let v = value(&arg)?;
+                let secs: f64 = parse_value(&v, "time limit")?;
+                // `Duration` takes neither negative nor infinite seconds
+                if !(secs > 0.0 && secs.is_finite()) {
+                    return Err(format!("invalid time limit '{}'", v));
+                }
+                bench.time_limit = Some(secs);
+            }
+            "-f" | "--format" => {
+                bench.json = match value(&arg)?.as_str() {
+                    "csv" => false,
+                    "json" => true,
+                    other => return Err(format!("unknown format '{}'", other)),
+                }
+            }
+            "-o" | "--output" => bench.output = Some(value(&arg)?),
+            other => return Err(format!("unknown bench option '{}'", other)),
+        }
+    }
+    Ok(Command::Bench(bench))
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut args = args.by_ref().peekable();
+    if args.peek().map(String::as_str) == Some("bench") {
+        args.next();
+        return parse_bench_args(args);
+    }
+    let mut algorithm = None;
+    let mut size = 100;
+    let mut distribution = Distribution::default();
+    let mut seed = None;
+    let mut format = Format::Text;
+    let mut count = true;
+    let mut print_array = false;
+    let mut wav = None;
+    let mut history = None;
+    let mut sound = ExportOptions {
+        format: ExportFormat::Wav,
+        ..ExportOptions::default()
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-l" | "--list" => return Ok(Command::List),
+            "-n" | "--size" => size = parse_value(&value(&arg)?, "size")?,
+            "-d" | "--distribution" => distribution = value(&arg)?.parse()?,
+            "-s" | "--seed" => seed = Some(parse_value(&value(&arg)?, "seed")?),
+            "-f" | "--format" => {
+                format = match value(&arg)?.as_str() {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    other => return Err(format!("unknown format '{}'", other)),
+                }
+            }
+            "--sink" => {
+                count = match value(&arg)?.as_str() {
+                    "count" => true,
+                    "none" => false,
+                    other => return Err(format!("unknown sink '{}'", other)),
+                }
+            }
+            "--print-array" => print_array = true,
+            "--wav" => wav = Some(value(&arg)?),
+            "--history" => history = Some(value(&arg)?),
+            "--stride" => sound.stride = parse_value(&value(&arg)?, "stride")?,
+            "--frame-ms" => sound.delay_ms = parse_value(&value(&arg)?, "frame length")?,
+            other if other.starts_with('-') => return Err(format!("unknown option '{}'", other)),
+            other => {
+                if algorithm.replace(other.to_string()).is_some() {
+                    return Err("only one algorithm can be given".to_string());
+                }
+            }
+        }
+    }
+
+    let algorithm = algorithm.ok_or("missing algorithm name")?;
+    Ok(Command::Run(Args {
+        algorithm,
+        size,
+        distribution,
+        seed,
+        format,
+        count,
+        print_array,
+        wav,
+        history,
+        sound,
+    }))
+}
+
+fn list(registry: &Registry) {
+    println!("Algorithms:");
+    for algorithm in registry.iter() {
+        println!("  {} ({})", algorithm.name(), algorithm.category());
+    }
+    println!("Distributions:");
+    for distribution in Distribution::all() {
+        println!("  {}", distribution);
+    }
+}
+
+fn main() -> ExitCode {
+    let registry = Registry::with_builtins();
+    match parse_args(std::env::args().skip(1)) {
+        Ok(Command::Run(args)) => run(&registry, args),
+        Ok(Command::Bench(args)) => run_bench(&registry, args),
+        Ok(Command::List) => {
+            list(&registry);
+            ExitCode::SUCCESS
+        }
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run_bench(registry: &Registry, args: BenchArgs) -> ExitCode {
+    let algorithms = match &args.algorithms {
+        Some(names) => {
+            let mut algorithms = Vec::new();
+            for name in names {
+                match registry.find(name) {
+                    Some(algorithm) => algorithms.push(algorithm),
+                    None => {
+                        eprintln!("error: unknown algorithm '{}' (see --list)", name);
+                        return ExitCode::from(2);
+                    }
+                }
+            }
+            algorithms
+        }
+        // Bogo Sort would only ever time out
+        None => registry
+            .iter()
+            .filter(|a| a.category() != Category::Probabilistic)
+            .cloned()
+            .collect(),
+    };
+
+    let mut config = BenchConfig::new(algorithms);
+    if let Some(sizes) = args.sizes {
+        config.sizes = sizes;
+    }
+    if let Some(distributions) = args.distributions {
+        config.distributions = distributions;
+    }
+    config.trials = args.trials.unwrap_or(config.trials);
+    config.warmup = args.warmup.unwrap_or(config.warmup);
+    config.seed = args.seed.unwrap_or(config.seed);
+    if let Some(secs) = args.time_limit {
+        config.time_limit = Duration::from_secs_f64(secs);
+    }
+
+    let results = bench::run(&config, |r| {
+        eprintln!(
+            "{:>16} {:>10} {:>8}: {} ({:.3} ms ± {:.3})",
+            r.algorithm,
+            r.distribution,
+            r.size,
+            r.status,
+            r.time.mean * 1000.0,
+            r.time.ci95 * 1000.0
+        );
+    });
+
+    let written = match &args.output {
+        Some(path) => File::create(path).and_then(|file| write_results(&results, file, args.json)),
+        None => write_results(&results, io::stdout().lock(), args.json),
+    };
+    if let Err(e) = written {
+        eprintln!("error: could not write results: {}", e);
+        return ExitCode::FAILURE;
+    }
+    if results.iter().all(|r| r.sorted) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn write_results(results: &[bench::BenchResult], out: impl Write, json: bool) -> io::Result<()> {
+    let mut out = BufWriter::new(out);
+    if json {
+        bench::write_json(results, &mut out)?;
+    } else {
+        bench::write_csv(results, &mut out)?;
+    }
+    out.flush()
+}
+
+/// Run `f` on a thread with the benchmark's large stack, as recursive sorts
+/// can go as deep as the array is long and overflow the main thread's.
+fn on_large_stack<T: Send>(f: impl FnOnce() -> T + Send) -> T {
+    thread::scope(|scope| {
+        thread::Builder::new()
+            .stack_size(bench::TRIAL_STACK_SIZE)
+            .spawn_scoped(scope, f)
+            .expect("failed to spawn sort thread")
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+fn run(registry: &Registry, args: Args) -> ExitCode {
+    let Some(algorithm) = registry.find(&args.algorithm) else {
+        eprintln!("error: unknown algorithm '{}' (see --list)", args.algorithm);
+        return ExitCode::from(2);
+    };
+
+    let seed = args.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let input = args
+        .distribution
+        .generate(args.size, &mut StdRng::seed_from_u64(seed));
+    let mut bars: Vec<SortBar> = input.iter().copied().map(SortBar::new).collect();
+
+    if args.wav.is_some() || args.history.is_some() {
+        let recorded =
+            on_large_stack(|| record_trace(algorithm.as_ref(), &bars, CancelToken::new()));
+        let Ok(trace) = recorded else {
+            eprintln!("error: {} was cancelled", algorithm.name());
+            return ExitCode::FAILURE;
+        };
+        let mut written = Vec::new();
+        if let Some(path) = &args.wav {
+            let result = export_run(&bars, &trace, &args.sound, path.as_ref());
+            written.push((path, result));
+        }
+        if let Some(path) = &args.history {
+            let every = history::ops_per_row(work_len(&trace), history::DEFAULT_ROWS);
+            written.push((
+                path,
+                history::history_image(&bars, &trace, every).save(path),
+            ));
+        }
+        for (path, result) in written {
+            if let Err(e) = result {
+                eprintln!("error: could not write {}: {}", path, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let sink = if args.count {
+        Sink::counting()
+    } else {
+        Sink::discard()
+    };
+    // Nothing can cancel a headless run, so this only fails if an
+    // algorithm misbehaves. The sink can't be shared with the sort thread,
+    // so it goes there and back.
+    let (result, elapsed, sink) = on_large_stack(|| {
+        let start = Instant::now();
+        let result = run_algorithm(algorithm.as_ref(), &mut bars, &sink);
+        (result, start.elapsed(), sink)
+    });
+    if result.is_err() {
+        eprintln!("error: {} was cancelled", algorithm.name());
+        return ExitCode::FAILURE;
+    }
+
+    let output: Vec<usize> = bars.iter().map(|b| b.value).collect();
+    let mut expected = input;
+    expected.sort_unstable();
+    let sorted = output == expected;
+
+    let report = Report {
+        algorithm: algorithm.name(),
+        args: &args,
+        seed,
+        sorted,
+        elapsed,
+        stats: sink.stats(),
+        output: &output,
+    };
+    match args.format {
+        Format::Text => report.print_text(),
+        Format::Json => report.print_json(),
+    }
+
+    if sorted {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+struct Report<'a> {
+    algorithm: &'a str,
+    args: &'a Args,
+    seed: u64,
+    sorted: bool,
+    elapsed: Duration,
+    stats: Option<Stats>,
+    output: &'a [usize],
+}
+
+impl Report<'_> {
+    fn print_text(&self) {
+        println!("algorithm:    {}", self.algorithm);
+        println!("size:         {}", self.args.size);
+        println!("distribution: {}", self.args.distribution);
+        println!("seed:         {}", self.seed);
+        println!("sorted:       {}", self.sorted);
+        println!(
+            "elapsed:      {:.3} ms",
+            self.elapsed.as_secs_f64() * 1000.0
+        );
+        if let Some(stats) = &self.stats {
+            println!("comparisons:  {}", stats.comparisons);
+            println!("swaps:        {}", stats.swaps);
+            println!("writes:       {}", stats.writes);
+            println!("reads:        {}", stats.reads);
+            println!("peak aux:     {}", stats.peak_aux);
+            println!("max depth:    {}", stats.max_depth);
+        }
+        if self.args.print_array {
+            let values: Vec<String> = self.output.iter().map(|v| v.to_string()).collect();
+            println!("values:       {}", values.join(" "));
+        }
+    }
+
+    fn print_json(&self) {
+        let mut fields = vec![
+            format!("\"algorithm\":{}", json::quote(self.algorithm)),
+            format!("\"size\":{}", self.args.size),
+            format!(
+                "\"distribution\":{}",
+                json::quote(&self.args.distribution.to_string())
+            ),
+            format!("\"seed\":{}", self.seed),
+            format!("\"sorted\":{}", self.sorted),
+            format!("\"elapsed_ms\":{}", self.elapsed.as_secs_f64() * 1000.0),
+        ];
+        if let Some(stats) = &self.stats {
+            fields.push(format!("\"comparisons\":{}", stats.comparisons));
+            fields.push(format!("\"swaps\":{}", stats.swaps));
+            fields.push(format!("\"writes\":{}", stats.writes));
+            fields.push(format!("\"reads\":{}", stats.reads));
+            fields.push(format!("\"peak_aux\":{}", stats.peak_aux));
+            fields.push(format!("\"max_depth\":{}", stats.max_depth));
+        }
+        if self.args.print_array {
+            let values: Vec<String> = self.output.iter().map(|v| v.to_string()).collect();
+            fields.push(format!("\"values\":[{}]", values.join(",")));
+        }
+        println!("{{{}}}", fields.join(","));
+    }
+}
@@ -1,33 +1,107 @@
 use crate::models::SortBar;
-use crate::sorting::Operation;
-use eframe::egui::{self};
-use std::sync::mpsc;
-
-/// Attempts to use all available CPU power to sort as fast as possible.
-/// This function will parallelize sorting if possible, using rayon or threads.
-/// It is designed to be triggered by a "Max" button in the top bar.
-pub fn max_speed_sort(bars: &mut [SortBar], tx: mpsc::Sender<Operation>) {
-    // If the array is very small, just use a fast single-threaded sort.
-    if bars.len() < 2048 {
-        // Use Rust's standard sort (unstable, but fastest)
-        let mut values: Vec<usize> = bars.iter().map(|b| b.value).collect();
+use crate::sorting::{Cancelled, Operation, Sink};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Fewest elements a thread is given, so that even the few hundred bars on
+/// screen are split across threads, but none gets a chunk of a handful.
+const MIN_CHUNK: usize = 64;
+
+/// How a max speed sort went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxSpeedReport {
+    /// Threads that sorted chunks of the array.
+    pub threads: usize,
+    /// Wall time of the sort itself, without handing the result to the display.
+    pub elapsed: Duration,
+}
+
+/// Threads worth using on this machine.
+pub fn available_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Sort `values` using up to `threads` scoped threads: every thread sorts
+/// its own chunk, then neighbouring runs are merged in rounds, each merge of
+/// a round on its own thread. Returns the number of threads used, which is
+/// 1 for arrays shorter than two chunks of `MIN_CHUNK`.
+pub fn parallel_sort(values: &mut [usize], threads: usize) -> usize {
+    let n = values.len();
+    let threads = threads.min(n / MIN_CHUNK).max(1);
+    if threads == 1 {
         values.sort_unstable();
-        for (i, v) in values.into_iter().enumerate() {
-            bars[i].value = v;
-            let _ = tx.send(Operation::SetColor(i, egui::Color32::WHITE));
+        return 1;
+    }
+
+    let chunk = n.div_ceil(threads);
+    thread::scope(|s| {
+        for part in values.chunks_mut(chunk) {
+            s.spawn(|| part.sort_unstable());
         }
-        let _ = tx.send(Operation::Done);
-        return;
+    });
+
+    // Merge back and forth between `values` and `buffer`, doubling the run
+    // width every round
+    let mut buffer = vec![0; n];
+    let mut in_values = true;
+    let mut width = chunk;
+    while width < n {
+        let (src, dst): (&[usize], &mut [usize]) = if in_values {
+            (values, &mut buffer)
+        } else {
+            (&buffer, values)
+        };
+        thread::scope(|s| {
+            for (runs, out) in src.chunks(2 * width).zip(dst.chunks_mut(2 * width)) {
+                s.spawn(move || {
+                    let (left, right) = runs.split_at(width.min(runs.len()));
+                    merge(left, right, out);
+                });
+            }
+        });
+        in_values = !in_values;
+        width *= 2;
     }
+    if !in_values {
+        values.copy_from_slice(&buffer);
+    }
+    threads
+}
+
+/// Merge the sorted slices `left` and `right` into `out`.
+fn merge(left: &[usize], right: &[usize], out: &mut [usize]) {
+    let (mut i, mut j) = (0, 0);
+    for slot in out.iter_mut() {
+        if j == right.len() || (i < left.len() && left[i] <= right[j]) {
+            *slot = left[i];
+            i += 1;
+        } else {
+            *slot = right[j];
+            j += 1;
+        }
+    }
+}
 
-    // For larger arrays, use parallel sorting if rayon is available.
-    // Parallel sort is not implemented due to Rust's thread safety and lifetime requirements.
-    // For now, use a single-threaded sort for maximum speed.
+/// Sort `bars` as fast as the machine allows with `parallel_sort`, then hand
+/// the result to the display as one `Overwrite` per element that moved.
+/// Meant to run on a worker thread, like any algorithm's `run`.
+pub fn max_speed_sort(
+    bars: &[SortBar],
+    threads: usize,
+    sink: &Sink,
+) -> Result<MaxSpeedReport, Cancelled> {
     let mut values: Vec<usize> = bars.iter().map(|b| b.value).collect();
-    values.sort_unstable();
-    for (i, v) in values.into_iter().enumerate() {
-        bars[i].value = v;
-        let _ = tx.send(Operation::SetColor(i, egui::Color32::WHITE));
+    let start = Instant::now();
+    let threads = parallel_sort(&mut values, threads);
+    let report = MaxSpeedReport {
+        threads,
+        elapsed: start.elapsed(),
+    };
+
+    for (i, (bar, value)) in bars.iter().zip(values).enumerate() {
+        if bar.value != value {
+            sink.send(Operation::Overwrite(i, SortBar::new(value)))?;
+        }
     }
-    let _ = tx.send(Operation::Done);
+    Ok(report)
 }
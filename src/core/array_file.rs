@@ -0,0 +1,154 @@
+//! Reading and writing bar values as CSV, JSON or newline-separated text.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Largest array we accept from a file; beyond this the bars are too thin
+/// to see and the slower sorts take minutes to play back.
+pub const MAX_LEN: usize = 10_000;
+
+/// Largest value we accept from a file, as bars scaled to a larger maximum
+/// flatten the small values.
+pub const MAX_VALUE: usize = 1_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrayFormat {
+    /// Values separated by commas and/or newlines.
+    Csv,
+    /// A JSON array of non-negative integers.
+    Json,
+    /// One value per line.
+    Lines,
+}
+
+impl ArrayFormat {
+    /// Pick the format from a file extension; anything but `.csv` and
+    /// `.json` is read as one value per line.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(e) if e.eq_ignore_ascii_case("csv") => ArrayFormat::Csv,
+            Some(e) if e.eq_ignore_ascii_case("json") => ArrayFormat::Json,
+            _ => ArrayFormat::Lines,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ArrayFileError {
+    Io(io::Error),
+    Empty,
+    TooLong(usize),
+    /// The entry at this (1-based) position is not a non-negative integer.
+    InvalidValue {
+        position: usize,
+        text: String,
+    },
+    NotJsonArray,
+    /// The value at this (1-based) position is above `MAX_VALUE`.
+    TooLarge {
+        position: usize,
+        value: usize,
+    },
+}
+
+impl fmt::Display for ArrayFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArrayFileError::Io(e) => write!(f, "{}", e),
+            ArrayFileError::Empty => write!(f, "file contains no values"),
+            ArrayFileError::TooLong(n) => {
+                write!(f, "{} values is more than the limit of {}", n, MAX_LEN)
+            }
+            ArrayFileError::InvalidValue { position, text } => write!(
+                f,
+                "value {} ('{}') is not a non-negative integer",
+                position, text
+            ),
+            ArrayFileError::NotJsonArray => write!(f, "expected a JSON array like [3, 1, 2]"),
+            ArrayFileError::TooLarge { position, value } => write!(
+                f,
+                "value {} ({}) is more than the limit of {}",
+                position, value, MAX_VALUE
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ArrayFileError {}
+
+impl From<io::Error> for ArrayFileError {
+    fn from(e: io::Error) -> Self {
+        ArrayFileError::Io(e)
+    }
+}
+
+/// Parse `text` as `format`, checking every value.
+pub fn parse(text: &str, format: ArrayFormat) -> Result<Vec<usize>, ArrayFileError> {
+    let items: Vec<&str> = match format {
+        ArrayFormat::Csv => text.split([',', '\n']).collect(),
+        ArrayFormat::Lines => text.lines().collect(),
+        ArrayFormat::Json => {
+            let inner = text
+                .trim()
+                .strip_prefix('[')
+                .and_then(|t| t.strip_suffix(']'))
+                .ok_or(ArrayFileError::NotJsonArray)?;
+            if inner.trim().is_empty() {
+                return Err(ArrayFileError::Empty);
+            }
+            // Unlike CSV, empty entries ("[1,,2]") are malformed JSON
+            inner.split(',').collect()
+        }
+    };
+
+    let mut values = Vec::new();
+    for item in items {
+        let item = item.trim();
+        if item.is_empty() && format != ArrayFormat::Json {
+            continue;
+        }
+        let value = item.parse().map_err(|_| ArrayFileError::InvalidValue {
+            position: values.len() + 1,
+            text: item.to_string(),
+        })?;
+        if value > MAX_VALUE {
+            return Err(ArrayFileError::TooLarge {
+                position: values.len() + 1,
+                value,
+            });
+        }
+        values.push(value);
+    }
+
+    if values.is_empty() {
+        Err(ArrayFileError::Empty)
+    } else if values.len() > MAX_LEN {
+        Err(ArrayFileError::TooLong(values.len()))
+    } else {
+        Ok(values)
+    }
+}
+
+/// Render `values` as `format`, ending with a newline.
+pub fn format(values: &[usize], format: ArrayFormat) -> String {
+    let items: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    match format {
+        ArrayFormat::Csv => format!("{}\n", items.join(",")),
+        ArrayFormat::Json => format!("[{}]\n", items.join(", ")),
+        ArrayFormat::Lines => items.iter().map(|v| format!("{}\n", v)).collect(),
+    }
+}
+
+/// Read the values in `path`, with the format taken from its extension.
+pub fn load(path: &Path) -> Result<Vec<usize>, ArrayFileError> {
+    let text = fs::read_to_string(path)?;
+    parse(&text, ArrayFormat::from_path(path))
+}
+
+/// Write `values` to `path`, with the format taken from its extension.
+pub fn save(path: &Path, values: &[usize]) -> Result<(), ArrayFileError> {
+    fs::write(path, format(values, ArrayFormat::from_path(path)))?;
+    Ok(())
+}
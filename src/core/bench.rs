@@ -0,0 +1,343 @@
+//! Benchmark harness: every algorithm over a grid of sizes and input shapes.
+//!
+//! Each trial runs an algorithm twice on the same input: once into a
+//! discarding `Sink` for the wall-clock time, so no channel sends or playback
+//! are measured, and once into a counting `Sink` for the operation counts.
+
+use crate::input::Distribution;
+use crate::json;
+use crate::models::SortBar;
+use crate::sorting::{run_algorithm, CancelToken, Cancelled, Sink, SortAlgorithm};
+use crate::stats::Stats;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Stack for trial threads, and for anything else running sorts on large
+/// arrays. Recursive sorts (Quick Sort on sorted input) go as deep as the
+/// array is long before the time limit stops them.
+pub const TRIAL_STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// What to run.
+#[derive(Clone)]
+pub struct BenchConfig {
+    pub algorithms: Vec<Arc<dyn SortAlgorithm>>,
+    pub sizes: Vec<usize>,
+    pub distributions: Vec<Distribution>,
+    /// Untimed runs before the measured trials of each cell.
+    pub warmup: usize,
+    pub trials: usize,
+    pub seed: u64,
+    /// A trial still running after this long is cancelled, and the
+    /// algorithm skips the larger sizes of that distribution.
+    pub time_limit: Duration,
+}
+
+impl BenchConfig {
+    /// The default grid: sizes from 16 to 1,000,000 and every distribution.
+    pub fn new(algorithms: Vec<Arc<dyn SortAlgorithm>>) -> Self {
+        Self {
+            algorithms,
+            sizes: vec![16, 256, 4_096, 65_536, 1_000_000],
+            distributions: Distribution::all().to_vec(),
+            warmup: 1,
+            trials: 5,
+            seed: 0,
+            time_limit: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Mean, standard deviation and 95% confidence half-width of some samples.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Summary {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub ci95: f64,
+}
+
+impl Summary {
+    /// Summarise `samples` using Student's t distribution for the interval.
+    /// Fewer than two samples give a zero-width interval.
+    pub fn of(samples: &[f64]) -> Self {
+        let n = samples.len();
+        if n == 0 {
+            return Self::default();
+        }
+        let mean = samples.iter().sum::<f64>() / n as f64;
+        if n == 1 {
+            return Self {
+                mean,
+                ..Self::default()
+            };
+        }
+        let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+        let std_dev = variance.sqrt();
+        Self {
+            mean,
+            std_dev,
+            ci95: t_critical_95(n - 1) * std_dev / (n as f64).sqrt(),
+        }
+    }
+}
+
+/// Two-sided 95% critical value of Student's t with `df` degrees of freedom.
+fn t_critical_95(df: usize) -> f64 {
+    const TABLE: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+        2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+        2.052, 2.048, 2.045, 2.042,
+    ];
+    match df {
+        0 => f64::NAN,
+        1..=30 => TABLE[df - 1],
+        _ => 1.960,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Complete,
+    /// A trial hit the time limit; only the trials before it are summarised.
+    TimedOut,
+    /// Not run because a smaller size of the same distribution timed out.
+    Skipped,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Complete => write!(f, "ok"),
+            Status::TimedOut => write!(f, "timeout"),
+            Status::Skipped => write!(f, "skipped"),
+        }
+    }
+}
+
+/// One cell of the grid: an algorithm on one size and distribution.
+#[derive(Debug, Clone)]
+pub struct BenchResult {
+    pub algorithm: &'static str,
+    pub distribution: Distribution,
+    pub size: usize,
+    pub status: Status,
+    /// Completed timed trials.
+    pub trials: usize,
+    /// Whether every completed trial produced sorted output.
+    pub sorted: bool,
+    /// Wall-clock seconds per trial.
+    pub time: Summary,
+    pub comparisons: Summary,
+    pub swaps: Summary,
+    pub writes: Summary,
+    pub reads: Summary,
+}
+
+struct Trial {
+    elapsed: Duration,
+    stats: Stats,
+    sorted: bool,
+}
+
+/// Time and count `algorithm` on `input`, giving up after `limit`.
+fn run_trial(
+    algorithm: &Arc<dyn SortAlgorithm>,
+    input: &[usize],
+    limit: Duration,
+) -> Result<Trial, Cancelled> {
+    let cancel = CancelToken::new();
+    let (tx, rx) = mpsc::channel();
+    let handle = {
+        let algorithm = Arc::clone(algorithm);
+        let input = input.to_vec();
+        let cancel = cancel.clone();
+        thread::Builder::new()
+            .stack_size(TRIAL_STACK_SIZE)
+            .spawn(move || {
+                let mut bars: Vec<SortBar> = input.iter().copied().map(SortBar::new).collect();
+                let sink = Sink::discard().with_cancel(cancel.clone());
+                let start = Instant::now();
+                let result = run_algorithm(algorithm.as_ref(), &mut bars, &sink);
+                let elapsed = start.elapsed();
+                let trial = result.and_then(|()| {
+                    let mut expected = input.clone();
+                    expected.sort_unstable();
+                    let sorted = bars.iter().map(|b| b.value).eq(expected);
+
+                    let mut bars: Vec<SortBar> = input.into_iter().map(SortBar::new).collect();
+                    let sink = Sink::counting().with_cancel(cancel);
+                    run_algorithm(algorithm.as_ref(), &mut bars, &sink)?;
+                    Ok(Trial {
+                        elapsed,
+                        stats: sink.stats().unwrap_or_default(),
+                        sorted,
+                    })
+                });
+                let _ = tx.send(trial);
+            })
+            .expect("failed to spawn benchmark thread")
+    };
+
+    let trial = rx.recv_timeout(limit).unwrap_or_else(|_| {
+        cancel.cancel();
+        Err(Cancelled)
+    });
+    let _ = handle.join();
+    trial
+}
+
+/// Run the whole grid, calling `progress` after each cell.
+///
+/// Every algorithm sees the same inputs for a given size and distribution.
+pub fn run(config: &BenchConfig, mut progress: impl FnMut(&BenchResult)) -> Vec<BenchResult> {
+    let mut results = Vec::new();
+    // (algorithm, distribution) pairs that hit the time limit
+    let mut gave_up: HashSet<(&'static str, Distribution)> = HashSet::new();
+
+    for &size in &config.sizes {
+        for (d, &distribution) in config.distributions.iter().enumerate() {
+            let mut rng = StdRng::seed_from_u64(
+                config
+                    .seed
+                    .wrapping_add((size as u64) << 8)
+                    .wrapping_add(d as u64),
+            );
+            let inputs: Vec<Vec<usize>> = (0..config.warmup + config.trials)
+                .map(|_| distribution.generate(size, &mut rng))
+                .collect();
+
+            for algorithm in &config.algorithms {
+                let mut result = BenchResult {
+                    algorithm: algorithm.name(),
+                    distribution,
+                    size,
+                    status: Status::Complete,
+                    trials: 0,
+                    sorted: true,
+                    time: Summary::default(),
+                    comparisons: Summary::default(),
+                    swaps: Summary::default(),
+                    writes: Summary::default(),
+                    reads: Summary::default(),
+                };
+                if gave_up.contains(&(algorithm.name(), distribution)) {
+                    result.status = Status::Skipped;
+                    progress(&result);
+                    results.push(result);
+                    continue;
+                }
+
+                let mut trials = Vec::new();
+                for (i, input) in inputs.iter().enumerate() {
+                    match run_trial(algorithm, input, config.time_limit) {
+                        Ok(trial) if i >= config.warmup => trials.push(trial),
+                        Ok(_) => {}
+                        Err(Cancelled) => {
+                            result.status = Status::TimedOut;
+                            gave_up.insert((algorithm.name(), distribution));
+                            break;
+                        }
+                    }
+                }
+
+                let summary = |f: &dyn Fn(&Trial) -> f64| {
+                    Summary::of(&trials.iter().map(f).collect::<Vec<_>>())
+                };
+                result.trials = trials.len();
+                result.sorted = trials.iter().all(|t| t.sorted);
+                result.time = summary(&|t| t.elapsed.as_secs_f64());
+                result.comparisons = summary(&|t| t.stats.comparisons as f64);
+                result.swaps = summary(&|t| t.stats.swaps as f64);
+                result.writes = summary(&|t| t.stats.writes as f64);
+                result.reads = summary(&|t| t.stats.reads as f64);
+                progress(&result);
+                results.push(result);
+            }
+        }
+    }
+    results
+}
+
+/// Write `results` as CSV with a header row. Cells without completed
+/// trials leave their measurements empty.
+pub fn write_csv(results: &[BenchResult], mut out: impl Write) -> io::Result<()> {
+    writeln!(
+        out,
+        "algorithm,distribution,size,status,trials,sorted,\
+         time_mean_s,time_std_s,time_ci95_s,\
+         comparisons_mean,comparisons_ci95,swaps_mean,swaps_ci95,\
+         writes_mean,writes_ci95,reads_mean,reads_ci95"
+    )?;
+    for r in results {
+        write!(
+            out,
+            "{},{},{},{},{},{}",
+            r.algorithm, r.distribution, r.size, r.status, r.trials, r.sorted
+        )?;
+        if r.trials == 0 {
+            writeln!(out, "{}", ",".repeat(11))?;
+            continue;
+        }
+        writeln!(
+            out,
+            ",{},{},{},{},{},{},{},{},{},{},{}",
+            r.time.mean,
+            r.time.std_dev,
+            r.time.ci95,
+            r.comparisons.mean,
+            r.comparisons.ci95,
+            r.swaps.mean,
+            r.swaps.ci95,
+            r.writes.mean,
+            r.writes.ci95,
+            r.reads.mean,
+            r.reads.ci95
+        )?;
+    }
+    Ok(())
+}
+
+/// Write `results` as a JSON array with one object per cell. Cells without
+/// completed trials have `null` measurements.
+pub fn write_json(results: &[BenchResult], mut out: impl Write) -> io::Result<()> {
+    let summary = |s: &Summary, trials: usize| {
+        if trials == 0 {
+            "null".to_string()
+        } else {
+            format!(
+                "{{\"mean\":{},\"std_dev\":{},\"ci95\":{}}}",
+                json::number(s.mean),
+                json::number(s.std_dev),
+                json::number(s.ci95)
+            )
+        }
+    };
+    writeln!(out, "[")?;
+    for (i, r) in results.iter().enumerate() {
+        let separator = if i + 1 < results.len() { "," } else { "" };
+        writeln!(
+            out,
+            "  {{\"algorithm\":{},\"distribution\":{},\"size\":{},\"status\":{},\
+             \"trials\":{},\"sorted\":{},\"time_s\":{},\"comparisons\":{},\
+             \"swaps\":{},\"writes\":{},\"reads\":{}}}{}",
+            json::quote(r.algorithm),
+            json::quote(&r.distribution.to_string()),
+            r.size,
+            json::quote(&r.status.to_string()),
+            r.trials,
+            r.sorted,
+            summary(&r.time, r.trials),
+            summary(&r.comparisons, r.trials),
+            summary(&r.swaps, r.trials),
+            summary(&r.writes, r.trials),
+            summary(&r.reads, r.trials),
+            separator
+        )?;
+    }
+    writeln!(out, "]")
+}
@@ -0,0 +1,219 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
+
+/// Shape of a generated input array. Values are always in `0..n`.
+///
+/// Some shapes take a parameter; on the command line it follows the name
+/// after a colon, e.g. `few-unique:3`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Distribution {
+    /// A random permutation of `0..n`.
+    #[default]
+    Shuffled,
+    /// `0..n` in ascending order.
+    Sorted,
+    /// `0..n` in descending order.
+    Reversed,
+    /// About `n / 3` distinct values, shuffled (the GUI's "Generate Duplicates").
+    Duplicates,
+    /// Independent uniform values, so duplicates appear by chance.
+    Random,
+    /// Sorted, then this many random pairs swapped.
+    NearlySorted { swaps: usize },
+    /// This many ascending ramps one after another.
+    Sawtooth { teeth: usize },
+    /// Ascending to the middle, then descending.
+    OrganPipe,
+    /// Shuffled values drawn from only this many distinct keys.
+    FewUnique { unique: usize },
+    /// Normally distributed around `n / 2`, clamped to `0..n`.
+    Gaussian,
+    /// Mostly small values with a long tail of large ones.
+    Skewed,
+    /// A sorted prefix followed by this many random values.
+    SortedWithTail { tail: usize },
+    /// Random values cut into this many individually sorted runs.
+    RandomRuns { runs: usize },
+}
+
+impl Distribution {
+    /// Every shape, parameterised ones with their default parameter.
+    pub fn all() -> &'static [Distribution] {
+        &[
+            Distribution::Shuffled,
+            Distribution::Sorted,
+            Distribution::Reversed,
+            Distribution::Duplicates,
+            Distribution::Random,
+            Distribution::NearlySorted { swaps: 4 },
+            Distribution::Sawtooth { teeth: 4 },
+            Distribution::OrganPipe,
+            Distribution::FewUnique { unique: 5 },
+            Distribution::Gaussian,
+            Distribution::Skewed,
+            Distribution::SortedWithTail { tail: 8 },
+            Distribution::RandomRuns { runs: 8 },
+        ]
+    }
+
+    /// Short name used on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Distribution::Shuffled => "shuffled",
+            Distribution::Sorted => "sorted",
+            Distribution::Reversed => "reversed",
+            Distribution::Duplicates => "duplicates",
+            Distribution::Random => "random",
+            Distribution::NearlySorted { .. } => "nearly-sorted",
+            Distribution::Sawtooth { .. } => "sawtooth",
+            Distribution::OrganPipe => "organ-pipe",
+            Distribution::FewUnique { .. } => "few-unique",
+            Distribution::Gaussian => "gaussian",
+            Distribution::Skewed => "skewed",
+            Distribution::SortedWithTail { .. } => "sorted-tail",
+            Distribution::RandomRuns { .. } => "random-runs",
+        }
+    }
+
+    /// The shape's parameter and what it means, if it has one.
+    pub fn parameter(self) -> Option<(&'static str, usize)> {
+        match self {
+            Distribution::NearlySorted { swaps } => Some(("swaps", swaps)),
+            Distribution::Sawtooth { teeth } => Some(("teeth", teeth)),
+            Distribution::FewUnique { unique } => Some(("unique values", unique)),
+            Distribution::SortedWithTail { tail } => Some(("tail length", tail)),
+            Distribution::RandomRuns { runs } => Some(("runs", runs)),
+            _ => None,
+        }
+    }
+
+    /// Mutable access to the parameter, for editing it in place.
+    pub fn parameter_mut(&mut self) -> Option<&mut usize> {
+        match self {
+            Distribution::NearlySorted { swaps: p }
+            | Distribution::Sawtooth { teeth: p }
+            | Distribution::FewUnique { unique: p }
+            | Distribution::SortedWithTail { tail: p }
+            | Distribution::RandomRuns { runs: p } => Some(p),
+            _ => None,
+        }
+    }
+
+    /// Generate `n` values. The result only depends on the state of `rng`,
+    /// so a seeded generator reproduces the same input.
+    pub fn generate<R: Rng + ?Sized>(self, n: usize, rng: &mut R) -> Vec<usize> {
+        if n == 0 {
+            return Vec::new();
+        }
+        match self {
+            Distribution::Shuffled => {
+                let mut values: Vec<usize> = (0..n).collect();
+                values.shuffle(rng);
+                values
+            }
+            Distribution::Sorted => (0..n).collect(),
+            Distribution::Reversed => (0..n).rev().collect(),
+            Distribution::Duplicates => {
+                let unique_values = (n / 3).max(1);
+                let mut values: Vec<usize> = (0..n).map(|i| i % unique_values).collect();
+                values.shuffle(rng);
+                values
+            }
+            Distribution::Random => (0..n).map(|_| rng.gen_range(0..n)).collect(),
+            Distribution::NearlySorted { swaps } => {
+                let mut values: Vec<usize> = (0..n).collect();
+                for _ in 0..swaps {
+                    let i = rng.gen_range(0..n);
+                    let j = rng.gen_range(0..n);
+                    values.swap(i, j);
+                }
+                values
+            }
+            Distribution::Sawtooth { teeth } => {
+                // Each ramp spans the full value range
+                let width = n.div_ceil(teeth.clamp(1, n));
+                (0..n).map(|i| (i % width) * n / width).collect()
+            }
+            Distribution::OrganPipe => (0..n)
+                .map(|i| {
+                    let rise = i.min(n - 1 - i);
+                    // Step by two so the peak reaches the top of the range
+                    (2 * rise).min(n - 1)
+                })
+                .collect(),
+            Distribution::FewUnique { unique } => {
+                let unique = unique.clamp(1, n);
+                (0..n)
+                    .map(|_| rng.gen_range(0..unique) * n / unique)
+                    .collect()
+            }
+            Distribution::Gaussian => (0..n)
+                .map(|_| {
+                    // Box-Muller transform
+                    let u1: f64 = 1.0 - rng.gen::<f64>();
+                    let u2: f64 = rng.gen();
+                    let z = (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos();
+                    let value = n as f64 / 2.0 + z * n as f64 / 6.0;
+                    value.clamp(0.0, (n - 1) as f64) as usize
+                })
+                .collect(),
+            Distribution::Skewed => (0..n)
+                .map(|_| {
+                    let u: f64 = rng.gen();
+                    ((u * u * u * n as f64) as usize).min(n - 1)
+                })
+                .collect(),
+            Distribution::SortedWithTail { tail } => {
+                let tail = tail.min(n);
+                let head = n - tail;
+                let mut values: Vec<usize> = (0..head).map(|i| i * n / head.max(1)).collect();
+                values.extend((0..tail).map(|_| rng.gen_range(0..n)));
+                values
+            }
+            Distribution::RandomRuns { runs } => {
+                let width = n.div_ceil(runs.clamp(1, n));
+                let mut values: Vec<usize> = (0..n).map(|_| rng.gen_range(0..n)).collect();
+                for run in values.chunks_mut(width) {
+                    run.sort_unstable();
+                }
+                values
+            }
+        }
+    }
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.parameter() {
+            Some((_, value)) => write!(f, "{}:{}", self.name(), value),
+            None => write!(f, "{}", self.name()),
+        }
+    }
+}
+
+impl FromStr for Distribution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, parameter) = match s.split_once(':') {
+            Some((name, parameter)) => (name, Some(parameter)),
+            None => (s, None),
+        };
+        let mut distribution = Distribution::all()
+            .iter()
+            .copied()
+            .find(|d| d.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("unknown distribution '{}'", name))?;
+        if let Some(parameter) = parameter {
+            let slot = distribution
+                .parameter_mut()
+                .ok_or_else(|| format!("distribution '{}' takes no parameter", name))?;
+            *slot = parameter
+                .parse()
+                .map_err(|_| format!("invalid parameter '{}' for '{}'", parameter, name))?;
+        }
+        Ok(distribution)
+    }
+}
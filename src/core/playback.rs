@@ -0,0 +1,798 @@
+use crate::models::SortBar;
+use crate::sorting::{AuxId, Operation};
+use crate::stats::Stats;
+use eframe::egui::Color32;
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::time::Duration;
+
+/// How fast a recorded trace is replayed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlaybackSpeed {
+    OpsPerSecond(f32),
+    OpsPerFrame(usize),
+}
+
+impl Default for PlaybackSpeed {
+    fn default() -> Self {
+        PlaybackSpeed::OpsPerSecond(300.0)
+    }
+}
+
+/// How many of the latest applied operations `Playback` can step back.
+/// Older undo records are dropped, so a long run doesn't keep one for every
+/// operation.
+pub const UNDO_LIMIT: usize = 1 << 20;
+
+/// What an applied operation overwrote, so it can be stepped back.
+///
+/// One is kept for each of up to `UNDO_LIMIT` operations, so it only holds
+/// what the common operations change, read alongside the operation itself.
+/// Anything larger goes in a `Snapshot`.
+#[derive(Debug, Clone, Default)]
+pub enum Undo {
+    /// Nothing `Undo` tracks changed.
+    #[default]
+    Nothing,
+    /// The colours of the two bars a `Compare` or `Swap` names, as they
+    /// were before it.
+    Colors(Color32, Color32),
+    /// The bar an `Overwrite` or `SetColor` replaced.
+    Bar(SortBar),
+    /// The pseudocode line before a `Line`.
+    Line(Option<usize>),
+    /// The value an `AuxWrite` replaced, and the element its array had
+    /// marked as touched before.
+    AuxWrite(usize, Option<(usize, Color32)>),
+    /// The element an `AuxRead`'s array had marked as touched before.
+    AuxRead(Option<(usize, Color32)>),
+}
+
+impl Undo {
+    fn restore(self, op: &Operation, bars: &mut [SortBar]) {
+        match (self, op) {
+            (Undo::Colors(first, second), &Operation::Compare(i, j)) => {
+                bars[i].color = first;
+                bars[j].color = second;
+            }
+            (Undo::Colors(first, second), &Operation::Swap(i, j)) => {
+                bars.swap(i, j);
+                bars[i].color = first;
+                bars[j].color = second;
+            }
+            (Undo::Bar(bar), &Operation::Overwrite(i, _) | &Operation::SetColor(i, _)) => {
+                bars[i] = bar
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The rarer and larger things an operation changed, kept in a side table
+/// next to its `Undo`. Fields are `None` where the operation left that
+/// thing alone.
+#[derive(Debug, Clone, Default)]
+struct Snapshot {
+    // The stats before the operation, where `Stats::unrecord` wouldn't give
+    // them back: a raised peak, or a depth or aux count held at zero
+    stats: Option<Stats>,
+    // A dropped auxiliary array and where it was in the list
+    dropped: Option<(usize, AuxState)>,
+    note: Option<Option<Note>>,
+    rotation: Option<Option<Rotation>>,
+    keys: Option<Option<Keys>>,
+    block_merge: Option<Option<BlockMerge>>,
+    heap: Option<Option<HeapView>>,
+    // Whether a swap took the sift-down path a level further
+    sifted: bool,
+    network: Option<Option<NetworkView>>,
+}
+
+impl Snapshot {
+    fn is_empty(&self) -> bool {
+        self.stats.is_none()
+            && self.dropped.is_none()
+            && self.note.is_none()
+            && self.rotation.is_none()
+            && self.keys.is_none()
+            && self.block_merge.is_none()
+            && self.heap.is_none()
+            && !self.sifted
+            && self.network.is_none()
+    }
+}
+
+/// An auxiliary array as the replayed operations left it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuxState {
+    pub id: AuxId,
+    pub name: &'static str,
+    pub values: Vec<usize>,
+    /// The element last read or written, with its highlight colour.
+    pub touched: Option<(usize, Color32)>,
+}
+
+/// Apply an `Aux*` operation to `arrays`; other operations change nothing.
+/// Reads and writes are undone with the returned `Undo`, and a drop with
+/// the array it removes, returned alongside its position.
+fn apply_aux(op: &Operation, arrays: &mut Vec<AuxState>) -> (Undo, Option<(usize, AuxState)>) {
+    let find = |arrays: &[AuxState], id: AuxId| arrays.iter().position(|a| a.id == id);
+    match *op {
+        Operation::AuxCreate(id, name, len) => arrays.push(AuxState {
+            id,
+            name,
+            values: vec![0; len],
+            touched: None,
+        }),
+        Operation::AuxWrite(id, index, value) => {
+            if let Some(k) = find(arrays, id) {
+                let array = &mut arrays[k];
+                let old = std::mem::replace(&mut array.values[index], value);
+                let touched = array.touched.replace((index, Color32::GREEN));
+                return (Undo::AuxWrite(old, touched), None);
+            }
+        }
+        Operation::AuxRead(id, index) => {
+            if let Some(k) = find(arrays, id) {
+                let touched = arrays[k].touched.replace((index, Color32::YELLOW));
+                return (Undo::AuxRead(touched), None);
+            }
+        }
+        Operation::AuxDrop(id, _) => {
+            if let Some(k) = find(arrays, id) {
+                return (Undo::Nothing, Some((k, arrays.remove(k))));
+            }
+        }
+        _ => {}
+    }
+    (Undo::Nothing, None)
+}
+
+/// Step `arrays` back from `op`, given what `apply_aux` returned for it.
+fn unapply_aux(
+    op: &Operation,
+    undo: &Undo,
+    dropped: Option<(usize, AuxState)>,
+    arrays: &mut Vec<AuxState>,
+) {
+    match (op, undo) {
+        (&Operation::AuxCreate(id, ..), _) => arrays.retain(|a| a.id != id),
+        (&Operation::AuxWrite(id, index, _), &Undo::AuxWrite(old, touched)) => {
+            if let Some(array) = arrays.iter_mut().find(|a| a.id == id) {
+                array.values[index] = old;
+                array.touched = touched;
+            }
+        }
+        (&Operation::AuxRead(id, _), &Undo::AuxRead(touched)) => {
+            if let Some(array) = arrays.iter_mut().find(|a| a.id == id) {
+                array.touched = touched;
+            }
+        }
+        (&Operation::AuxDrop(..), _) => {
+            if let Some((position, array)) = dropped {
+                arrays.insert(position, array);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// What an algorithm said, with `Operation::Note`, about how it is
+/// handling a range of elements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Note {
+    pub range: Range<usize>,
+    pub text: &'static str,
+}
+
+/// A boundary between two neighbouring runs, weighed by Powersort. The
+/// smaller the power, the nearer the root of the merge tree the boundary
+/// sits, and the later its runs are merged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PowerNode {
+    pub left: Range<usize>,
+    pub right: Range<usize>,
+    pub power: usize,
+}
+
+/// Two neighbouring pieces of the array trading places, announced with
+/// `Operation::Rotate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rotation {
+    pub left: Range<usize>,
+    pub right: Range<usize>,
+}
+
+/// Where block merge sort keeps the keys it set aside, announced with
+/// `Operation::Keys`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keys {
+    pub tags: Range<usize>,
+    pub buffer: Range<usize>,
+}
+
+/// A block merge in progress, announced with `Operation::Blocks`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockMerge {
+    pub left: Range<usize>,
+    pub right: Range<usize>,
+    pub block_len: usize,
+}
+
+impl BlockMerge {
+    /// The whole blocks, in order. A partial block at the end of the right
+    /// run is not one of them and has no tag.
+    pub fn blocks(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        let count = (self.right.end - self.left.start) / self.block_len;
+        (0..count).map(|i| {
+            let start = self.left.start + i * self.block_len;
+            start..start + self.block_len
+        })
+    }
+}
+
+/// Where heap sort's heap stands, as announced with `Operation::SiftDown`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeapView {
+    /// Elements `0..heap_len` form the heap; the rest is the sorted suffix.
+    pub heap_len: usize,
+    /// Nodes the current sift-down has moved through, starting at its root.
+    /// Each is a child of the one before.
+    pub path: Vec<usize>,
+}
+
+/// Where the run of a sorting network stands, as announced with
+/// `Operation::Layer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkView {
+    /// The layer being run, counting from 0.
+    pub layer: usize,
+    /// Comparators of that layer run so far, the current one included.
+    pub done: usize,
+}
+
+/// A recorded operation trace and a cursor that replays it at `speed`.
+///
+/// Sort workers append to the trace as fast as they run; the visualizer calls
+/// `advance` once per frame to apply however many operations are due.
+/// The last `UNDO_LIMIT` applied operations keep an `Undo` record so
+/// playback can also run backwards.
+#[derive(Default)]
+pub struct Playback {
+    trace: Vec<Operation>,
+    // One entry for each of the last `undo.len()` applied operations
+    undo: VecDeque<Undo>,
+    // Snapshots of the operations among those that changed more, by cursor
+    snapshots: VecDeque<(usize, Snapshot)>,
+    cursor: usize,
+    // Recorded operations, and those before `cursor`, that aren't markers
+    work_len: usize,
+    work_played: usize,
+    pub speed: PlaybackSpeed,
+    pub paused: bool,
+    // Fractional operations owed from previous frames in ops/sec mode
+    carry: f32,
+    // Counts for the operations up to `cursor`
+    stats: Stats,
+    // Auxiliary arrays alive at `cursor`, oldest first
+    aux: Vec<AuxState>,
+    // Ranges entered and not yet exited at `cursor`, outermost first
+    call_stack: Vec<Range<usize>>,
+    // Runs pushed and not yet merged away at `cursor`, bottom of the stack first
+    runs: Vec<Range<usize>>,
+    // Run boundaries Powersort has weighed up to `cursor`, left to right
+    power_nodes: Vec<PowerNode>,
+    // Pseudocode line of the last `Operation::Line` before `cursor`
+    line: Option<usize>,
+    // The note in force at `cursor`
+    note: Option<Note>,
+    // The latest rotation, block merge sort's keys and the block merge in
+    // progress at `cursor`
+    rotation: Option<Rotation>,
+    keys: Option<Keys>,
+    block_merge: Option<BlockMerge>,
+    // Heap sort's heap and a sorting network's progress at `cursor`
+    heap: Option<HeapView>,
+    network: Option<NetworkView>,
+}
+
+impl Playback {
+    pub fn new(speed: PlaybackSpeed) -> Self {
+        Self {
+            speed,
+            ..Default::default()
+        }
+    }
+
+    /// Drop the recorded trace and rewind, keeping the chosen speed.
+    pub fn clear(&mut self) {
+        self.trace.clear();
+        self.undo.clear();
+        self.snapshots.clear();
+        self.cursor = 0;
+        self.work_len = 0;
+        self.work_played = 0;
+        self.carry = 0.0;
+        self.stats = Stats::default();
+        self.aux.clear();
+        self.call_stack.clear();
+        self.runs.clear();
+        self.power_nodes.clear();
+        self.line = None;
+        self.note = None;
+        self.rotation = None;
+        self.keys = None;
+        self.block_merge = None;
+        self.heap = None;
+        self.network = None;
+    }
+
+    pub fn push(&mut self, op: Operation) {
+        if !op.is_marker() {
+            self.work_len += 1;
+        }
+        self.trace.push(op);
+    }
+
+    /// Everything recorded so far, played or not.
+    pub fn trace(&self) -> &[Operation] {
+        &self.trace
+    }
+
+    /// Operations recorded but not yet played.
+    pub fn buffered(&self) -> usize {
+        self.trace.len() - self.cursor
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn len(&self) -> usize {
+        self.trace.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trace.is_empty()
+    }
+
+    /// Recorded operations that aren't markers, which is what exports count
+    /// in strides and history rows (see `export::work_len`).
+    pub fn work_len(&self) -> usize {
+        self.work_len
+    }
+
+    /// Applied operations that aren't markers.
+    pub fn work_played(&self) -> usize {
+        self.work_played
+    }
+
+    /// The auxiliary arrays the algorithm holds at this point of the run.
+    pub fn aux_arrays(&self) -> &[AuxState] {
+        &self.aux
+    }
+
+    /// The ranges of the recursive calls active at this point of the run,
+    /// outermost first.
+    pub fn call_stack(&self) -> &[Range<usize>] {
+        &self.call_stack
+    }
+
+    /// The sorted runs waiting to be merged at this point of the run, bottom
+    /// of the stack first.
+    pub fn run_stack(&self) -> &[Range<usize>] {
+        &self.runs
+    }
+
+    /// The run boundaries whose power Powersort has computed at this point
+    /// of the run, in order. The last is the one it is deciding on now.
+    pub fn power_nodes(&self) -> &[PowerNode] {
+        &self.power_nodes
+    }
+
+    /// The power of the boundary at element `at`, if it has been computed.
+    pub fn power_at(&self, at: usize) -> Option<usize> {
+        let k = self
+            .power_nodes
+            .binary_search_by_key(&at, |node| node.left.end)
+            .ok()?;
+        Some(self.power_nodes[k].power)
+    }
+
+    /// The note in force at this point of the run, if any.
+    pub fn note(&self) -> Option<&Note> {
+        self.note.as_ref()
+    }
+
+    /// The rotation in progress at this point of the run, if any.
+    pub fn rotation(&self) -> Option<&Rotation> {
+        self.rotation.as_ref()
+    }
+
+    /// Where block merge sort keeps its keys at this point of the run, if
+    /// it has set them aside.
+    pub fn keys(&self) -> Option<&Keys> {
+        self.keys.as_ref()
+    }
+
+    /// The block merge in progress at this point of the run, if any.
+    pub fn block_merge(&self) -> Option<&BlockMerge> {
+        self.block_merge.as_ref()
+    }
+
+    /// Heap sort's heap at this point of the run, once it has announced one.
+    pub fn heap(&self) -> Option<&HeapView> {
+        self.heap.as_ref()
+    }
+
+    /// How far a sorting network has got at this point of the run, once it
+    /// has announced a layer.
+    pub fn network(&self) -> Option<NetworkView> {
+        self.network
+    }
+
+    /// For each block of the block merge in progress, whether it came from
+    /// the left run, read off its tag in `bars`. Empty when there is no
+    /// block merge in progress.
+    pub fn block_origins(&self, bars: &[SortBar]) -> Vec<bool> {
+        let (Some(keys), Some(merge)) = (&self.keys, &self.block_merge) else {
+            return Vec::new();
+        };
+        let count = merge.blocks().count();
+        let from_left = merge.left.len() / merge.block_len;
+        let tags = bars
+            .get(keys.tags.start..keys.tags.start + count)
+            .unwrap_or_default();
+        tags.iter()
+            .map(|tag| tags.iter().filter(|other| other.value < tag.value).count() < from_left)
+            .collect()
+    }
+
+    /// The pseudocode line the algorithm is on at this point of the run.
+    pub fn current_line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// Statistics for the operations applied so far.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Number of operations due after a frame lasting `dt` seconds.
+    fn due(&mut self, dt: f32) -> usize {
+        match self.speed {
+            PlaybackSpeed::OpsPerFrame(n) => n,
+            PlaybackSpeed::OpsPerSecond(rate) => {
+                self.carry += rate * dt;
+                let due = self.carry.floor();
+                self.carry -= due;
+                due as usize
+            }
+        }
+    }
+
+    /// Whether the last applied operation is `Operation::Done`.
+    pub fn is_finished(&self) -> bool {
+        self.cursor > 0 && matches!(self.trace[self.cursor - 1], Operation::Done)
+    }
+
+    /// Apply the operations due after `dt` seconds to `bars`. Nothing is
+    /// applied while paused, and markers (see `Operation::is_marker`) are
+    /// applied without counting towards what is due.
+    ///
+    /// Returns `true` if `Operation::Done` was reached during this call.
+    pub fn advance(&mut self, dt: f32, bars: &mut [SortBar], default_color: Color32) -> bool {
+        if self.paused {
+            self.carry = 0.0;
+            return false;
+        }
+        if !self.is_empty() && !self.is_finished() {
+            self.stats.elapsed += Duration::from_secs_f32(dt);
+        }
+        let due = self.due(dt);
+        if self.buffered() == 0 {
+            // Don't bank time while waiting on the worker, or playback bursts
+            self.carry = 0.0;
+        }
+        let mut applied = 0;
+        while applied < due {
+            let Some(op) = self.trace.get(self.cursor) else {
+                break;
+            };
+            if !op.is_marker() {
+                applied += 1;
+            }
+            if !self.step_forward(bars, default_color) {
+                break;
+            }
+            if self.is_finished() {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Apply the next recorded operation, whether or not playback is paused.
+    ///
+    /// Returns `false` if nothing is buffered or the run is already finished.
+    pub fn step_forward(&mut self, bars: &mut [SortBar], default_color: Color32) -> bool {
+        if self.is_finished() {
+            return false;
+        }
+        let Some(op) = self.trace.get(self.cursor) else {
+            return false;
+        };
+        let mut undo = apply(op, bars, default_color);
+        let (aux_undo, dropped) = apply_aux(op, &mut self.aux);
+        if !matches!(aux_undo, Undo::Nothing) {
+            undo = aux_undo;
+        }
+        let mut snapshot = Snapshot {
+            dropped,
+            ..Snapshot::default()
+        };
+        match *op {
+            Operation::EnterRange(start, end) => self.call_stack.push(start..end),
+            Operation::ExitRange(start, end) => {
+                self.call_stack.pop();
+                if self
+                    .note
+                    .as_ref()
+                    .is_some_and(|note| note.range == (start..end))
+                {
+                    snapshot.note = Some(self.note.take());
+                }
+                if self.rotation.is_some() {
+                    snapshot.rotation = Some(self.rotation.take());
+                }
+                if self
+                    .block_merge
+                    .as_ref()
+                    .is_some_and(|merge| merge.left.start == start && merge.right.end == end)
+                {
+                    snapshot.block_merge = Some(self.block_merge.take());
+                }
+            }
+            Operation::Note(start, end, text) => {
+                let note = Note {
+                    range: start..end,
+                    text,
+                };
+                snapshot.note = Some(self.note.replace(note));
+            }
+            Operation::Rotate(start, mid, end) => {
+                let rotation = Rotation {
+                    left: start..mid,
+                    right: mid..end,
+                };
+                snapshot.rotation = Some(self.rotation.replace(rotation));
+            }
+            Operation::Keys(start, mid, end) => {
+                let keys = (start < end).then_some(Keys {
+                    tags: start..mid,
+                    buffer: mid..end,
+                });
+                snapshot.keys = Some(std::mem::replace(&mut self.keys, keys));
+            }
+            Operation::Blocks(start, mid, end, block_len) => {
+                let merge = BlockMerge {
+                    left: start..mid,
+                    right: mid..end,
+                    block_len,
+                };
+                snapshot.block_merge = Some(self.block_merge.replace(merge));
+            }
+            Operation::PushRun(start, end) => self.runs.push(start..end),
+            Operation::MergeRuns(i, _) => {
+                let end = self.runs.remove(i + 1).end;
+                self.runs[i].end = end;
+            }
+            Operation::NodePower(start, mid, end, power) => self.power_nodes.push(PowerNode {
+                left: start..mid,
+                right: mid..end,
+                power,
+            }),
+            Operation::SiftDown(root, heap_len) => {
+                let heap = HeapView {
+                    heap_len,
+                    path: vec![root],
+                };
+                snapshot.heap = Some(self.heap.replace(heap));
+            }
+            Operation::Swap(parent, child) => {
+                // A swap from the end of the sift-down path down to a child
+                // takes the path with it
+                if let Some(heap) = &mut self.heap {
+                    if heap.path.last() == Some(&parent)
+                        && child > parent
+                        && child < heap.heap_len
+                        && (child - 1) / 2 == parent
+                    {
+                        heap.path.push(child);
+                        snapshot.sifted = true;
+                    }
+                }
+            }
+            Operation::Layer(layer) => {
+                let network = NetworkView { layer, done: 0 };
+                snapshot.network = Some(self.network.replace(network));
+            }
+            Operation::Compare(..) => {
+                if let Some(network) = &mut self.network {
+                    network.done += 1;
+                }
+            }
+            Operation::Line(line) => undo = Undo::Line(self.line.replace(line)),
+            _ => {}
+        }
+        let before = self.stats;
+        self.stats.record(op);
+        let mut undone = self.stats;
+        undone.unrecord(op);
+        snapshot.stats = (undone != before).then_some(before);
+        if !snapshot.is_empty() {
+            self.snapshots.push_back((self.cursor, snapshot));
+        }
+        if !op.is_marker() {
+            self.work_played += 1;
+        }
+        self.undo.push_back(undo);
+        if self.undo.len() > UNDO_LIMIT {
+            self.undo.pop_front();
+            let oldest = self.cursor + 1 - UNDO_LIMIT;
+            while self.snapshots.front().is_some_and(|&(at, _)| at < oldest) {
+                self.snapshots.pop_front();
+            }
+        }
+        self.cursor += 1;
+        true
+    }
+
+    /// Whether `step_backward` can take an operation back: playback is past
+    /// the start and within `UNDO_LIMIT` operations of where it got to.
+    pub fn can_step_backward(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Take back the most recently applied operation.
+    ///
+    /// Returns `false` if playback is already at the start of the trace, or
+    /// as far back as its undo records go.
+    pub fn step_backward(&mut self, bars: &mut [SortBar]) -> bool {
+        let Some(undo) = self.undo.pop_back() else {
+            return false;
+        };
+        self.cursor -= 1;
+        let op = &self.trace[self.cursor];
+        if !op.is_marker() {
+            self.work_played -= 1;
+        }
+        let snapshot = match self.snapshots.back() {
+            Some(&(at, _)) if at == self.cursor => self.snapshots.pop_back().unwrap().1,
+            _ => Snapshot::default(),
+        };
+        match snapshot.stats {
+            Some(before) => {
+                self.stats = Stats {
+                    elapsed: self.stats.elapsed,
+                    ..before
+                }
+            }
+            None => self.stats.unrecord(op),
+        }
+        // Exits and merges carry what they took apart, so the stacks need no
+        // undo record, and neither do a network's comparator counts
+        match *op {
+            Operation::EnterRange(..) => {
+                self.call_stack.pop();
+            }
+            Operation::ExitRange(start, end) => self.call_stack.push(start..end),
+            Operation::PushRun(..) => {
+                self.runs.pop();
+            }
+            Operation::NodePower(..) => {
+                self.power_nodes.pop();
+            }
+            Operation::MergeRuns(i, mid) => {
+                let end = self.runs[i].end;
+                self.runs[i].end = mid;
+                self.runs.insert(i + 1, mid..end);
+            }
+            Operation::Swap(..) if snapshot.sifted => {
+                if let Some(heap) = &mut self.heap {
+                    heap.path.pop();
+                }
+            }
+            Operation::Compare(..) => {
+                if let Some(network) = &mut self.network {
+                    network.done -= 1;
+                }
+            }
+            _ => {}
+        }
+        if let Undo::Line(line) = undo {
+            self.line = line;
+        }
+        if let Some(note) = snapshot.note {
+            self.note = note;
+        }
+        if let Some(rotation) = snapshot.rotation {
+            self.rotation = rotation;
+        }
+        if let Some(keys) = snapshot.keys {
+            self.keys = keys;
+        }
+        if let Some(merge) = snapshot.block_merge {
+            self.block_merge = merge;
+        }
+        if let Some(heap) = snapshot.heap {
+            self.heap = heap;
+        }
+        if let Some(network) = snapshot.network {
+            self.network = network;
+        }
+        unapply_aux(op, &undo, snapshot.dropped, &mut self.aux);
+        undo.restore(op, bars);
+        true
+    }
+}
+
+/// Apply a single operation to the displayed bars, returning what it takes
+/// to step it back.
+///
+/// `Color32::WHITE` is the algorithms' "reset" colour and is remapped to the
+/// theme's `default_color`.
+pub fn apply(op: &Operation, bars: &mut [SortBar], default_color: Color32) -> Undo {
+    let themed = |col: Color32| {
+        if col == Color32::WHITE {
+            default_color
+        } else {
+            col
+        }
+    };
+    match op {
+        Operation::Compare(i, j) => {
+            let undo = Undo::Colors(bars[*i].color, bars[*j].color);
+            bars[*i].color = Color32::YELLOW;
+            bars[*j].color = Color32::YELLOW;
+            undo
+        }
+        Operation::Swap(i, j) => {
+            let undo = Undo::Colors(bars[*i].color, bars[*j].color);
+            bars.swap(*i, *j);
+            bars[*i].color = Color32::GREEN;
+            bars[*j].color = Color32::GREEN;
+            undo
+        }
+        Operation::Overwrite(i, bar) => {
+            let bar = SortBar {
+                value: bar.value,
+                color: themed(bar.color),
+            };
+            Undo::Bar(std::mem::replace(&mut bars[*i], bar))
+        }
+        Operation::SetColor(i, col) => {
+            let undo = Undo::Bar(bars[*i].clone());
+            bars[*i].color = themed(*col);
+            undo
+        }
+        Operation::Read(_)
+        | Operation::AuxCreate(..)
+        | Operation::AuxWrite(..)
+        | Operation::AuxRead(..)
+        | Operation::AuxDrop(..)
+        | Operation::SiftDown(..)
+        | Operation::EnterRange(..)
+        | Operation::ExitRange(..)
+        | Operation::PushRun(..)
+        | Operation::MergeRuns(..)
+        | Operation::NodePower(..)
+        | Operation::Note(..)
+        | Operation::Rotate(..)
+        | Operation::Keys(..)
+        | Operation::Blocks(..)
+        | Operation::Layer(_)
+        | Operation::Line(_)
+        | Operation::Done => Undo::Nothing,
+    }
+}
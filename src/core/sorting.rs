@@ -1,3 +1,7 @@
+#[path = "../Sorting/bitonic_sort.rs"]
+mod bitonic_sort;
+#[path = "../Sorting/block_merge_sort.rs"]
+mod block_merge_sort;
 #[path = "../Sorting/bubble_sort.rs"]
 mod bubble_sort;
 
@@ -15,6 +19,12 @@ mod insertion_sort;
 #[path = "../Sorting/merge_sort_visual.rs"]
 mod merge_sort_visual;
 
+#[path = "../Sorting/odd_even_merge_sort.rs"]
+mod odd_even_merge_sort;
+#[path = "../Sorting/pdqsort.rs"]
+mod pdqsort;
+#[path = "../Sorting/powersort.rs"]
+mod powersort;
 #[path = "../Sorting/quicksort_visual.rs"]
 mod quicksort_visual;
 #[path = "../Sorting/radix_sort_visual.rs"]
@@ -30,87 +40,236 @@ mod sort_utils;
 mod timsort;
 
 use crate::models::SortBar;
-pub use bubble_sort::bubble_sort;
+use crate::stats::Stats;
+pub use bitonic_sort::{bitonic_network, bitonic_sort, BitonicSort};
+pub use block_merge_sort::{block_merge_sort, BlockMergeSort};
+pub use bubble_sort::{bubble_sort, BubbleSort};
 
-pub use cocktail_sort_visual::cocktail_sort_visual;
-pub use counting_sort_visual::counting_sort_visual;
+pub use cocktail_sort_visual::{cocktail_sort_visual, CocktailSort};
+pub use counting_sort_visual::{counting_sort_visual, CountingSort, MAX_COUNTED_VALUE};
 use eframe::egui::Color32;
-pub use gnome_sort_visual::gnome_sort_visual;
-pub use heap_sort_visual::heap_sort_visual;
-pub use insertion_sort::insertion_sort;
+pub use gnome_sort_visual::{gnome_sort_visual, GnomeSort};
+pub use heap_sort_visual::{heap_sort_visual, HeapSort};
+pub use insertion_sort::{insertion_sort, InsertionSort};
 
-pub use merge_sort_visual::merge_sort_visual;
+pub use merge_sort_visual::{merge_sort_visual, MergeSort};
 
-pub use quicksort_visual::quick_sort_visual;
-pub use radix_sort_visual::radix_sort_visual;
+pub use odd_even_merge_sort::{odd_even_merge_network, odd_even_merge_sort, OddEvenMergeSort};
+pub use pdqsort::{pdq_sort, pdq_sort_with_depth_limit, PdqSort};
+pub use powersort::{merge_report, node_power, power_sort, MergeReport, PowerSort};
+pub use quicksort_visual::{quick_sort_visual, QuickSort};
+pub use radix_sort_visual::{radix_sort_visual, RadixSort};
 use rand::{thread_rng, Rng};
-pub use selection_sort::selection_sort;
-pub use shell_sort_visual::shell_sort_visual;
+pub use selection_sort::{selection_sort, SelectionSort};
+pub use shell_sort_visual::{shell_sort_visual, ShellSort};
 
+use std::cell::{Cell, RefCell};
 use std::fmt;
-use std::sync::mpsc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
 use std::thread;
 
-pub use timsort::tim_sort;
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
-pub enum SortingAlgorithm {
-    #[default]
-    Bubble,
-    Selection,
-    Insertion,
-    QuickVisual,
-    MergeSort,
-    HeapSort,
-    CountingSort,
-    RadixSort,
-    ShellSort,
-    CocktailSort,
-    GnomeSort,
-
-    TimSort,
-    BogoSort,
-}
-
-impl SortingAlgorithm {
-    pub fn all() -> &'static [SortingAlgorithm] {
+pub use timsort::{tim_sort, TimSort};
+
+/// Where an algorithm sits on the info page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    Production,
+    Specialized,
+    Research,
+    Educational,
+    Novelty,
+    Probabilistic,
+}
+
+impl Category {
+    /// All categories in the order the info page lists them.
+    pub fn all() -> &'static [Category] {
         &[
-            SortingAlgorithm::Bubble,
-            SortingAlgorithm::Selection,
-            SortingAlgorithm::Insertion,
-            SortingAlgorithm::QuickVisual,
-            SortingAlgorithm::MergeSort,
-            SortingAlgorithm::HeapSort,
-            SortingAlgorithm::CountingSort,
-            SortingAlgorithm::RadixSort,
-            SortingAlgorithm::ShellSort,
-            SortingAlgorithm::CocktailSort,
-            SortingAlgorithm::GnomeSort,
-            SortingAlgorithm::TimSort,
-            SortingAlgorithm::BogoSort,
+            Category::Production,
+            Category::Specialized,
+            Category::Research,
+            Category::Educational,
+            Category::Novelty,
+            Category::Probabilistic,
         ]
     }
 }
 
-impl fmt::Display for SortingAlgorithm {
+impl fmt::Display for Category {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let name = match self {
-            SortingAlgorithm::Bubble => "Bubble Sort",
-            SortingAlgorithm::Selection => "Selection Sort",
-            SortingAlgorithm::Insertion => "Insertion Sort",
-            SortingAlgorithm::QuickVisual => "Quick Sort",
-            SortingAlgorithm::MergeSort => "Merge Sort",
-            SortingAlgorithm::HeapSort => "Heap Sort",
-            SortingAlgorithm::CountingSort => "Counting Sort",
-            SortingAlgorithm::RadixSort => "Radix Sort",
-            SortingAlgorithm::ShellSort => "Shell Sort",
-            SortingAlgorithm::CocktailSort => "Cocktail Sort",
-            SortingAlgorithm::GnomeSort => "Gnome Sort",
-
-            SortingAlgorithm::TimSort => "Tim Sort",
-            SortingAlgorithm::BogoSort => "Bogo Sort",
+        let label = match self {
+            Category::Production => "Production-Grade Algorithms",
+            Category::Specialized => "Efficient Specialized Algorithms",
+            Category::Research => "Advanced Research Algorithms",
+            Category::Educational => "Educational Algorithms",
+            Category::Novelty => "Specialized and Novelty Algorithms",
+            Category::Probabilistic => "Probabilistic Algorithms",
         };
-        write!(f, "{}", name)
+        write!(f, "{}", label)
+    }
+}
+
+/// Big-O strings shown to the user; informational only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Complexity {
+    pub best: &'static str,
+    pub average: &'static str,
+    pub worst: &'static str,
+    pub space: &'static str,
+}
+
+impl fmt::Display for Complexity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "best {}, average {}, worst {}, space {}",
+            self.best, self.average, self.worst, self.space
+        )
+    }
+}
+
+/// One layer of a sorting network: comparators on distinct elements,
+/// which could all run at the same time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkLayer {
+    /// Each `(i, j)`, with `i < j`, puts the smaller of elements `i` and
+    /// `j` at `i`.
+    pub comparators: Vec<(usize, usize)>,
+    /// The pseudocode line the layer comes from.
+    pub line: usize,
+}
+
+/// A sorting algorithm the visualizer can run.
+///
+/// Implement this (in a single file, or in a crate depending on sorthos) and
+/// add it to a [`Registry`] to make it show up in the selector and info page.
+pub trait SortAlgorithm: Send + Sync {
+    /// Display name; also the key the registry looks algorithms up by.
+    fn name(&self) -> &'static str;
+
+    fn category(&self) -> Category;
+
+    fn is_stable(&self) -> bool;
+
+    fn complexity(&self) -> Complexity;
+
+    /// Sort `bars` in place, emitting every step into `sink`.
+    fn run(&self, bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled>;
+
+    /// A short pseudocode listing of the algorithm. `run` emits
+    /// `Operation::Line` with the line it is on, so the listing can follow
+    /// playback. Empty if the algorithm has none.
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// For sorting networks, the layers of comparators `run` goes through
+    /// on `n` elements, announcing each with `Operation::Layer`. `None` for
+    /// algorithms whose comparisons depend on the data.
+    fn network(&self, _n: usize) -> Option<Vec<NetworkLayer>> {
+        None
+    }
+}
+
+/// The set of algorithms offered by the GUI.
+#[derive(Clone, Default)]
+pub struct Registry {
+    algorithms: Vec<Arc<dyn SortAlgorithm>>,
+}
+
+impl Registry {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry holding every algorithm that ships with sorthos.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry
+            .register(BubbleSort)
+            .register(SelectionSort)
+            .register(InsertionSort)
+            .register(QuickSort)
+            .register(PdqSort)
+            .register(MergeSort)
+            .register(BlockMergeSort)
+            .register(HeapSort)
+            .register(CountingSort)
+            .register(RadixSort)
+            .register(ShellSort)
+            .register(BitonicSort)
+            .register(OddEvenMergeSort)
+            .register(CocktailSort)
+            .register(GnomeSort)
+            .register(TimSort)
+            .register(PowerSort)
+            .register(BogoSort);
+        registry
+    }
+
+    /// Add `algorithm`, replacing any registered algorithm with the same name.
+    pub fn register(&mut self, algorithm: impl SortAlgorithm + 'static) -> &mut Self {
+        let algorithm: Arc<dyn SortAlgorithm> = Arc::new(algorithm);
+        match self
+            .algorithms
+            .iter_mut()
+            .find(|a| a.name() == algorithm.name())
+        {
+            Some(existing) => *existing = algorithm,
+            None => self.algorithms.push(algorithm),
+        }
+        self
+    }
+
+    /// Look an algorithm up by name, ignoring case.
+    pub fn get(&self, name: &str) -> Option<Arc<dyn SortAlgorithm>> {
+        self.algorithms
+            .iter()
+            .find(|a| a.name().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+
+    /// Look an algorithm up the way it is typed on a command line: case,
+    /// spaces, dashes and a trailing "sort" are ignored, so "quick",
+    /// "quick-sort" and "Quick Sort" all find Quick Sort.
+    pub fn find(&self, query: &str) -> Option<Arc<dyn SortAlgorithm>> {
+        fn key(name: &str) -> String {
+            let key: String = name
+                .chars()
+                .filter(|c| c.is_ascii_alphanumeric())
+                .map(|c| c.to_ascii_lowercase())
+                .collect();
+            match key.strip_suffix("sort") {
+                Some(stem) if !stem.is_empty() => stem.to_string(),
+                _ => key,
+            }
+        }
+        let query = key(query);
+        self.algorithms
+            .iter()
+            .find(|a| key(a.name()) == query)
+            .cloned()
+    }
+
+    /// Algorithms in registration order.
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn SortAlgorithm>> {
+        self.algorithms.iter()
+    }
+
+    pub fn in_category(&self, category: Category) -> impl Iterator<Item = &Arc<dyn SortAlgorithm>> {
+        self.algorithms
+            .iter()
+            .filter(move |a| a.category() == category)
+    }
+
+    pub fn len(&self) -> usize {
+        self.algorithms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.algorithms.is_empty()
     }
 }
 
@@ -120,97 +279,383 @@ pub enum Operation {
     Swap(usize, usize),
     SetColor(usize, Color32),
     Overwrite(usize, SortBar), // The new operation to directly place a bar
+    /// An element read outside of a comparison, e.g. copied into a buffer.
+    Read(usize),
+    /// A named auxiliary array of this many zeroes was allocated. Emitted by
+    /// `Sink::aux`; the other `Aux*` operations come from the `AuxArray`
+    /// it returns.
+    AuxCreate(AuxId, &'static str, usize),
+    /// Element `.1` of an auxiliary array was set to `.2`.
+    AuxWrite(AuxId, usize, usize),
+    /// Element `.1` of an auxiliary array was read.
+    AuxRead(AuxId, usize),
+    /// An auxiliary array of this many elements was released.
+    AuxDrop(AuxId, usize),
+    /// A sift-down starts at node `.0` of the binary heap held in the first
+    /// `.1` elements; everything after them is already sorted. Emitted by
+    /// heap sort so the tree view can follow it.
+    SiftDown(usize, usize),
+    /// A recursive call starts on the elements from `.0` up to, not
+    /// including, `.1`. Divide-and-conquer sorts pair every one with an
+    /// `ExitRange` of the same range, so the live ranges form the call stack.
+    EnterRange(usize, usize),
+    /// The call working on the elements from `.0` up to `.1` returns.
+    ExitRange(usize, usize),
+    /// The sorted run from `.0` up to, not including, `.1` joins the stack
+    /// of runs waiting to be merged. Emitted by run-merging sorts such as
+    /// TimSort.
+    PushRun(usize, usize),
+    /// Pending runs `.0` and `.0 + 1`, counting from the bottom of the run
+    /// stack, merge into one; the second started at element `.1`.
+    MergeRuns(usize, usize),
+    /// Powersort worked out the power of the boundary between the runs
+    /// from `.0` to `.1` and from `.1` to `.2`: `.3`, the first binary digit
+    /// in which the runs' midpoints, as fractions of the array length,
+    /// differ.
+    NodePower(usize, usize, usize, usize),
+    /// A note on how the elements from `.0` up to `.1` are being handled,
+    /// e.g. that a hybrid sort fell back to another algorithm for them. It
+    /// holds until the next note, or until the call on exactly that range
+    /// exits.
+    Note(usize, usize, &'static str),
+    /// The elements from `.0` up to `.2` are being rotated so that those
+    /// from `.1` on come first. Holds until the next rotation or the next
+    /// `ExitRange`.
+    Rotate(usize, usize, usize),
+    /// Block merge sort set aside the elements from `.0` up to `.1` as tags
+    /// for its blocks and those from `.1` up to `.2` as an internal buffer;
+    /// their order means nothing until they are merged back in. Equal
+    /// `.0` and `.2` say the keys are back among the rest.
+    Keys(usize, usize, usize),
+    /// A block merge of the runs from `.0` to `.1` and from `.1` to `.2` in
+    /// blocks of `.3` elements: counting from `.0`, the `i`th whole block
+    /// is tagged with the `i`th tag, and came from the first run if its tag
+    /// is among the `(.1 - .0) / .3` smallest tags in use. Holds until the
+    /// call on exactly that range exits.
+    Blocks(usize, usize, usize, usize),
+    /// A sorting network starts on layer `.0` of its
+    /// `SortAlgorithm::network`, counting from 0.
+    Layer(usize),
+    /// Execution reached line `.0`, counting from 1, of the algorithm's
+    /// `SortAlgorithm::pseudocode` listing.
+    Line(usize),
     Done,
 }
 
-/// Copies sorted `values` back into `bars` and repaints them white.
+impl Operation {
+    /// Whether the operation only annotates the run (the current line, the
+    /// call stack, the heap, the run stack, notes, block merges, network
+    /// layers) and leaves the array and its colours alone.
+    pub fn is_marker(&self) -> bool {
+        matches!(
+            self,
+            Operation::Line(_)
+                | Operation::SiftDown(..)
+                | Operation::EnterRange(..)
+                | Operation::ExitRange(..)
+                | Operation::PushRun(..)
+                | Operation::MergeRuns(..)
+                | Operation::NodePower(..)
+                | Operation::Note(..)
+                | Operation::Rotate(..)
+                | Operation::Keys(..)
+                | Operation::Blocks(..)
+                | Operation::Layer(_)
+        )
+    }
+}
+
+/// Tells apart the auxiliary arrays of one run.
+pub type AuxId = usize;
 
-/// Returns `true` if the slice is in non‑decreasing order.
+/// Handle to a named auxiliary array announced with `Sink::aux`, such as a
+/// merge buffer or a table of counts. Emitting its reads and writes through
+/// the handle lets the visualizer draw the array under the main bars.
+#[derive(Debug, Clone, Copy)]
+pub struct AuxArray {
+    id: AuxId,
+    len: usize,
+}
 
-pub fn start_sort(
-    algorithm: SortingAlgorithm,
-    mut bars: Vec<SortBar>,
-    tx: mpsc::Sender<Operation>,
-) {
-    println!(
-        "[DEBUG] start_sort: Spawning thread for algorithm: {:?}",
-        algorithm
-    );
-    thread::spawn(move || {
-        let len = bars.len();
-
-        // Handle edge cases - arrays with 0 or 1 elements are already sorted
-        if len <= 1 {
-            let _ = tx.send(Operation::Done);
-            println!("[DEBUG] start_sort: Array len <= 1, sending Done.");
-            return;
+impl AuxArray {
+    pub fn write(&self, sink: &Sink, index: usize, value: usize) -> Result<(), Cancelled> {
+        sink.send(Operation::AuxWrite(self.id, index, value))
+    }
+
+    pub fn read(&self, sink: &Sink, index: usize) -> Result<(), Cancelled> {
+        sink.send(Operation::AuxRead(self.id, index))
+    }
+
+    /// Announce that the array is no longer used.
+    pub fn release(self, sink: &Sink) -> Result<(), Cancelled> {
+        sink.send(Operation::AuxDrop(self.id, self.len))
+    }
+}
+
+/// Capacity of the channel between a sort worker and the visualizer.
+///
+/// Algorithms run flat out, so a bounded channel is what keeps a runaway
+/// worker (e.g. Bogo Sort) from recording far ahead of playback.
+pub const OP_CHANNEL_CAPACITY: usize = 4096;
+
+/// Error returned once a run has been cancelled. Algorithms propagate it
+/// with `?` so they stop at the very next operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sort cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Shared flag telling a sort worker to stop.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Where an algorithm sends its operations, bundled with the token that
+/// cancels it.
+///
+/// The visualizer records operations through a channel; headless runs can
+/// discard them or only count them instead.
+pub struct Sink {
+    target: Target,
+    cancel: CancelToken,
+    next_aux: Cell<AuxId>,
+}
+
+enum Target {
+    Channel(mpsc::SyncSender<Operation>),
+    Discard,
+    Count(Cell<Stats>),
+    Record(RefCell<Vec<Operation>>),
+}
+
+impl Sink {
+    pub fn new(tx: mpsc::SyncSender<Operation>, cancel: CancelToken) -> Self {
+        Self {
+            target: Target::Channel(tx),
+            next_aux: Cell::new(0),
+            cancel,
         }
+    }
 
-        match algorithm {
-            SortingAlgorithm::Bubble => {
-                println!("[DEBUG] start_sort: Running bubble_sort");
-                bubble_sort(&mut bars, &tx)
-            }
-            SortingAlgorithm::Selection => {
-                println!("[DEBUG] start_sort: Running selection_sort");
-                selection_sort(&mut bars, &tx)
-            }
-            SortingAlgorithm::Insertion => {
-                println!("[DEBUG] start_sort: Running insertion_sort");
-                insertion_sort(&mut bars, &tx)
-            }
-            SortingAlgorithm::QuickVisual => {
-                println!("[DEBUG] start_sort: Running quick_sort_visual");
-                quick_sort_visual(&mut bars, &tx)
-            }
-            SortingAlgorithm::MergeSort => {
-                println!("[DEBUG] start_sort: Running merge_sort_visual");
-                merge_sort_visual(&mut bars, &tx)
-            }
-            SortingAlgorithm::HeapSort => {
-                println!("[DEBUG] start_sort: Running heap_sort_visual");
-                heap_sort_visual(&mut bars, &tx)
-            }
-            SortingAlgorithm::CountingSort => {
-                println!("[DEBUG] start_sort: Running counting_sort_visual");
-                counting_sort_visual(&mut bars, &tx)
-            }
-            SortingAlgorithm::RadixSort => {
-                println!("[DEBUG] start_sort: Running radix_sort_visual");
-                radix_sort_visual(&mut bars, &tx)
-            }
-            SortingAlgorithm::ShellSort => {
-                println!("[DEBUG] start_sort: Running shell_sort_visual");
-                shell_sort_visual(&mut bars, &tx)
-            }
-            SortingAlgorithm::CocktailSort => {
-                println!("[DEBUG] start_sort: Running cocktail_sort_visual");
-                cocktail_sort_visual(&mut bars, &tx)
-            }
-            SortingAlgorithm::GnomeSort => {
-                println!("[DEBUG] start_sort: Running gnome_sort_visual");
-                gnome_sort_visual(&mut bars, &tx)
-            }
+    /// A sink that drops every operation, for timing an algorithm on its own.
+    pub fn discard() -> Self {
+        Self {
+            target: Target::Discard,
+            next_aux: Cell::new(0),
+            cancel: CancelToken::new(),
+        }
+    }
 
-            SortingAlgorithm::TimSort => {
-                println!("[DEBUG] start_sort: Running tim_sort");
-                tim_sort(&mut bars, &tx);
+    /// A sink that tallies operations into `Stats` instead of keeping them.
+    pub fn counting() -> Self {
+        Self {
+            target: Target::Count(Cell::new(Stats::default())),
+            next_aux: Cell::new(0),
+            cancel: CancelToken::new(),
+        }
+    }
+
+    /// A sink that keeps every operation in memory, for exporting a run
+    /// without the GUI. Collect them with `into_trace`.
+    pub fn recording() -> Self {
+        Self {
+            target: Target::Record(RefCell::new(Vec::new())),
+            next_aux: Cell::new(0),
+            cancel: CancelToken::new(),
+        }
+    }
+
+    /// The operations kept by a `recording` sink; empty for other sinks.
+    pub fn into_trace(self) -> Vec<Operation> {
+        match self.target {
+            Target::Record(trace) => trace.into_inner(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Make this sink stop the run once `cancel` is cancelled.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// The counts so far if this is a `counting` sink.
+    pub fn stats(&self) -> Option<Stats> {
+        match &self.target {
+            Target::Count(stats) => Some(stats.get()),
+            _ => None,
+        }
+    }
+
+    /// Emit `op`, failing with `Cancelled` if the run was cancelled or
+    /// nobody is listening anymore.
+    pub fn send(&self, op: Operation) -> Result<(), Cancelled> {
+        self.check()?;
+        match &self.target {
+            Target::Channel(tx) => tx.send(op).map_err(|_| Cancelled),
+            Target::Discard => Ok(()),
+            Target::Count(stats) => {
+                let mut counted = stats.get();
+                counted.record(&op);
+                stats.set(counted);
+                Ok(())
             }
-            SortingAlgorithm::BogoSort => {
-                println!("[DEBUG] start_sort: Running bogo_sort");
-                bogo_sort(&mut bars, &tx);
+            Target::Record(trace) => {
+                trace.borrow_mut().push(op);
+                Ok(())
             }
         }
-        let _ = tx.send(Operation::Done);
-        println!("[DEBUG] start_sort: Sorting thread finished, sent Done.");
-    });
+    }
+
+    /// Allocate a named auxiliary array of `len` zeroes.
+    pub fn aux(&self, name: &'static str, len: usize) -> Result<AuxArray, Cancelled> {
+        let id = self.next_aux.get();
+        self.next_aux.set(id + 1);
+        self.send(Operation::AuxCreate(id, name, len))?;
+        Ok(AuxArray { id, len })
+    }
+
+    /// Fail with `Cancelled` if the run was cancelled. For loops that can
+    /// spin without emitting anything.
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.cancel.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Runs `algorithm` on a copy of `bars` and returns everything it did,
+/// ending with `Operation::Done`, as the visualizer would have recorded it.
+pub fn record_trace(
+    algorithm: &dyn SortAlgorithm,
+    bars: &[SortBar],
+    cancel: CancelToken,
+) -> Result<Vec<Operation>, Cancelled> {
+    let mut bars = bars.to_vec();
+    let sink = Sink::recording().with_cancel(cancel);
+    run_algorithm(algorithm, &mut bars, &sink)?;
+    sink.send(Operation::Done)?;
+    Ok(sink.into_trace())
+}
+
+/// Runs `algorithm` on `bars` on the current thread, emitting every operation
+/// into `sink` as fast as the algorithm produces them. No `Done` is sent.
+pub fn run_algorithm(
+    algorithm: &dyn SortAlgorithm,
+    bars: &mut [SortBar],
+    sink: &Sink,
+) -> Result<(), Cancelled> {
+    // Arrays with 0 or 1 elements are already sorted
+    if bars.len() <= 1 {
+        return Ok(());
+    }
+    algorithm.run(bars, sink)
+}
+
+/// Handle to a running sort worker thread.
+pub struct SortWorker {
+    cancel: CancelToken,
+    handle: thread::JoinHandle<Result<(), Cancelled>>,
+}
+
+impl SortWorker {
+    /// Ask the worker to stop at its next operation. A worker blocked on a
+    /// full channel only notices once the receiver is dropped.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Wait for the worker thread to exit. `Err(Cancelled)` means it stopped
+    /// before finishing the sort.
+    pub fn join(self) -> Result<(), Cancelled> {
+        self.handle.join().unwrap_or(Err(Cancelled))
+    }
+}
+
+/// Spawns a worker thread that records `algorithm`'s operation trace into
+/// `tx`, followed by `Operation::Done` unless it is cancelled first.
+pub fn start_sort(
+    algorithm: Arc<dyn SortAlgorithm>,
+    mut bars: Vec<SortBar>,
+    tx: mpsc::SyncSender<Operation>,
+) -> SortWorker {
+    spawn_worker(tx, move |sink| run_algorithm(algorithm.as_ref(), &mut bars, sink))
 }
 
-// (Block Merge Sort removed)
+/// Spawns a worker thread that runs `work` with a sink feeding `tx`, then
+/// sends `Operation::Done` unless it is cancelled first. `start_sort` is
+/// this with an algorithm's `run`.
+pub fn spawn_worker<F>(tx: mpsc::SyncSender<Operation>, work: F) -> SortWorker
+where
+    F: FnOnce(&Sink) -> Result<(), Cancelled> + Send + 'static,
+{
+    let cancel = CancelToken::new();
+    let sink = Sink::new(tx, cancel.clone());
+    let handle = thread::spawn(move || work(&sink).and_then(|()| sink.send(Operation::Done)));
+    SortWorker { cancel, handle }
+}
 
 // ---------- Bogo Sort ----------
-pub fn bogo_sort(bars: &mut Vec<SortBar>, tx: &mpsc::Sender<Operation>) {
-    use std::time::Duration;
+pub struct BogoSort;
+
+impl SortAlgorithm for BogoSort {
+    fn name(&self) -> &'static str {
+        "Bogo Sort"
+    }
+
+    fn category(&self) -> Category {
+        Category::Probabilistic
+    }
+
+    fn is_stable(&self) -> bool {
+        false
+    }
+
+    fn complexity(&self) -> Complexity {
+        Complexity {
+            best: "O(n)",
+            average: "O(n·n!)",
+            worst: "unbounded",
+            space: "O(1)",
+        }
+    }
+
+    fn run(&self, bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
+        bogo_sort(bars, sink)
+    }
+
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "while a is not sorted:",
+            "    for i in n - 1 down to 1:",
+            "        j = random index in 0 ..= i",
+            "        swap a[i], a[j]",
+        ]
+    }
+}
+
+pub fn bogo_sort(bars: &mut [SortBar], sink: &Sink) -> Result<(), Cancelled> {
     let mut rng = thread_rng();
     let len = bars.len();
 
@@ -219,26 +664,28 @@ pub fn bogo_sort(bars: &mut Vec<SortBar>, tx: &mpsc::Sender<Operation>) {
     }
 
     while !bars_are_sorted(bars) {
+        sink.check()?;
+        sink.send(Operation::Line(1))?;
         // Fisher-Yates shuffle with visual feedback
         for i in (1..len).rev() {
             let j = rng.gen_range(0..=i);
             if i != j {
                 bars.swap(i, j);
-                let _ = tx.send(Operation::Swap(i, j));
-                let _ = tx.send(Operation::SetColor(i, Color32::YELLOW));
-                let _ = tx.send(Operation::SetColor(j, Color32::YELLOW));
-                thread::sleep(Duration::from_millis(15));
-                let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
-                let _ = tx.send(Operation::SetColor(j, Color32::WHITE));
+                sink.send(Operation::Line(4))?;
+                sink.send(Operation::Swap(i, j))?;
+                sink.send(Operation::SetColor(i, Color32::YELLOW))?;
+                sink.send(Operation::SetColor(j, Color32::YELLOW))?;
+                sink.send(Operation::SetColor(i, Color32::WHITE))?;
+                sink.send(Operation::SetColor(j, Color32::WHITE))?;
             }
         }
-        thread::sleep(Duration::from_millis(60));
     }
 
     // Final sorted state
     for i in 0..len {
-        let _ = tx.send(Operation::SetColor(i, Color32::WHITE));
+        sink.send(Operation::SetColor(i, Color32::WHITE))?;
     }
+    Ok(())
 }
 
 // (Removed Bozo Sort, Stooge Sort, and Slow Sort implementations)
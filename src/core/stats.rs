@@ -0,0 +1,97 @@
+use crate::sorting::Operation;
+use std::time::Duration;
+
+/// Operation counts for one run, built up from its `Operation` stream.
+///
+/// `reads` only counts explicit reads, of the main array or an auxiliary
+/// one; the elements a comparison or swap looks at are already covered by
+/// those counters. `writes` likewise include writes to auxiliary arrays.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Stats {
+    pub comparisons: usize,
+    pub swaps: usize,
+    pub writes: usize,
+    pub reads: usize,
+    /// Auxiliary elements currently allocated.
+    pub aux: usize,
+    /// Largest value `aux` reached during the run.
+    pub peak_aux: usize,
+    /// Ranges entered but not yet exited, i.e. the recursion depth.
+    pub depth: usize,
+    /// Largest value `depth` reached during the run.
+    pub max_depth: usize,
+    /// Playback time spent on the run so far, pauses excluded.
+    pub elapsed: Duration,
+}
+
+impl Stats {
+    pub fn record(&mut self, op: &Operation) {
+        match op {
+            Operation::Compare(..) => self.comparisons += 1,
+            Operation::Swap(..) => self.swaps += 1,
+            Operation::Overwrite(..) => self.writes += 1,
+            Operation::Read(_) | Operation::AuxRead(..) => self.reads += 1,
+            Operation::AuxWrite(..) => self.writes += 1,
+            Operation::AuxCreate(_, _, n) => {
+                self.aux += n;
+                self.peak_aux = self.peak_aux.max(self.aux);
+            }
+            Operation::AuxDrop(_, n) => self.aux = self.aux.saturating_sub(*n),
+            Operation::EnterRange(..) => {
+                self.depth += 1;
+                self.max_depth = self.max_depth.max(self.depth);
+            }
+            Operation::ExitRange(..) => self.depth = self.depth.saturating_sub(1),
+            Operation::SetColor(..)
+            | Operation::SiftDown(..)
+            | Operation::PushRun(..)
+            | Operation::MergeRuns(..)
+            | Operation::NodePower(..)
+            | Operation::Note(..)
+            | Operation::Rotate(..)
+            | Operation::Keys(..)
+            | Operation::Blocks(..)
+            | Operation::Layer(_)
+            | Operation::Line(_)
+            | Operation::Done => {}
+        }
+    }
+
+    /// Take back a `record(op)` by doing the opposite. That leaves the peaks
+    /// alone, and can't bring back a level `record` stopped at zero, so
+    /// callers keep the earlier stats where it would come out different.
+    pub fn unrecord(&mut self, op: &Operation) {
+        match op {
+            Operation::Compare(..) => self.comparisons -= 1,
+            Operation::Swap(..) => self.swaps -= 1,
+            Operation::Overwrite(..) => self.writes -= 1,
+            Operation::Read(_) | Operation::AuxRead(..) => self.reads -= 1,
+            Operation::AuxWrite(..) => self.writes -= 1,
+            Operation::AuxCreate(_, _, n) => self.aux -= n,
+            Operation::AuxDrop(_, n) => self.aux += n,
+            Operation::EnterRange(..) => self.depth -= 1,
+            Operation::ExitRange(..) => self.depth += 1,
+            Operation::SetColor(..)
+            | Operation::SiftDown(..)
+            | Operation::PushRun(..)
+            | Operation::MergeRuns(..)
+            | Operation::NodePower(..)
+            | Operation::Note(..)
+            | Operation::Rotate(..)
+            | Operation::Keys(..)
+            | Operation::Blocks(..)
+            | Operation::Layer(_)
+            | Operation::Line(_)
+            | Operation::Done => {}
+        }
+    }
+
+    /// Tally a whole trace, e.g. one recorded without the GUI.
+    pub fn from_trace<'a>(trace: impl IntoIterator<Item = &'a Operation>) -> Self {
+        let mut stats = Self::default();
+        for op in trace {
+            stats.record(op);
+        }
+        stats
+    }
+}
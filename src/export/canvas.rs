@@ -0,0 +1,199 @@
+//! A window stand-in for exports: egui runs headless, and the triangles it
+//! tessellates what was painted into are filled on the CPU. Frames are
+//! therefore drawn by the same code as the screen, text included.
+
+use crate::gui_backend::gui::Theme;
+use eframe::egui::epaint::{ClippedPrimitive, ImageData, Mesh, Primitive, Vertex};
+use eframe::egui::{self, pos2, vec2, Color32, Painter, Pos2, Rect, TextureId};
+use image::{Rgba, RgbaImage};
+use std::collections::HashMap;
+
+/// A texture egui asked for, in premultiplied sRGBA, row by row.
+struct Texture {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color32>,
+}
+
+impl Texture {
+    fn sample(&self, uv: Pos2) -> Color32 {
+        let x = ((uv.x * self.width as f32) as usize).min(self.width - 1);
+        let y = ((uv.y * self.height as f32) as usize).min(self.height - 1);
+        self.pixels[y * self.width + x]
+    }
+}
+
+/// Paints frames of a fixed size, one pixel per point, in a theme's colours.
+pub struct Canvas {
+    ctx: egui::Context,
+    width: u32,
+    height: u32,
+    background: Rgba<u8>,
+    textures: HashMap<TextureId, Texture>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32, theme: Theme) -> Self {
+        let ctx = egui::Context::default();
+        let visuals = theme.default_visuals();
+        let background = Rgba(visuals.panel_fill.to_array());
+        ctx.set_visuals(visuals);
+        Self {
+            ctx,
+            width,
+            height,
+            background,
+            textures: HashMap::new(),
+        }
+    }
+
+    /// Let `draw` paint the whole frame, over the theme's panel colour, and
+    /// return the pixels.
+    pub fn paint(&mut self, draw: impl Fn(&Painter, Rect)) -> RgbaImage {
+        let rect = Rect::from_min_size(Pos2::ZERO, vec2(self.width as f32, self.height as f32));
+        let input = egui::RawInput {
+            screen_rect: Some(rect),
+            ..Default::default()
+        };
+        let output = self.ctx.run(input, |ctx| {
+            draw(&ctx.layer_painter(egui::LayerId::background()), rect);
+        });
+        for (id, delta) in output.textures_delta.set {
+            let [width, height] = delta.image.size();
+            let pixels: Vec<Color32> = match &delta.image {
+                ImageData::Color(image) => image.pixels.clone(),
+                ImageData::Font(image) => image.srgba_pixels(None).collect(),
+            };
+            match (delta.pos, self.textures.get_mut(&id)) {
+                (Some([x, y]), Some(texture)) => {
+                    for (row, line) in pixels.chunks(width).enumerate() {
+                        let start = (y + row) * texture.width + x;
+                        texture.pixels[start..start + width].copy_from_slice(line);
+                    }
+                }
+                _ => {
+                    let texture = Texture {
+                        width,
+                        height,
+                        pixels,
+                    };
+                    self.textures.insert(id, texture);
+                }
+            }
+        }
+
+        let mut image = RgbaImage::from_pixel(self.width, self.height, self.background);
+        let primitives = self.ctx.tessellate(output.shapes, output.pixels_per_point);
+        for ClippedPrimitive {
+            clip_rect,
+            primitive,
+        } in primitives
+        {
+            if let Primitive::Mesh(mesh) = primitive {
+                self.fill_mesh(&mut image, clip_rect, &mesh);
+            }
+        }
+        for id in output.textures_delta.free {
+            self.textures.remove(&id);
+        }
+        image
+    }
+
+    fn fill_mesh(&self, image: &mut RgbaImage, clip: Rect, mesh: &Mesh) {
+        let texture = self.textures.get(&mesh.texture_id);
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| &mesh.vertices[triangle[k] as usize]);
+            fill_triangle(image, clip, texture, a, b, c);
+        }
+    }
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `p`: positive with `p`
+/// to the right of the edge from `a` to `b`, as y grows downwards.
+fn edge(a: Pos2, b: Pos2, p: Pos2) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Whether pixels centred exactly on the edge from `a` to `b` belong to the
+/// triangle. Of two triangles sharing an edge, each sees it run the other
+/// way, so exactly one of them gets those pixels.
+fn owns_edge(a: Pos2, b: Pos2) -> bool {
+    b.y > a.y || (b.y == a.y && b.x > a.x)
+}
+
+/// Blend a triangle, with colours and texture coordinates interpolated
+/// between its corners, over `image` inside `clip`. Pixels are covered when
+/// their centre is.
+fn fill_triangle(
+    image: &mut RgbaImage,
+    clip: Rect,
+    texture: Option<&Texture>,
+    a: &Vertex,
+    b: &Vertex,
+    c: &Vertex,
+) {
+    let (a, b, c) = if edge(a.pos, b.pos, c.pos) < 0.0 {
+        (a, c, b)
+    } else {
+        (a, b, c)
+    };
+    let area = edge(a.pos, b.pos, c.pos);
+    if area <= 0.0 {
+        return;
+    }
+    let bounds = Rect::from_points(&[a.pos, b.pos, c.pos])
+        .intersect(clip)
+        .intersect(Rect::from_min_size(
+            Pos2::ZERO,
+            vec2(image.width() as f32, image.height() as f32),
+        ));
+    if !bounds.is_positive() {
+        return;
+    }
+    let owned = [
+        owns_edge(b.pos, c.pos),
+        owns_edge(c.pos, a.pos),
+        owns_edge(a.pos, b.pos),
+    ];
+    for y in bounds.top().floor() as u32..bounds.bottom().ceil() as u32 {
+        for x in bounds.left().floor() as u32..bounds.right().ceil() as u32 {
+            let p = pos2(x as f32 + 0.5, y as f32 + 0.5);
+            let weights = [
+                edge(b.pos, c.pos, p),
+                edge(c.pos, a.pos, p),
+                edge(a.pos, b.pos, p),
+            ];
+            if weights
+                .iter()
+                .zip(owned)
+                .any(|(&w, owned)| w < 0.0 || (w == 0.0 && !owned))
+            {
+                continue;
+            }
+            let [wa, wb, wc] = weights.map(|w| w / area);
+            let mut color = [0.0; 4];
+            for (channel, value) in color.iter_mut().enumerate() {
+                *value = wa * a.color[channel] as f32
+                    + wb * b.color[channel] as f32
+                    + wc * c.color[channel] as f32;
+            }
+            if let Some(texture) = texture {
+                let uv = pos2(
+                    wa * a.uv.x + wb * b.uv.x + wc * c.uv.x,
+                    wa * a.uv.y + wb * b.uv.y + wc * c.uv.y,
+                );
+                let texel = texture.sample(uv);
+                for (channel, value) in color.iter_mut().enumerate() {
+                    *value *= texel[channel] as f32 / 255.0;
+                }
+            }
+            // Premultiplied alpha: what shows through is scaled by 1 - alpha
+            let pixel = image.get_pixel_mut(x, y);
+            let keep = 1.0 - color[3] / 255.0;
+            for (channel, value) in color.iter().enumerate() {
+                let blended = value + keep * pixel[channel] as f32;
+                pixel[channel] = blended.round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+}
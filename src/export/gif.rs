@@ -0,0 +1,38 @@
+use super::{frames, ExportOptions};
+use crate::models::SortBar;
+use crate::sorting::Operation;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, ImageResult, Luma, Pixel};
+use std::io::Write;
+
+/// How frame colours are reduced to GIF's 256-colour palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GifPalette {
+    /// A palette chosen per frame from the colours actually drawn.
+    #[default]
+    Adaptive,
+    /// Shades of grey only, which are reproduced exactly and compress well.
+    Grayscale,
+}
+
+/// Encode the run of `trace` over `input` as a looping GIF.
+pub fn write_gif(
+    input: &[SortBar],
+    trace: &[Operation],
+    options: &ExportOptions,
+    out: impl Write,
+) -> ImageResult<()> {
+    let mut encoder = GifEncoder::new_with_speed(out, 10);
+    encoder.set_repeat(Repeat::Infinite)?;
+    let delay = Delay::from_numer_denom_ms(options.delay_ms, 1);
+    for mut image in frames(input, trace, options.stride, &options.style) {
+        if options.palette == GifPalette::Grayscale {
+            for pixel in image.pixels_mut() {
+                let Luma([l]) = pixel.to_luma();
+                *pixel = image::Rgba([l, l, l, 255]);
+            }
+        }
+        encoder.encode_frame(Frame::from_parts(image, 0, 0, delay))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use super::{work_chunks, work_len};
+use crate::gui::renderers::hue;
+use crate::models::SortBar;
+use crate::sorting::Operation;
+use image::{Rgba, RgbaImage};
+
+/// Rows a history aims for when the caller has no preference.
+pub const DEFAULT_ROWS: usize = 512;
+
+/// Operations per row that give about `rows` rows for a trace of `ops`
+/// operations that aren't markers, as counted by `work_len`.
+pub fn ops_per_row(ops: usize, rows: usize) -> usize {
+    ops.div_ceil(rows.max(1)).max(1)
+}
+
+/// The whole run as one picture: a row of pixels per array state, one pixel
+/// per element coloured by value, taken before the first operation and after
+/// every `every` operations that aren't markers. Time runs down the image.
+pub fn history_image(input: &[SortBar], trace: &[Operation], every: usize) -> RgbaImage {
+    let every = every.max(1);
+    let width = input.len().max(1) as u32;
+    let rows = 1 + work_len(trace).div_ceil(every);
+    let top = input
+        .iter()
+        .map(|b| b.value + 1)
+        .max()
+        .unwrap_or(1)
+        .max(input.len()) as f32;
+
+    let mut image = RgbaImage::new(width, rows as u32);
+    let mut values: Vec<usize> = input.iter().map(|b| b.value).collect();
+    let mut draw_row = |y: usize, values: &[usize]| {
+        for (x, &value) in values.iter().enumerate() {
+            image.put_pixel(x as u32, y as u32, Rgba(hue(value, top).to_array()));
+        }
+    };
+    draw_row(0, &values);
+    for (y, ops) in work_chunks(trace, every).enumerate() {
+        for op in ops {
+            match op {
+                Operation::Swap(i, j) => values.swap(*i, *j),
+                Operation::Overwrite(i, bar) => values[*i] = bar.value,
+                _ => {}
+            }
+        }
+        draw_row(y + 1, &values);
+    }
+    image
+}
@@ -0,0 +1,215 @@
+//! Offscreen rendering of sort runs for export, done on the CPU so it works
+//! without a window.
+
+pub mod canvas;
+pub mod gif;
+pub mod history;
+pub mod png;
+pub mod wav;
+pub mod y4m;
+
+use crate::gui::check_theme_consistency::apply_theme_consistency;
+use crate::gui::renderers::{draw_run, BarRenderer, Bars};
+use crate::gui_backend::gui::Theme;
+use crate::models::SortBar;
+use crate::playback::Playback;
+use crate::sorting::Operation;
+use image::{ImageResult, RgbaImage};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::sync::Arc;
+
+use self::canvas::Canvas;
+pub use self::gif::GifPalette;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    /// One animated GIF.
+    #[default]
+    Gif,
+    /// A directory of numbered lossless PNG frames.
+    PngSequence,
+    /// An uncompressed YUV4MPEG2 video stream.
+    Y4m,
+    /// The run as sound, timed to match the video formats.
+    Wav,
+}
+
+impl ExportFormat {
+    pub fn all() -> &'static [ExportFormat] {
+        &[
+            ExportFormat::Gif,
+            ExportFormat::PngSequence,
+            ExportFormat::Y4m,
+            ExportFormat::Wav,
+        ]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Gif => "GIF",
+            ExportFormat::PngSequence => "PNG frames",
+            ExportFormat::Y4m => "Y4M video",
+            ExportFormat::Wav => "WAV audio",
+        }
+    }
+}
+
+/// Size, colours and view of exported frames.
+#[derive(Debug, Clone)]
+pub struct FrameStyle {
+    pub width: u32,
+    pub height: u32,
+    pub theme: Theme,
+    /// How the bars are drawn, as on screen.
+    pub renderer: Arc<dyn BarRenderer>,
+}
+
+impl Default for FrameStyle {
+    fn default() -> Self {
+        Self {
+            width: 640,
+            height: 360,
+            theme: Theme::default(),
+            renderer: Arc::new(Bars),
+        }
+    }
+}
+
+/// Everything that decides what an export looks like.
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    pub style: FrameStyle,
+    /// Operations applied between frames.
+    pub stride: usize,
+    /// Display time of each frame. GIF counts in hundredths of a second and
+    /// many viewers slow anything under 20 ms down; Y4M turns it into a
+    /// frame rate, and WAV plays each frame's operations within it.
+    pub delay_ms: u32,
+    /// Only used for GIF.
+    pub palette: GifPalette,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::default(),
+            style: FrameStyle::default(),
+            stride: 10,
+            delay_ms: 40,
+            palette: GifPalette::default(),
+        }
+    }
+}
+
+/// Write the run of `trace` over `input` to `path` in `options.format`.
+/// For a PNG sequence `path` is the directory the frames go in.
+pub fn export_run(
+    input: &[SortBar],
+    trace: &[Operation],
+    options: &ExportOptions,
+    path: &Path,
+) -> ImageResult<()> {
+    match options.format {
+        ExportFormat::Gif => {
+            let file = BufWriter::new(File::create(path)?);
+            gif::write_gif(input, trace, options, file)
+        }
+        ExportFormat::PngSequence => png::write_png_sequence(input, trace, options, path),
+        ExportFormat::Y4m => {
+            let file = BufWriter::new(File::create(path)?);
+            Ok(y4m::write_y4m(input, trace, options, file)?)
+        }
+        ExportFormat::Wav => {
+            let file = BufWriter::new(File::create(path)?);
+            Ok(wav::write_wav(input, trace, options, file)?)
+        }
+    }
+}
+
+/// Draw `bars` on their own through the style's renderer, as the
+/// visualizer's central panel does before a run.
+pub fn render_bars(bars: &[SortBar], style: &FrameStyle) -> RgbaImage {
+    let default_color = style.theme.bar_color();
+    Canvas::new(style.width, style.height, style.theme).paint(|painter, rect| {
+        style.renderer.draw(painter, rect, bars, default_color);
+    })
+}
+
+/// Operations in `trace` that count towards a stride or a history row: all
+/// but markers (see `Operation::is_marker`), which `Playback::advance`
+/// doesn't count either.
+pub fn work_len(trace: &[Operation]) -> usize {
+    trace.iter().filter(|op| !op.is_marker()).count()
+}
+
+/// `trace` in pieces of `stride` operations that aren't markers, each
+/// ending with the last of them, so markers go with what follows them.
+/// Markers after the last such operation change nothing and are left out.
+pub fn work_chunks(trace: &[Operation], stride: usize) -> impl Iterator<Item = &[Operation]> {
+    let stride = stride.max(1);
+    let mut rest = trace;
+    std::iter::from_fn(move || {
+        let mut counted = 0;
+        let mut end = None;
+        for (k, op) in rest.iter().enumerate() {
+            if !op.is_marker() {
+                counted += 1;
+                end = Some(k + 1);
+                if counted == stride {
+                    break;
+                }
+            }
+        }
+        let (chunk, tail) = rest.split_at(end?);
+        rest = tail;
+        Some(chunk)
+    })
+}
+
+/// Replays `trace` over `input` and renders a frame before the first
+/// operation, after every `stride` operations that aren't markers, and once
+/// more at the end. Frames show what the central panel would at those
+/// points, overlays included.
+pub fn frames<'a>(
+    input: &[SortBar],
+    trace: &'a [Operation],
+    stride: usize,
+    style: &FrameStyle,
+) -> impl Iterator<Item = RgbaImage> + 'a {
+    let default_color = style.theme.bar_color();
+    let mut bars = input.to_vec();
+    apply_theme_consistency(&mut bars, style.theme);
+    let mut playback = Playback::default();
+    let mut canvas = Canvas::new(style.width, style.height, style.theme);
+    let renderer = Arc::clone(&style.renderer);
+    let mut render = move |bars: &[SortBar], playback: &Playback| {
+        canvas.paint(|painter, rect| {
+            draw_run(
+                painter,
+                rect,
+                renderer.as_ref(),
+                bars,
+                playback,
+                default_color,
+            );
+        })
+    };
+    let first = render(&bars, &playback);
+    let rest = work_chunks(trace, stride).map(move |ops| {
+        for op in ops {
+            playback.push(op.clone());
+            playback.step_forward(&mut bars, default_color);
+        }
+        render(&bars, &playback)
+    });
+    std::iter::once(first).chain(rest)
+}
+
+/// Number of frames `frames` yields for a trace of `ops` operations that
+/// aren't markers, as counted by `work_len`.
+pub fn frame_count(ops: usize, stride: usize) -> usize {
+    1 + ops.div_ceil(stride.max(1))
+}
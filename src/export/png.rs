@@ -0,0 +1,21 @@
+use super::{frames, ExportOptions};
+use crate::models::SortBar;
+use crate::sorting::Operation;
+use image::ImageResult;
+use std::fs;
+use std::path::Path;
+
+/// Write every frame of the run as `frame_000000.png`, `frame_000001.png`, ...
+/// into `dir`, creating it if needed.
+pub fn write_png_sequence(
+    input: &[SortBar],
+    trace: &[Operation],
+    options: &ExportOptions,
+    dir: &Path,
+) -> ImageResult<()> {
+    fs::create_dir_all(dir)?;
+    for (i, image) in frames(input, trace, options.stride, &options.style).enumerate() {
+        image.save(dir.join(format!("frame_{:06}.png", i)))?;
+    }
+    Ok(())
+}
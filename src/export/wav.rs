@@ -0,0 +1,129 @@
+use super::{frame_count, work_chunks, work_len, ExportOptions};
+use crate::models::SortBar;
+use crate::playback::apply;
+use crate::sorting::Operation;
+use eframe::egui::Color32;
+use std::f32::consts::TAU;
+use std::io::{self, Write};
+
+pub const SAMPLE_RATE: u32 = 44_100;
+
+/// Pitch of the smallest and largest values.
+const MIN_HZ: f32 = 120.0;
+const MAX_HZ: f32 = 1_200.0;
+const TONE_MS: u32 = 60;
+const ATTACK_MS: u32 = 4;
+/// At most this many operations of a frame are heard, spread evenly over
+/// it; beyond that the tones smear into noise anyway.
+const MAX_TONES_PER_FRAME: usize = 8;
+
+/// Turn the run of `trace` over `input` into mono PCM at `SAMPLE_RATE`.
+///
+/// Each `Compare`, `Swap` and `Overwrite` plays a short tone for every bar
+/// value it touches, pitched from low to high by value. The audio is laid out
+/// on the same clock as the video exports: one frame of `options.delay_ms`
+/// per `options.stride` operations other than markers, starting with a
+/// silent frame for the input, so the WAV lines up with a GIF or Y4M of the
+/// same run and with playback in the app.
+pub fn synthesize(input: &[SortBar], trace: &[Operation], options: &ExportOptions) -> Vec<i16> {
+    let stride = options.stride.max(1);
+    let frame_len = (SAMPLE_RATE * options.delay_ms.max(1) / 1_000) as usize;
+    let tone_len = (SAMPLE_RATE * TONE_MS / 1_000) as usize;
+    let top = input
+        .iter()
+        .map(|b| b.value + 1)
+        .max()
+        .unwrap_or(1)
+        .max(input.len()) as f32;
+
+    let mut mix = vec![0f32; frame_count(work_len(trace), stride) * frame_len];
+    let mut bars = input.to_vec();
+    for (k, ops) in work_chunks(trace, stride).enumerate() {
+        let audible = ops
+            .iter()
+            .filter(|op| {
+                matches!(
+                    op,
+                    Operation::Compare(..) | Operation::Swap(..) | Operation::Overwrite(..)
+                )
+            })
+            .count();
+        let heard = audible.min(MAX_TONES_PER_FRAME);
+        // The first frame shows the input, so chunk k plays under frame k + 1
+        let frame_start = (k + 1) * frame_len;
+        let mut seen = 0;
+        let mut played = 0;
+        for op in ops {
+            let values = match op {
+                Operation::Compare(i, j) => vec![bars[*i].value, bars[*j].value],
+                Operation::Swap(i, j) => vec![bars[*j].value, bars[*i].value],
+                Operation::Overwrite(_, bar) => vec![bar.value],
+                _ => vec![],
+            };
+            apply(op, &mut bars, Color32::WHITE);
+            if values.is_empty() {
+                continue;
+            }
+            // Pick `heard` of the audible operations, evenly spaced
+            if played < heard && seen * heard >= played * audible {
+                let start = frame_start + played * frame_len / heard;
+                for value in values {
+                    add_tone(&mut mix[start..], frequency(value as f32 / top), tone_len);
+                }
+                played += 1;
+            }
+            seen += 1;
+        }
+    }
+
+    let peak = mix.iter().fold(0f32, |m, s| m.max(s.abs()));
+    let gain = if peak > 0.9 { 0.9 / peak } else { 1.0 };
+    mix.into_iter()
+        .map(|s| (s * gain * i16::MAX as f32) as i16)
+        .collect()
+}
+
+/// Map `position` in `0.0..1.0` onto an exponential pitch scale, so equal
+/// steps in value sound like equal intervals.
+fn frequency(position: f32) -> f32 {
+    MIN_HZ * (MAX_HZ / MIN_HZ).powf(position)
+}
+
+/// Add a sine tone with a short attack and an exponential decay to the start
+/// of `out`, cutting it off at the end of the buffer.
+fn add_tone(out: &mut [f32], hz: f32, len: usize) {
+    let attack = (SAMPLE_RATE * ATTACK_MS / 1_000) as f32;
+    for (t, sample) in out.iter_mut().take(len).enumerate() {
+        let t = t as f32;
+        let envelope = (t / attack).min(1.0) * (-5.0 * t / len as f32).exp();
+        *sample += 0.25 * envelope * (TAU * hz * t / SAMPLE_RATE as f32).sin();
+    }
+}
+
+/// Write the sonified run as a 16-bit mono WAV file.
+pub fn write_wav(
+    input: &[SortBar],
+    trace: &[Operation],
+    options: &ExportOptions,
+    mut out: impl Write,
+) -> io::Result<()> {
+    let samples = synthesize(input, trace, options);
+    let data_len = (samples.len() * 2) as u32;
+    out.write_all(b"RIFF")?;
+    out.write_all(&(36 + data_len).to_le_bytes())?;
+    out.write_all(b"WAVEfmt ")?;
+    out.write_all(&16u32.to_le_bytes())?;
+    // PCM, one channel
+    out.write_all(&1u16.to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?;
+    out.write_all(&SAMPLE_RATE.to_le_bytes())?;
+    out.write_all(&(SAMPLE_RATE * 2).to_le_bytes())?;
+    out.write_all(&2u16.to_le_bytes())?;
+    out.write_all(&16u16.to_le_bytes())?;
+    out.write_all(b"data")?;
+    out.write_all(&data_len.to_le_bytes())?;
+    for sample in samples {
+        out.write_all(&sample.to_le_bytes())?;
+    }
+    out.flush()
+}
@@ -0,0 +1,47 @@
+use super::{frames, ExportOptions};
+use crate::models::SortBar;
+use crate::sorting::Operation;
+use image::RgbaImage;
+use std::io::{self, Write};
+
+/// Write the run as an uncompressed YUV4MPEG2 stream, which ffmpeg and most
+/// other video tools read directly.
+///
+/// Frames are stored in 4:4:4 so thin bars keep their exact colours instead
+/// of bleeding into their neighbours through chroma subsampling.
+pub fn write_y4m(
+    input: &[SortBar],
+    trace: &[Operation],
+    options: &ExportOptions,
+    mut out: impl Write,
+) -> io::Result<()> {
+    let style = &options.style;
+    writeln!(
+        out,
+        "YUV4MPEG2 W{} H{} F1000:{} Ip A1:1 C444 XCOLORRANGE=LIMITED",
+        style.width,
+        style.height,
+        options.delay_ms.max(1)
+    )?;
+    for image in frames(input, trace, options.stride, style) {
+        out.write_all(b"FRAME\n")?;
+        out.write_all(&to_yuv444(&image))?;
+    }
+    out.flush()
+}
+
+/// Convert to planar limited-range BT.601 Y, U and V.
+fn to_yuv444(image: &RgbaImage) -> Vec<u8> {
+    let n = (image.width() * image.height()) as usize;
+    let mut planes = vec![0u8; 3 * n];
+    for (i, pixel) in image.pixels().enumerate() {
+        let [r, g, b, _] = pixel.0.map(f32::from);
+        let y = 16.0 + (65.481 * r + 128.553 * g + 24.966 * b) / 255.0;
+        let u = 128.0 + (-37.797 * r - 74.203 * g + 112.0 * b) / 255.0;
+        let v = 128.0 + (112.0 * r - 93.786 * g - 18.214 * b) / 255.0;
+        planes[i] = y.round() as u8;
+        planes[n + i] = u.round() as u8;
+        planes[2 * n + i] = v.round() as u8;
+    }
+    planes
+}
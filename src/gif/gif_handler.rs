@@ -35,9 +35,9 @@ impl GifHandler {
         self.current_frame = 0;
         self.elapsed = 0.0;
 
-        use std::io::Cursor;
         use image::codecs::gif::GifDecoder;
         use image::AnimationDecoder;
+        use std::io::Cursor;
 
         // Decode GIF and collect frames
         let reader = GifDecoder::new(Cursor::new(gif_bytes))?;
@@ -54,14 +54,21 @@ impl GifHandler {
             let w = w_u32 as usize;
             let h = h_u32 as usize;
             let raw = buffer.into_raw(); // Vec<u8>
-            let pixels = raw.chunks_exact(4)
+            let pixels = raw
+                .chunks_exact(4)
                 .map(|ch| egui::Color32::from_rgba_unmultiplied(ch[0], ch[1], ch[2], ch[3]))
                 .collect::<Vec<_>>();
 
-            let image = egui::ColorImage { size: [w, h], pixels };
+            let image = egui::ColorImage {
+                size: [w, h],
+                pixels,
+            };
             let frame_name = format!("{}_frame{}", name, frame_index);
             let texture = ctx.load_texture(frame_name, image, egui::TextureOptions::default());
-            self.frames.push(Frame { texture, duration: delay });
+            self.frames.push(Frame {
+                texture,
+                duration: delay,
+            });
         }
 
         Ok(())
@@ -71,7 +78,9 @@ impl GifHandler {
         if !self.frames.is_empty() {
             self.elapsed += delta_time;
             // advance frame while enough time has passed
-            while self.elapsed >= self.frames[self.current_frame].duration && !self.frames.is_empty() {
+            while self.elapsed >= self.frames[self.current_frame].duration
+                && !self.frames.is_empty()
+            {
                 self.elapsed -= self.frames[self.current_frame].duration;
                 self.current_frame = (self.current_frame + 1) % self.frames.len();
             }
@@ -86,7 +95,7 @@ impl GifHandler {
         // Ensure egui repaints every frame for smooth animation
         ui.ctx().request_repaint();
         self.update(ui.input(|i| i.unstable_dt));
-        
+
         if let Some(texture) = self.get_current_frame() {
             ui.image((texture.id(), egui::Vec2::from(size)));
         } else {
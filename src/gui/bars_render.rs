@@ -1,38 +1,213 @@
+use crate::array_file;
+use crate::code::speed::{self, MaxSpeedReport};
+use crate::export::history::{self, history_image};
+use crate::export::{export_run, ExportFormat, ExportOptions, GifPalette};
 use crate::gui::check_theme_consistency::apply_theme_consistency;
+use crate::gui::heap_tree::draw_heap_tree;
+use crate::gui::network::{draw_network, MAX_WIRES};
+use crate::gui::renderers::{self, BarRenderer};
 use crate::gui_backend::gui::Theme;
+use crate::input::Distribution;
 use crate::models::SortBar;
-use crate::sorting::{start_sort, Operation, SortingAlgorithm};
+use crate::playback::{Playback, PlaybackSpeed, PowerNode, UNDO_LIMIT};
+use crate::sorting::{
+    merge_report, record_trace, spawn_worker, start_sort, CancelToken, MergeReport, Operation,
+    PowerSort, SortAlgorithm, SortWorker, TimSort, OP_CHANNEL_CAPACITY,
+};
 use eframe::egui::{self, Color32};
 use rand::seq::SliceRandom;
-use std::sync::mpsc;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How many recorded-but-unplayed operations to hold before we stop draining
+/// the worker channel (which in turn blocks the worker).
+pub(crate) const MAX_LOOKAHEAD: usize = 100_000;
+
+/// While a run is still coming in, the history image is rebuilt from the
+/// whole trace at most this often, and at most once per ten times as long
+/// as the last rebuild took.
+const HISTORY_REFRESH: Duration = Duration::from_millis(500);
+
+/// Choice between ops/sec and ops/frame pacing, with a slider for the rate.
+pub fn speed_controls(ui: &mut egui::Ui, speed: &mut PlaybackSpeed) {
+    ui.label("Speed:");
+    let per_second = matches!(speed, PlaybackSpeed::OpsPerSecond(_));
+    ui.horizontal(|ui| {
+        if ui.selectable_label(per_second, "ops/sec").clicked() && !per_second {
+            *speed = PlaybackSpeed::default();
+        }
+        if ui.selectable_label(!per_second, "ops/frame").clicked() && per_second {
+            *speed = PlaybackSpeed::OpsPerFrame(1);
+        }
+    });
+    match speed {
+        PlaybackSpeed::OpsPerSecond(rate) => {
+            ui.add(egui::Slider::new(rate, 1.0..=20_000.0).logarithmic(true))
+                .on_hover_text("Operations played back per second");
+        }
+        PlaybackSpeed::OpsPerFrame(n) => {
+            ui.add(egui::Slider::new(n, 1..=1_000).logarithmic(true))
+                .on_hover_text("Operations played back per frame");
+        }
+    }
+}
 
 pub struct SortVisualizerApp {
     pub bars: Vec<SortBar>,
-    pub algorithm: SortingAlgorithm,
+    pub algorithm: Arc<dyn SortAlgorithm>,
     pub num_bars: usize,
-    pub sorting: bool,
     rx: mpsc::Receiver<Operation>,
-    pub tx: mpsc::Sender<Operation>,
+    pub tx: mpsc::SyncSender<Operation>,
+    worker: Option<SortWorker>,
+    pub playback: Playback,
+    // The array the recorded run started from
+    run_input: Option<Vec<SortBar>>,
     current_theme: Theme,
     pub status_message: String,
+    pub export_options: ExportOptions,
+    export: Option<Export>,
+    // Where a running max speed sort reports how it went
+    max_speed_report: Option<mpsc::Receiver<MaxSpeedReport>>,
+    /// How the bars are drawn, picked from `renderers`.
+    pub renderer: Arc<dyn BarRenderer>,
+    pub renderers: Vec<Arc<dyn BarRenderer>>,
+    // Size in physical pixels of the bar area as last drawn
+    view_size: Option<[u32; 2]>,
+    pub show_history: bool,
+    /// Rows the history image aims for; sets how many operations each
+    /// row covers.
+    pub history_rows: usize,
+    history: Option<History>,
+    /// Show heap sort's heap as a tree in its own window.
+    pub show_heap: bool,
+    /// Show a sorting network's wires and comparators, layer by layer, in
+    /// its own window.
+    pub show_network: bool,
+    /// Show the ranges of the active recursive calls in their own window.
+    pub show_call_stack: bool,
+    /// Show the algorithm's pseudocode, with the current line marked, in a
+    /// side panel.
+    pub show_pseudocode: bool,
+    // Powersort and TimSort run on the same array, for their merge policies
+    merge_comparison: Option<Vec<MergeReport>>,
+}
+
+/// `twice_mid / 2n` in binary to `digits` places, e.g. `0.0110`.
+fn binary_fraction(twice_mid: usize, n: usize, digits: usize) -> String {
+    let mut text = String::from("0.");
+    let mut x = twice_mid;
+    for _ in 0..digits {
+        x *= 2;
+        if x >= 2 * n {
+            text.push('1');
+            x -= 2 * n;
+        } else {
+            text.push('0');
+        }
+    }
+    text
+}
+
+/// How Powersort arrived at the power of `node`: the midpoints of its two
+/// runs as binary fractions of `n`, which agree up to the digit the power
+/// names.
+fn show_power_node(ui: &mut egui::Ui, node: &PowerNode, n: usize) {
+    ui.label(format!(
+        "Boundary at {}: power {}",
+        node.right.start, node.power
+    ));
+    for (name, run) in [("A", &node.left), ("B", &node.right)] {
+        ui.monospace(format!(
+            "{} [{}, {}) midpoint {}",
+            name,
+            run.start,
+            run.end,
+            binary_fraction(run.start + run.end, n, node.power)
+        ));
+    }
+    ui.small("Lower powers sit higher in the merge tree and are merged later.");
+}
+
+/// The history image of the recorded run, uploaded for display.
+struct History {
+    // Operations it covers, markers aside, and operations per row
+    key: (usize, usize),
+    texture: egui::TextureHandle,
+    // When it was built and how long that took
+    built: Instant,
+    cost: Duration,
+}
+
+/// An export running on a background thread; it reports back with a
+/// status line.
+struct Export {
+    cancel: CancelToken,
+    rx: mpsc::Receiver<String>,
 }
 
 impl SortVisualizerApp {
     pub fn reset_bars(&mut self) {
-        // Abandon any in-progress sort by replacing the channel and clearing sorting flag
-        let (new_tx, new_rx) = mpsc::channel();
-        self.tx = new_tx;
-        self.rx = new_rx;
-        self.sorting = false;
+        // Stop any in-progress sort before replacing the array
+        let cancelled = self.is_sorting();
+        self.cancel_sort();
 
         self.bars = (0..self.num_bars).map(SortBar::new).collect();
         // Apply current theme to newly reset bars
         self.apply_theme(self.current_theme);
         // Reset status message
-        self.status_message = "Array reset with unique values".to_string();
+        self.status_message = if cancelled {
+            "Sort cancelled - array reset with unique values".to_string()
+        } else {
+            "Array reset with unique values".to_string()
+        };
+    }
+
+    /// Whether a run's worker is still held. It is started with the run and
+    /// joined by `finish_run` once playback first reaches `Operation::Done`,
+    /// or by `stop_worker` on a cancel or reset, so stepping back over a
+    /// finished run leaves this false.
+    pub fn is_sorting(&self) -> bool {
+        self.worker.is_some()
+    }
+
+    /// Stop the current run: cancel and join its worker and drop whatever it
+    /// recorded, leaving the bars as they are on screen.
+    pub fn cancel_sort(&mut self) {
+        let was_running = self.is_sorting();
+        self.stop_worker();
+        self.discard_run();
+        if was_running {
+            self.status_message = format!("{} cancelled", self.algorithm.name());
+        }
+    }
+
+    /// Cancel and join the worker thread, if any. The channel is replaced
+    /// first so a worker blocked on a full channel wakes up, and so nothing
+    /// it already queued leaks into the next run.
+    fn stop_worker(&mut self) {
+        if let Some(worker) = &self.worker {
+            worker.cancel();
+        }
+        let (new_tx, new_rx) = mpsc::sync_channel(OP_CHANNEL_CAPACITY);
+        self.tx = new_tx;
+        self.rx = new_rx;
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+
+    /// Forget the recorded run once the array it belongs to is replaced, so
+    /// stepping back can't apply it to different bars.
+    fn discard_run(&mut self) {
+        self.playback.clear();
+        self.run_input = None;
+        self.history = None;
     }
 
     pub fn shuffle_bars(&mut self) {
+        self.discard_run();
         self.bars.shuffle(&mut rand::thread_rng());
         let duplicate_count = self.count_duplicates();
         if duplicate_count > 0 {
@@ -68,8 +243,8 @@ impl SortVisualizerApp {
         let mut unique_bars = Vec::new();
 
         for bar in &self.bars {
-            if !seen_values.contains_key(&bar.value) {
-                seen_values.insert(bar.value, true);
+            if let std::collections::hash_map::Entry::Vacant(e) = seen_values.entry(bar.value) {
+                e.insert(true);
                 unique_bars.push(bar.clone());
             }
         }
@@ -86,6 +261,7 @@ impl SortVisualizerApp {
             }
         }
 
+        self.discard_run();
         self.bars = unique_bars;
         // Apply current theme to the deduplicated bars
         apply_theme_consistency(&mut self.bars, self.current_theme);
@@ -109,6 +285,7 @@ impl SortVisualizerApp {
             bars.push(SortBar::new(value));
         }
 
+        self.discard_run();
         self.bars = bars;
         // Shuffle to make the duplicates more interesting
         self.shuffle_bars();
@@ -120,75 +297,738 @@ impl SortVisualizerApp {
         self.status_message = format!("Generated array with {} duplicates", duplicate_count);
     }
 
+    /// Replace the bars with `num_bars` values shaped like `distribution`.
+    pub fn generate(&mut self, distribution: Distribution) {
+        self.discard_run();
+        self.bars = distribution
+            .generate(self.num_bars, &mut rand::thread_rng())
+            .into_iter()
+            .map(SortBar::new)
+            .collect();
+        apply_theme_consistency(&mut self.bars, self.current_theme);
+        self.status_message = format!("Generated {} input", distribution);
+    }
+
+    /// Replace the bars with the values in `path`. Problems with the file are
+    /// reported in the status line and leave the bars untouched.
+    pub fn open_array(&mut self, path: &Path) {
+        match array_file::load(path) {
+            Ok(values) => {
+                self.cancel_sort();
+                self.num_bars = values.len();
+                self.bars = values.into_iter().map(SortBar::new).collect();
+                apply_theme_consistency(&mut self.bars, self.current_theme);
+                self.status_message =
+                    format!("Loaded {} values from {}", self.num_bars, path.display());
+            }
+            Err(e) => {
+                self.status_message = format!("Could not open {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Write the current bar values to `path`.
+    pub fn save_array(&mut self, path: &Path) {
+        let values: Vec<usize> = self.bars.iter().map(|b| b.value).collect();
+        self.status_message = match array_file::save(path, &values) {
+            Ok(()) => format!("Saved {} values to {}", values.len(), path.display()),
+            Err(e) => format!("Could not save {}: {}", path.display(), e),
+        };
+    }
+
     pub fn start_sorting(&mut self, max_speed: bool) {
-        if self.sorting {
-            println!("[DEBUG] Sorting already in progress, ignoring start_sorting call.");
+        if self.is_sorting() {
             return;
         }
-        println!(
-            "[DEBUG] Starting sorting with algorithm: {:?}, max_speed: {}",
-            self.algorithm, max_speed
-        );
-        self.sorting = true;
+        self.stop_worker();
+        self.playback.clear();
+        self.playback.paused = false;
+        self.history = None;
         // Ensure displayed bars have the correct colors before sorting
         apply_theme_consistency(&mut self.bars, self.current_theme);
+        self.run_input = Some(self.bars.clone());
         // Clone and enforce correct bar colors before starting
         let mut bars_clone = self.bars.clone();
         apply_theme_consistency(&mut bars_clone, self.current_theme);
         let tx = self.tx.clone();
         if max_speed {
-            println!("[DEBUG] Calling max_speed_sort...");
-            crate::code::speed::max_speed_sort(&mut bars_clone, tx);
+            let threads = speed::available_threads();
+            let (report_tx, report_rx) = mpsc::channel();
+            self.worker = Some(spawn_worker(tx, move |sink| {
+                let report = speed::max_speed_sort(&bars_clone, threads, sink)?;
+                let _ = report_tx.send(report);
+                Ok(())
+            }));
+            self.max_speed_report = Some(report_rx);
+            self.status_message = "Max speed sort running".to_string();
         } else {
-            let algo = self.algorithm;
-            println!("[DEBUG] Calling start_sort with algorithm: {:?}", algo);
-            start_sort(algo, bars_clone, tx);
+            self.max_speed_report = None;
+            let algo = Arc::clone(&self.algorithm);
+            self.status_message = format!("{} running", algo.name());
+            self.worker = Some(start_sort(algo, bars_clone, tx));
+        }
+    }
+
+    /// Render a run of the current algorithm to `path` in the chosen export
+    /// format on a background thread. The run starts from the input of the
+    /// recorded run if there is one, and from the bars on screen otherwise.
+    pub fn export_run(&mut self, path: PathBuf) {
+        if self.export.is_some() {
+            return;
+        }
+        let input = self.run_input.clone().unwrap_or_else(|| self.bars.clone());
+        let algorithm = Arc::clone(&self.algorithm);
+        let mut options = self.export_options.clone();
+        options.style.theme = self.current_theme;
+        options.style.renderer = Arc::clone(&self.renderer);
+        let label = options.format.label();
+        let cancel = CancelToken::new();
+        let (tx, rx) = mpsc::channel();
+        {
+            let cancel = cancel.clone();
+            thread::spawn(move || {
+                let status = match record_trace(algorithm.as_ref(), &input, cancel) {
+                    Ok(trace) => match export_run(&input, &trace, &options, &path) {
+                        Ok(()) => format!("Exported {} to {}", label, path.display()),
+                        Err(e) => format!("Could not export {}: {}", path.display(), e),
+                    },
+                    Err(_) => format!("{} export cancelled", label),
+                };
+                let _ = tx.send(status);
+            });
+        }
+        self.status_message = format!("Exporting {} run to {}...", self.algorithm.name(), label);
+        self.export = Some(Export { cancel, rx });
+    }
+
+    /// Ask where an export in `format` should go.
+    fn pick_export_path(format: ExportFormat) -> Option<PathBuf> {
+        match format {
+            ExportFormat::Gif => rfd::FileDialog::new()
+                .add_filter("GIF", &["gif"])
+                .set_file_name("sort.gif")
+                .save_file(),
+            ExportFormat::PngSequence => rfd::FileDialog::new()
+                .set_title("Folder for PNG frames")
+                .pick_folder(),
+            ExportFormat::Y4m => rfd::FileDialog::new()
+                .add_filter("YUV4MPEG2", &["y4m"])
+                .set_file_name("sort.y4m")
+                .save_file(),
+            ExportFormat::Wav => rfd::FileDialog::new()
+                .add_filter("WAV", &["wav"])
+                .set_file_name("sort.wav")
+                .save_file(),
         }
     }
 
-    fn handle_ops(&mut self) {
-        let mut op_count = 0;
-        while let Ok(op) = self.rx.try_recv() {
-            op_count += 1;
-            match op {
-                Operation::Compare(i, j) => {
-                    println!("[DEBUG] Operation::Compare({}, {})", i, j);
-                    self.bars[i].color = Color32::YELLOW;
-                    self.bars[j].color = Color32::YELLOW;
+    /// Pick up the result of a finished export.
+    fn poll_export(&mut self) {
+        let Some(export) = &self.export else {
+            return;
+        };
+        match export.rx.try_recv() {
+            Ok(status) => {
+                self.status_message = status;
+                self.export = None;
+            }
+            Err(mpsc::TryRecvError::Empty) => {}
+            Err(mpsc::TryRecvError::Disconnected) => self.export = None,
+        }
+    }
+
+    /// Export settings and the button that starts an export.
+    pub fn show_export_controls(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Export").show(ui, |ui| {
+            let options = &mut self.export_options;
+            ui.horizontal_wrapped(|ui| {
+                for &format in ExportFormat::all() {
+                    ui.radio_value(&mut options.format, format, format.label());
                 }
-                Operation::Swap(i, j) => {
-                    println!("[DEBUG] Operation::Swap({}, {})", i, j);
-                    self.bars.swap(i, j);
-                    self.bars[i].color = Color32::GREEN;
-                    self.bars[j].color = Color32::GREEN;
+            });
+            // Sound has no picture
+            if options.format != ExportFormat::Wav {
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut options.style.width).range(16..=4096));
+                    ui.label("×");
+                    ui.add(egui::DragValue::new(&mut options.style.height).range(16..=4096));
+                    if let Some([width, height]) = self.view_size {
+                        if ui
+                            .small_button("Match view")
+                            .on_hover_text("Use the size of the bars on screen")
+                            .clicked()
+                        {
+                            options.style.width = width.clamp(16, 4096);
+                            options.style.height = height.clamp(16, 4096);
+                        }
+                    }
+                });
+            }
+            ui.add(
+                egui::Slider::new(&mut options.stride, 1..=1_000)
+                    .logarithmic(true)
+                    .text("ops/frame"),
+            );
+            if options.format != ExportFormat::PngSequence {
+                ui.add(egui::Slider::new(&mut options.delay_ms, 10..=1_000).text("ms/frame"));
+            }
+            if options.format == ExportFormat::Gif {
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut options.palette, GifPalette::Adaptive, "Colour");
+                    ui.radio_value(&mut options.palette, GifPalette::Grayscale, "Grey");
+                });
+            }
+            if !self.playback.is_empty() {
+                ui.small(format!(
+                    "≈ {} frames",
+                    crate::export::frame_count(self.playback.work_len(), options.stride)
+                ));
+            }
+            let format = options.format;
+            let idle = self.export.is_none();
+            if ui
+                .add_enabled(
+                    idle,
+                    egui::Button::new(format!("Export {}…", format.label())),
+                )
+                .clicked()
+            {
+                if let Some(path) = Self::pick_export_path(format) {
+                    self.export_run(path);
                 }
-                Operation::Overwrite(i, bar) => {
-                    println!("[DEBUG] Operation::Overwrite({}, {:?})", i, bar.value);
-                    self.bars[i] = bar;
+            }
+        });
+    }
+
+    /// Pull newly recorded operations into the trace and play back whatever
+    /// is due after a frame lasting `dt` seconds. A max speed run's result
+    /// is played as soon as it arrives, whatever the playback speed.
+    fn handle_ops(&mut self, dt: f32) {
+        self.poll_export();
+        let max_speed = self.max_speed_report.is_some();
+        while max_speed || self.playback.buffered() < MAX_LOOKAHEAD {
+            match self.rx.try_recv() {
+                Ok(op) => self.playback.push(op),
+                Err(_) => break,
+            }
+        }
+
+        let default = self.default_color();
+        let speed = self.playback.speed;
+        if max_speed {
+            self.playback.speed = PlaybackSpeed::OpsPerFrame(usize::MAX);
+        }
+        let done = self.playback.advance(dt, &mut self.bars, default);
+        self.playback.speed = speed;
+        if done {
+            self.finish_run();
+        }
+    }
+
+    /// Join the worker once playback reaches `Operation::Done` and say how
+    /// the run went. Reaching it again after stepping back changes nothing.
+    fn finish_run(&mut self) {
+        let Some(worker) = self.worker.take() else {
+            return;
+        };
+        let _ = worker.join();
+        self.status_message = match self.max_speed_report.take() {
+            Some(rx) => match rx.try_recv() {
+                Ok(report) => format!(
+                    "Max speed sort done: {} values in {:.3} ms on {} thread{}",
+                    self.bars.len(),
+                    report.elapsed.as_secs_f64() * 1000.0,
+                    report.threads,
+                    if report.threads == 1 { "" } else { "s" }
+                ),
+                Err(_) => "Max speed sort done".to_string(),
+            },
+            None => format!("{} done", self.algorithm.name()),
+        };
+    }
+
+    /// The bar colour that `Color32::WHITE` resets map to under the current theme.
+    fn default_color(&self) -> Color32 {
+        self.current_theme.bar_color()
+    }
+
+    /// Playback speed selector plus a progress readout for the current trace.
+    pub fn show_speed_controls(&mut self, ui: &mut egui::Ui) {
+        speed_controls(ui, &mut self.playback.speed);
+        ui.small(format!(
+            "op {} / {}",
+            self.playback.cursor(),
+            self.playback.len()
+        ));
+    }
+
+    /// Picker for how the bars are drawn. Switching takes effect on the next
+    /// frame, also during a run.
+    pub fn show_view_controls(&mut self, ui: &mut egui::Ui) {
+        ui.label("View:");
+        egui::ComboBox::from_id_salt("renderer")
+            .selected_text(self.renderer.name())
+            .show_ui(ui, |ui| {
+                for renderer in &self.renderers {
+                    let selected = renderer.name() == self.renderer.name();
+                    if ui.selectable_label(selected, renderer.name()).clicked() {
+                        self.renderer = Arc::clone(renderer);
+                    }
                 }
-                Operation::SetColor(i, col) => {
-                    // remap "WHITE reset" to your theme’s default background color
-                    let default = match self.current_theme {
-                        Theme::Light => Color32::BLACK,
-                        Theme::Dark => Color32::WHITE,
-                    };
-                    println!("[DEBUG] Operation::SetColor({}, {:?})", i, col);
-                    self.bars[i].color = if col == Color32::WHITE { default } else { col };
+            });
+        ui.checkbox(&mut self.show_history, "History")
+            .on_hover_text("The whole run as one image, one row per array state");
+        ui.checkbox(&mut self.show_heap, "Heap tree")
+            .on_hover_text("Heap sort's array drawn as the binary tree it encodes");
+        ui.checkbox(&mut self.show_network, "Network")
+            .on_hover_text(
+                "A sorting network's comparators by layer; those in one layer run at once",
+            );
+        ui.checkbox(&mut self.show_call_stack, "Call stack")
+            .on_hover_text(
+            "The ranges divide-and-conquer sorts are recursing on, or the runs waiting to merge",
+        );
+        ui.checkbox(&mut self.show_pseudocode, "Pseudocode")
+            .on_hover_text("The algorithm's pseudocode, with the line being run marked");
+    }
+
+    /// Operations per row of the history image for the recorded run.
+    fn history_every(&self) -> usize {
+        history::ops_per_row(self.playback.work_len(), self.history_rows)
+    }
+
+    /// Save the history image of the recorded run as a PNG at `path`.
+    pub fn save_history(&mut self, path: &Path) {
+        let Some(input) = &self.run_input else {
+            self.status_message = "Nothing recorded yet".to_string();
+            return;
+        };
+        let image = history_image(input, self.playback.trace(), self.history_every());
+        self.status_message = match image.save(path) {
+            Ok(()) => format!("Saved history to {}", path.display()),
+            Err(e) => format!("Could not save {}: {}", path.display(), e),
+        };
+    }
+
+    /// Window with the whole recorded run as one image, one row per array
+    /// state, and a line at the current playback position.
+    fn show_history_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_history;
+        let complete =
+            !self.is_sorting() || matches!(self.playback.trace().last(), Some(Operation::Done));
+        egui::Window::new("Run history")
+            .open(&mut open)
+            .default_size([360.0, 420.0])
+            .show(ctx, |ui| {
+                ui.add(
+                    egui::Slider::new(&mut self.history_rows, 16..=4_096)
+                        .logarithmic(true)
+                        .text("rows"),
+                );
+                let Some(input) = &self.run_input else {
+                    ui.label("Sort something to see its history.");
+                    return;
+                };
+                let every = self.history_every();
+                let key = (self.playback.work_len(), every);
+                let stale = match &self.history {
+                    None => true,
+                    Some(history) if history.key == key => false,
+                    // Only the rows slider moves the key once the trace is
+                    // complete, so follow that straight away
+                    Some(_) if complete => true,
+                    Some(history) => {
+                        history.built.elapsed() >= HISTORY_REFRESH.max(10 * history.cost)
+                    }
+                };
+                if stale {
+                    let built = Instant::now();
+                    let image = history_image(input, self.playback.trace(), every);
+                    let size = [image.width() as usize, image.height() as usize];
+                    let texture = ctx.load_texture(
+                        "run_history",
+                        egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw()),
+                        egui::TextureOptions::NEAREST,
+                    );
+                    self.history = Some(History {
+                        key,
+                        texture,
+                        built,
+                        cost: built.elapsed(),
+                    });
                 }
-                Operation::Done => {
-                    println!("[DEBUG] Operation::Done received, sorting finished.");
-                    self.sorting = false;
+                let Some(history) = &self.history else {
+                    return;
+                };
+                // The image may lag behind the trace while it comes in
+                let (_, every) = history.key;
+                ui.small(format!("{} ops per row", every));
+                if ui.button("Save PNG…").clicked() {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("PNG", &["png"])
+                        .set_file_name("history.png")
+                        .save_file()
+                    {
+                        self.save_history(&path);
+                    }
+                    return;
                 }
+
+                let size = ui.available_size().max(egui::vec2(64.0, 64.0));
+                let response = ui.add(egui::Image::new((history.texture.id(), size)));
+                let rows = history.texture.size()[1].max(1) as f32;
+                let row = ((self.playback.work_played() / every) as f32).min(rows);
+                let y = response.rect.top() + response.rect.height() * row / rows;
+                ui.painter().hline(
+                    response.rect.x_range(),
+                    y,
+                    egui::Stroke::new(1.0, ui.visuals().strong_text_color()),
+                );
+            });
+        self.show_history = open;
+    }
+
+    /// Window drawing the heap as a tree, following playback.
+    fn show_heap_window(&mut self, ctx: &egui::Context) {
+        let default_color = self.current_theme.bar_color();
+        let view = self.playback.heap();
+        egui::Window::new("Heap tree")
+            .open(&mut self.show_heap)
+            .default_size([420.0, 320.0])
+            .show(ctx, |ui| {
+                let Some(view) = view else {
+                    ui.label("Run Heap Sort to see its heap here.");
+                    return;
+                };
+                ui.small(format!(
+                    "Heap of {}, sifting down from node {}",
+                    view.heap_len, view.path[0]
+                ));
+                let size = ui.available_size().max(egui::vec2(120.0, 120.0));
+                let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+                draw_heap_tree(
+                    ui.painter(),
+                    rect,
+                    &self.bars,
+                    view,
+                    default_color,
+                    ui.visuals().text_color(),
+                    Color32::from_rgb(255, 140, 0),
+                );
+            });
+    }
+
+    /// Window drawing the selected sorting network's comparators layer by
+    /// layer, following playback.
+    fn show_network_window(&mut self, ctx: &egui::Context) {
+        let n = self.bars.len();
+        let is_network = self.algorithm.network(0).is_some();
+        let network = if is_network && n <= MAX_WIRES {
+            self.algorithm.network(n)
+        } else {
+            None
+        };
+        let view = self
+            .playback
+            .network()
+            .filter(|view| network.as_ref().is_some_and(|net| view.layer < net.len()));
+        egui::Window::new("Sorting network")
+            .open(&mut self.show_network)
+            .default_size([480.0, 360.0])
+            .show(ctx, |ui| {
+                let Some(network) = network else {
+                    ui.label(if is_network {
+                        format!("Networks are drawn for up to {MAX_WIRES} elements.")
+                    } else {
+                        "Pick Bitonic Sort or Odd-Even Merge Sort to see its network here."
+                            .to_string()
+                    });
+                    return;
+                };
+                let total: usize = network.iter().map(|layer| layer.comparators.len()).sum();
+                ui.small(match view {
+                    Some(view) => format!(
+                        "Layer {} of {}, comparator {} of {}; a layer's comparators could all run at once",
+                        view.layer + 1,
+                        network.len(),
+                        view.done,
+                        network[view.layer].comparators.len()
+                    ),
+                    None => format!("{} layers, {} comparators", network.len(), total),
+                });
+                let size = ui.available_size().max(egui::vec2(160.0, 120.0));
+                let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+                draw_network(
+                    ui.painter(),
+                    rect,
+                    &network,
+                    &self.bars,
+                    view,
+                    ui.visuals().text_color(),
+                    Color32::from_rgb(255, 140, 0),
+                );
+            });
+    }
+
+    /// Side panel with the algorithm's pseudocode and the line the replayed
+    /// run is on. Only shown for algorithms that have a listing.
+    fn show_pseudocode_panel(&self, ctx: &egui::Context) {
+        let listing = self.algorithm.pseudocode();
+        if listing.is_empty() {
+            return;
+        }
+        let current = self.playback.current_line();
+        egui::SidePanel::right("pseudocode")
+            .default_width(280.0)
+            .show(ctx, |ui| {
+                ui.label(format!("{}:", self.algorithm.name()));
+                ui.separator();
+                let visuals = ui.visuals().clone();
+                egui::ScrollArea::both().show(ui, |ui| {
+                    for (i, line) in listing.iter().enumerate() {
+                        let text =
+                            egui::RichText::new(format!("{:>2}  {}", i + 1, line)).monospace();
+                        if current == Some(i + 1) {
+                            ui.label(
+                                text.background_color(visuals.selection.bg_fill)
+                                    .color(visuals.selection.stroke.color),
+                            );
+                        } else {
+                            ui.label(text);
+                        }
+                    }
+                });
+            });
+    }
+
+    /// Window listing the ranges of the active recursive calls, innermost
+    /// last, with the current and deepest recursion reached. Sorts that
+    /// merge natural runs get their stack of pending runs instead.
+    fn show_call_stack_window(&mut self, ctx: &egui::Context) {
+        let stack = self.playback.call_stack();
+        let runs = self.playback.run_stack();
+        let stats = self.playback.stats();
+        let playback = &self.playback;
+        let n = self.bars.len();
+        egui::Window::new("Call stack")
+            .open(&mut self.show_call_stack)
+            .default_size([240.0, 320.0])
+            .show(ctx, |ui| {
+                if !runs.is_empty() {
+                    ui.label(format!("Pending runs: {}", runs.len()));
+                    if let Some(node) = playback.power_nodes().last() {
+                        show_power_node(ui, node, n);
+                    }
+                    ui.separator();
+                    egui::ScrollArea::vertical()
+                        .auto_shrink([false, false])
+                        .show(ui, |ui| {
+                            // Top of the stack first, as it is the next to merge
+                            for (k, run) in runs.iter().enumerate().rev() {
+                                // Powersort's power of the boundary under the run
+                                let power = match playback.power_at(run.start) {
+                                    Some(power) if k > 0 => format!("  power {}", power),
+                                    _ => String::new(),
+                                };
+                                let text = egui::RichText::new(format!(
+                                    "{:>3} [{}, {})  {} elements{}",
+                                    k + 1,
+                                    run.start,
+                                    run.end,
+                                    run.len(),
+                                    power
+                                ))
+                                .monospace();
+                                if k + 1 == runs.len() {
+                                    ui.label(text.strong());
+                                } else {
+                                    ui.label(text);
+                                }
+                            }
+                        });
+                    return;
+                }
+                if stats.max_depth == 0 {
+                    ui.label(
+                        "Run Quick Sort or Merge Sort to see their recursion here, \
+                         or Tim Sort or Powersort to see their pending runs.",
+                    );
+                    return;
+                }
+                ui.label(format!(
+                    "Depth {}, deepest so far {}",
+                    stack.len(),
+                    stats.max_depth
+                ));
+                ui.separator();
+                let row_h = ui.text_style_height(&egui::TextStyle::Monospace);
+                egui::ScrollArea::vertical()
+                    .stick_to_bottom(true)
+                    .auto_shrink([false, false])
+                    .show_rows(ui, row_h, stack.len(), |ui, rows| {
+                        for depth in rows {
+                            let range = &stack[depth];
+                            let text = egui::RichText::new(format!(
+                                "{:>3} {}[{}, {})  {} elements",
+                                depth + 1,
+                                " ".repeat(depth.min(40)),
+                                range.start,
+                                range.end,
+                                range.len()
+                            ))
+                            .monospace();
+                            if depth + 1 == stack.len() {
+                                ui.label(text.strong());
+                            } else {
+                                ui.label(text);
+                            }
+                        }
+                    });
+            });
+    }
+
+    /// Play/pause and single-step buttons for the recorded run.
+    pub fn show_playback_controls(&mut self, ui: &mut egui::Ui) {
+        let has_trace = !self.playback.is_empty();
+        ui.horizontal(|ui| {
+            let label = if self.playback.paused {
+                "▶ Play"
+            } else {
+                "⏸ Pause"
+            };
+            if ui
+                .add_enabled(has_trace, egui::Button::new(label))
+                .clicked()
+            {
+                self.playback.paused = !self.playback.paused;
+            }
+            if ui
+                .add_enabled(self.playback.can_step_backward(), egui::Button::new("⏮"))
+                .on_hover_text("Step back one operation")
+                .on_disabled_hover_text(if self.playback.cursor() > 0 {
+                    format!(
+                        "Only the last {} operations can be stepped back",
+                        UNDO_LIMIT
+                    )
+                } else {
+                    "At the start of the run".to_string()
+                })
+                .clicked()
+            {
+                self.step_backward();
+            }
+            if ui
+                .add_enabled(has_trace, egui::Button::new("⏭"))
+                .on_hover_text("Step forward one operation")
+                .clicked()
+            {
+                self.step_forward();
             }
+            if ui
+                .add_enabled(self.is_sorting(), egui::Button::new("⏹"))
+                .on_hover_text("Cancel the running sort")
+                .clicked()
+            {
+                self.cancel_sort();
+            }
+        });
+    }
+
+    /// Live counts for the current run. They are kept after the run finishes
+    /// and only reset when the next one starts.
+    pub fn show_stats(&self, ui: &mut egui::Ui) {
+        let stats = self.playback.stats();
+        ui.label("Statistics:");
+        egui::Grid::new("run_stats").num_columns(2).show(ui, |ui| {
+            let rows = [
+                ("Comparisons", stats.comparisons.to_string()),
+                ("Swaps", stats.swaps.to_string()),
+                ("Writes", stats.writes.to_string()),
+                ("Reads", stats.reads.to_string()),
+                ("Peak aux memory", stats.peak_aux.to_string()),
+                ("Max recursion depth", stats.max_depth.to_string()),
+                ("Elapsed", format!("{:.2} s", stats.elapsed.as_secs_f32())),
+            ];
+            for (name, value) in rows {
+                ui.label(name);
+                ui.monospace(value);
+                ui.end_row();
+            }
+        });
+    }
+
+    /// Button running Powersort and TimSort on copies of the current array,
+    /// and the counts of the last such comparison.
+    pub fn show_merge_comparison(&mut self, ui: &mut egui::Ui) {
+        if ui
+            .add_enabled(
+                !self.is_sorting(),
+                egui::Button::new("Powersort vs Tim Sort"),
+            )
+            .on_hover_text(
+                "Run both on the current array and compare their work. \
+                 Random Runs or Sawtooth input gives them runs to merge.",
+            )
+            .clicked()
+        {
+            let input = self.run_input.as_deref().unwrap_or(&self.bars);
+            self.merge_comparison = Some(vec![
+                merge_report(&PowerSort, input),
+                merge_report(&TimSort, input),
+            ]);
         }
-        if op_count > 0 {
-            println!(
-                "[DEBUG] handle_ops processed {} operations this frame.",
-                op_count
-            );
+        let Some(reports) = &self.merge_comparison else {
+            return;
+        };
+        egui::Grid::new("merge_comparison")
+            .num_columns(reports.len() + 1)
+            .show(ui, |ui| {
+                ui.label("");
+                for report in reports {
+                    ui.strong(report.algorithm);
+                }
+                ui.end_row();
+                let column = |value: fn(&MergeReport) -> usize| -> Vec<usize> {
+                    reports.iter().map(value).collect()
+                };
+                let rows = [
+                    ("Comparisons", column(|r| r.stats.comparisons)),
+                    ("Writes", column(|r| r.stats.writes)),
+                    ("Reads", column(|r| r.stats.reads)),
+                    ("Merges", column(|r| r.merges)),
+                    ("Merge cost", column(|r| r.merge_cost)),
+                ];
+                for (name, values) in rows {
+                    ui.label(name);
+                    let best = values.iter().min();
+                    for value in &values {
+                        let text = egui::RichText::new(value.to_string()).monospace();
+                        if Some(value) == best {
+                            ui.label(text.strong());
+                        } else {
+                            ui.label(text);
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+
+    /// Pause playback and apply exactly one more operation.
+    pub fn step_forward(&mut self) {
+        self.playback.paused = true;
+        let default = self.default_color();
+        if self.playback.step_forward(&mut self.bars, default) && self.playback.is_finished() {
+            self.finish_run();
         }
     }
 
+    /// Pause playback and take back the last applied operation.
+    pub fn step_backward(&mut self) {
+        self.playback.paused = true;
+        self.playback.step_backward(&mut self.bars);
+    }
+
     /// Apply the current theme to all bar colors.
     pub fn apply_theme(&mut self, theme: Theme) {
         // Store and apply theme
@@ -197,17 +1037,33 @@ impl SortVisualizerApp {
     }
 
     /// Create a new SortVisualizerApp with given number of bars and initial algorithm.
-    pub fn new(num_bars: usize, algorithm: SortingAlgorithm) -> Self {
-        let (tx, rx) = mpsc::channel();
+    pub fn new(num_bars: usize, algorithm: Arc<dyn SortAlgorithm>) -> Self {
+        let (tx, rx) = mpsc::sync_channel(OP_CHANNEL_CAPACITY);
         let mut app = Self {
             bars: Vec::new(),
             algorithm,
             num_bars,
-            sorting: false,
             tx,
             rx,
+            worker: None,
+            playback: Playback::new(PlaybackSpeed::default()),
+            run_input: None,
             current_theme: Theme::Light, // default, will be applied below
             status_message: String::new(),
+            export_options: ExportOptions::default(),
+            export: None,
+            max_speed_report: None,
+            view_size: None,
+            show_history: false,
+            history_rows: history::DEFAULT_ROWS,
+            history: None,
+            show_heap: false,
+            show_network: false,
+            show_call_stack: false,
+            show_pseudocode: true,
+            merge_comparison: None,
+            renderer: Arc::new(renderers::Bars),
+            renderers: renderers::builtins(),
         };
         // Initialize bars with default values and apply theme
         app.reset_bars();
@@ -215,32 +1071,60 @@ impl SortVisualizerApp {
     }
 }
 
+impl Drop for SortVisualizerApp {
+    /// Make sure closing the app doesn't leave a worker (e.g. Bogo Sort)
+    /// spinning in the background.
+    fn drop(&mut self) {
+        self.stop_worker();
+        if let Some(export) = &self.export {
+            export.cancel.cancel();
+        }
+    }
+}
+
 impl eframe::App for SortVisualizerApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // First, handle incoming sort operations:
-        self.handle_ops();
+        // First, play back whatever part of the recorded trace is due
+        let dt = ctx.input(|i| i.stable_dt).min(0.1);
+        self.handle_ops(dt);
 
+        if self.show_pseudocode {
+            self.show_pseudocode_panel(ctx);
+        }
         egui::CentralPanel::default().show(ctx, |ui| {
             let painter = ui.painter();
             let rect = ui.available_rect_before_wrap();
-            let n = self.bars.len() as f32;
-            let bar_w = rect.width() / n;
-
-            for (i, bar) in self.bars.iter().enumerate() {
-                let x = rect.left() + i as f32 * bar_w;
-                let h = rect.height() * (bar.value as f32 + 1.0) / n;
-                let y = rect.bottom() - h;
-
-                painter.rect_filled(
-                    egui::Rect::from_min_size(egui::pos2(x, y), egui::vec2(bar_w - 1.0, h)),
-                    0.0,
-                    bar.color,
-                );
-            }
+            let scale = ctx.pixels_per_point();
+            self.view_size = Some([
+                (rect.width() * scale).round() as u32,
+                (rect.height() * scale).round() as u32,
+            ]);
+            renderers::draw_run(
+                painter,
+                rect,
+                self.renderer.as_ref(),
+                &self.bars,
+                &self.playback,
+                self.default_color(),
+            );
         });
+        if self.show_history {
+            self.show_history_window(ctx);
+        }
+        if self.show_heap {
+            self.show_heap_window(ctx);
+        }
+        if self.show_network {
+            self.show_network_window(ctx);
+        }
+        if self.show_call_stack {
+            self.show_call_stack_window(ctx);
+        }
 
-        // keep repainting at ~60 fps during sort
-        if self.sorting {
+        // keep repainting at ~60 fps during sort, while a recorded run plays
+        // on after it, and while an export runs so its result shows up
+        let playing = !self.playback.paused && self.playback.buffered() > 0;
+        if self.is_sorting() || playing || self.export.is_some() {
             ctx.request_repaint_after(std::time::Duration::from_millis(16));
         }
     }
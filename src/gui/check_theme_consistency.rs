@@ -1,5 +1,5 @@
-use crate::models::SortBar;
 use crate::gui_backend::gui::Theme;
+use crate::models::SortBar;
 use eframe::egui::Color32;
 
 /// Reset all bar colors to the default for the given theme
@@ -0,0 +1,121 @@
+//! Heap sort's array drawn as the binary tree it encodes.
+//!
+//! Node `k` of the heap lives at index `k` of the array, with children at
+//! `2k + 1` and `2k + 2`. The view is drawn from the bars and the
+//! `HeapView` that playback keeps up to date, so it follows playback both
+//! ways.
+
+use crate::models::SortBar;
+use crate::playback::HeapView;
+use eframe::egui::{self, pos2, vec2, Color32, Painter, Rect, Stroke};
+
+/// Draw the heap as a tree in the top of `rect` and the sorted suffix as a
+/// row of cells under it. Nodes take their bar's highlight colour; the
+/// edges of the active sift-down path are drawn in `path_color`.
+pub fn draw_heap_tree(
+    painter: &Painter,
+    rect: Rect,
+    bars: &[SortBar],
+    view: &HeapView,
+    default_color: Color32,
+    text_color: Color32,
+    path_color: Color32,
+) {
+    let heap_len = view.heap_len.min(bars.len());
+    let font = egui::FontId::proportional(11.0);
+    let sorted_h = if heap_len < bars.len() {
+        (0.15 * rect.height()).clamp(24.0, 48.0)
+    } else {
+        0.0
+    };
+    let tree = Rect::from_min_max(rect.min, pos2(rect.max.x, rect.max.y - sorted_h));
+
+    // Level of node `k`, counting the root as level 0
+    let depth = |k: usize| (k + 1).ilog2() as usize;
+    let levels = if heap_len == 0 {
+        0
+    } else {
+        depth(heap_len - 1) + 1
+    };
+    let level_h = tree.height() / levels.max(1) as f32;
+    let center = |k: usize| {
+        let d = depth(k);
+        let across = 1usize << d;
+        let x = tree.left() + ((k + 1 - across) as f32 + 0.5) * tree.width() / across as f32;
+        pos2(x, tree.top() + (d as f32 + 0.5) * level_h)
+    };
+    let radius = |k: usize| {
+        let across = (1usize << depth(k)) as f32;
+        (0.45 * tree.width() / across)
+            .min(0.35 * level_h)
+            .clamp(1.5, 16.0)
+    };
+
+    for k in 1..heap_len {
+        let parent = (k - 1) / 2;
+        let on_path = view
+            .path
+            .windows(2)
+            .any(|edge| edge[0] == parent && edge[1] == k);
+        let stroke = if on_path {
+            Stroke::new(3.0, path_color)
+        } else {
+            Stroke::new(1.0, text_color.gamma_multiply(0.4))
+        };
+        painter.line_segment([center(parent), center(k)], stroke);
+    }
+    // Unhighlighted elements get a muted fill so their labels stay readable
+    let fill = |bar: &SortBar| {
+        if bar.color == default_color {
+            text_color.gamma_multiply(0.15)
+        } else {
+            bar.color
+        }
+    };
+    for (k, bar) in bars.iter().enumerate().take(heap_len) {
+        let r = radius(k);
+        painter.circle_filled(center(k), r, fill(bar));
+        if view.path.contains(&k) {
+            painter.circle_stroke(center(k), r, Stroke::new(2.0, path_color));
+        }
+        if r >= 7.0 {
+            painter.text(
+                center(k),
+                egui::Align2::CENTER_CENTER,
+                bar.value.to_string(),
+                font.clone(),
+                text_color,
+            );
+        }
+    }
+
+    if heap_len < bars.len() {
+        let row = Rect::from_min_max(pos2(rect.left(), tree.bottom()), rect.max);
+        painter.text(
+            row.left_top(),
+            egui::Align2::LEFT_TOP,
+            format!("Sorted ({})", bars.len() - heap_len),
+            font.clone(),
+            text_color,
+        );
+        let cells = Rect::from_min_max(row.min + vec2(0.0, 14.0), row.max);
+        let w = cells.width() / bars.len() as f32;
+        for (i, bar) in bars.iter().enumerate().skip(heap_len) {
+            let cell = Rect::from_min_size(
+                pos2(cells.left() + i as f32 * w, cells.top()),
+                vec2(w, cells.height()),
+            )
+            .shrink(0.5);
+            painter.rect_filled(cell, 0.0, fill(bar));
+            if w >= 18.0 {
+                painter.text(
+                    cell.center(),
+                    egui::Align2::CENTER_CENTER,
+                    bar.value.to_string(),
+                    font.clone(),
+                    text_color,
+                );
+            }
+        }
+    }
+}
@@ -1,2 +1,6 @@
 pub mod bars_render;
 pub mod check_theme_consistency;
+pub mod heap_tree;
+pub mod network;
+pub mod race;
+pub mod renderers;
@@ -0,0 +1,112 @@
+//! A sorting network drawn the way such networks are usually pictured: a
+//! horizontal wire per element and, for each comparator, a vertical link
+//! between the two wires it compares. Each layer gets a shaded column, as
+//! its comparators could all run at once; within it they sit side by side
+//! only where they would otherwise overlap.
+
+use crate::models::SortBar;
+use crate::playback::NetworkView;
+use crate::sorting::NetworkLayer;
+use eframe::egui::{self, pos2, Color32, Painter, Rect, Stroke};
+
+/// Networks on more elements than this are not drawn.
+pub const MAX_WIRES: usize = 128;
+
+/// The sub-column of each comparator of `layer`, packed so that no two
+/// comparators in one sub-column overlap, and how many sub-columns it takes.
+fn pack(layer: &NetworkLayer) -> (Vec<usize>, usize) {
+    let mut order: Vec<usize> = (0..layer.comparators.len()).collect();
+    order.sort_by_key(|&c| layer.comparators[c].0);
+    let mut column = vec![0; layer.comparators.len()];
+    // The lowest wire reached so far in each sub-column
+    let mut reach: Vec<usize> = Vec::new();
+    for c in order {
+        let (i, j) = layer.comparators[c];
+        match reach.iter().position(|&end| end < i) {
+            Some(k) => {
+                reach[k] = j;
+                column[c] = k;
+            }
+            None => {
+                column[c] = reach.len();
+                reach.push(j);
+            }
+        }
+    }
+    (column, reach.len().max(1))
+}
+
+/// Draw `network` over one wire per bar, each labelled with its bar's value
+/// when there is room. The layer `view` is on is shaded in `active_color`,
+/// with the comparators it has run so far in that colour and the latest
+/// drawn thicker; layers still to come are faded.
+pub fn draw_network(
+    painter: &Painter,
+    rect: Rect,
+    network: &[NetworkLayer],
+    bars: &[SortBar],
+    view: Option<NetworkView>,
+    text_color: Color32,
+    active_color: Color32,
+) {
+    let n = bars.len();
+    if n == 0 {
+        return;
+    }
+    let wire_gap = rect.height() / n as f32;
+    let labels = wire_gap >= 9.0;
+    let left = rect.left() + if labels { 30.0 } else { 4.0 };
+    let y = |wire: usize| rect.top() + (wire as f32 + 0.5) * wire_gap;
+    let font = egui::FontId::monospace((0.8 * wire_gap).min(11.0));
+    for (wire, bar) in bars.iter().enumerate() {
+        painter.line_segment(
+            [pos2(left, y(wire)), pos2(rect.right(), y(wire))],
+            Stroke::new(1.0, text_color.gamma_multiply(0.3)),
+        );
+        if labels {
+            painter.text(
+                pos2(left - 4.0, y(wire)),
+                egui::Align2::RIGHT_CENTER,
+                bar.value.to_string(),
+                font.clone(),
+                text_color,
+            );
+        }
+    }
+
+    let packed: Vec<_> = network.iter().map(pack).collect();
+    // One unit per sub-column, and one between neighbouring layers
+    let units: usize = packed.iter().map(|(_, width)| width + 1).sum::<usize>() + 1;
+    let unit = (rect.right() - left) / units as f32;
+    let r = (0.25 * wire_gap).clamp(1.0, 3.5);
+    let mut x = left + unit;
+    for (k, (layer, (column, width))) in network.iter().zip(&packed).enumerate() {
+        let band = Rect::from_min_max(
+            pos2(x - 0.4 * unit, rect.top()),
+            pos2(x + (*width as f32 - 0.6) * unit, rect.bottom()),
+        );
+        let current = view.filter(|view| view.layer == k);
+        let shade = if current.is_some() {
+            active_color.gamma_multiply(0.2)
+        } else {
+            text_color.gamma_multiply(0.06)
+        };
+        painter.rect_filled(band, 2.0, shade);
+        for (c, &(i, j)) in layer.comparators.iter().enumerate() {
+            let (color, thickness) = match (current, view) {
+                (Some(view), _) if c + 1 == view.done => (active_color, 2.5),
+                (Some(view), _) if c < view.done => (active_color, 1.5),
+                (None, Some(view)) if k > view.layer => (text_color.gamma_multiply(0.35), 1.0),
+                _ => (text_color, 1.0),
+            };
+            let cx = x + column[c] as f32 * unit;
+            painter.line_segment(
+                [pos2(cx, y(i)), pos2(cx, y(j))],
+                Stroke::new(thickness, color),
+            );
+            painter.circle_filled(pos2(cx, y(i)), r, color);
+            painter.circle_filled(pos2(cx, y(j)), r, color);
+        }
+        x += (width + 1) as f32 * unit;
+    }
+}
@@ -0,0 +1,302 @@
+use crate::gui::bars_render::{speed_controls, MAX_LOOKAHEAD};
+use crate::gui::check_theme_consistency::apply_theme_consistency;
+use crate::gui::renderers::draw_bars;
+use crate::gui_backend::gui::Theme;
+use crate::models::SortBar;
+use crate::playback::{Playback, PlaybackSpeed};
+use crate::sorting::{
+    start_sort, Operation, Registry, SortAlgorithm, SortWorker, OP_CHANNEL_CAPACITY,
+};
+use eframe::egui::{self, Color32};
+use std::sync::{mpsc, Arc};
+
+/// One algorithm's copy of the race input, with its own worker and trace.
+struct Lane {
+    algorithm: Arc<dyn SortAlgorithm>,
+    bars: Vec<SortBar>,
+    playback: Playback,
+    rx: mpsc::Receiver<Operation>,
+    worker: Option<SortWorker>,
+    // Whether `Operation::Done` has been recorded
+    complete: bool,
+}
+
+impl Lane {
+    fn start(algorithm: Arc<dyn SortAlgorithm>, input: &[SortBar]) -> Self {
+        let (tx, rx) = mpsc::sync_channel(OP_CHANNEL_CAPACITY);
+        let worker = start_sort(Arc::clone(&algorithm), input.to_vec(), tx);
+        Self {
+            algorithm,
+            bars: input.to_vec(),
+            playback: Playback::default(),
+            rx,
+            worker: Some(worker),
+            complete: false,
+        }
+    }
+
+    /// Pull whatever the worker has sent since the last frame.
+    fn receive(&mut self) {
+        while self.playback.buffered() < MAX_LOOKAHEAD {
+            match self.rx.try_recv() {
+                Ok(op) => {
+                    self.complete |= matches!(op, Operation::Done);
+                    self.playback.push(op);
+                }
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+impl Drop for Lane {
+    fn drop(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            worker.cancel();
+            // Drop the receiver first so a worker blocked on a full channel
+            // wakes up
+            let (_, rx) = mpsc::sync_channel(0);
+            drop(std::mem::replace(&mut self.rx, rx));
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Several algorithms sorting copies of the same array side by side.
+///
+/// Every lane replays its own trace, but all of them advance on one clock at
+/// one speed, so an algorithm that needs fewer operations finishes first.
+pub struct Race {
+    /// Algorithms that get a lane when the race starts.
+    pub contestants: Vec<Arc<dyn SortAlgorithm>>,
+    pub speed: PlaybackSpeed,
+    pub paused: bool,
+    lanes: Vec<Lane>,
+    // Lane indices in the order they finished
+    finish_order: Vec<usize>,
+    theme: Theme,
+}
+
+impl Race {
+    /// A race between Heap Sort and Quick Sort, or whichever of them
+    /// `registry` has.
+    pub fn new(registry: &Registry) -> Self {
+        Self {
+            contestants: ["Heap Sort", "Quick Sort"]
+                .iter()
+                .filter_map(|name| registry.get(name))
+                .collect(),
+            speed: PlaybackSpeed::default(),
+            paused: false,
+            lanes: Vec::new(),
+            finish_order: Vec::new(),
+            theme: Theme::default(),
+        }
+    }
+
+    /// Give every contestant its own copy of `input` and start them all.
+    pub fn start(&mut self, input: &[SortBar]) {
+        self.stop();
+        let mut input = input.to_vec();
+        apply_theme_consistency(&mut input, self.theme);
+        self.lanes = self
+            .contestants
+            .iter()
+            .map(|algorithm| Lane::start(Arc::clone(algorithm), &input))
+            .collect();
+        self.paused = false;
+    }
+
+    /// Cancel every lane and clear the board.
+    pub fn stop(&mut self) {
+        self.lanes.clear();
+        self.finish_order.clear();
+    }
+
+    /// Whether a race has started and some lane is still sorting.
+    pub fn is_running(&self) -> bool {
+        self.finish_order.len() < self.lanes.len()
+    }
+
+    /// Names of the finished algorithms, winner first.
+    pub fn finishers(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.finish_order
+            .iter()
+            .map(|&i| self.lanes[i].algorithm.name())
+    }
+
+    /// The bars of each lane, in contestant order.
+    pub fn lane_bars(&self) -> impl Iterator<Item = &[SortBar]> {
+        self.lanes.iter().map(|lane| lane.bars.as_slice())
+    }
+
+    pub fn apply_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+        for lane in &mut self.lanes {
+            apply_theme_consistency(&mut lane.bars, theme);
+        }
+    }
+
+    /// Move every lane on by a frame lasting `dt` seconds.
+    ///
+    /// If a worker has not yet recorded the operations its lane is due, the
+    /// whole race waits for it rather than letting the other lanes pull ahead.
+    pub fn advance(&mut self, dt: f32) {
+        if self.paused {
+            return;
+        }
+        let due = match self.speed {
+            PlaybackSpeed::OpsPerFrame(n) => n,
+            PlaybackSpeed::OpsPerSecond(rate) => (rate * dt).ceil() as usize + 1,
+        };
+        for lane in &mut self.lanes {
+            lane.receive();
+        }
+        if self
+            .lanes
+            .iter()
+            .any(|lane| !lane.complete && lane.playback.buffered() < due)
+        {
+            return;
+        }
+
+        let default_color = self.theme.bar_color();
+        let mut finished = Vec::new();
+        for (i, lane) in self.lanes.iter_mut().enumerate() {
+            lane.playback.speed = self.speed;
+            if lane.playback.advance(dt, &mut lane.bars, default_color) {
+                if let Some(worker) = lane.worker.take() {
+                    let _ = worker.join();
+                }
+                finished.push(i);
+            }
+        }
+        // Lanes finishing on the same frame are placed by operations used
+        finished.sort_by_key(|&i| self.lanes[i].playback.cursor());
+        self.finish_order.extend(finished);
+    }
+
+    /// Contestant picker, speed and start/stop buttons for the side panel.
+    /// `input` is the array the race starts from.
+    pub fn show_controls(&mut self, ui: &mut egui::Ui, registry: &Registry, input: &[SortBar]) {
+        ui.label("Contestants:");
+        for algorithm in registry.iter() {
+            let position = self
+                .contestants
+                .iter()
+                .position(|a| a.name() == algorithm.name());
+            let mut selected = position.is_some();
+            if ui.checkbox(&mut selected, algorithm.name()).changed() {
+                match position {
+                    Some(i) => {
+                        self.contestants.remove(i);
+                    }
+                    None => self.contestants.push(Arc::clone(algorithm)),
+                }
+            }
+        }
+        ui.separator();
+        speed_controls(ui, &mut self.speed);
+        ui.separator();
+        ui.small(format!(
+            "Input: the current array ({} bars), set up on the controls page",
+            input.len()
+        ));
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    !self.contestants.is_empty(),
+                    egui::Button::new("Start race"),
+                )
+                .clicked()
+            {
+                self.start(input);
+            }
+            let label = if self.paused { "▶ Play" } else { "⏸ Pause" };
+            if ui
+                .add_enabled(self.is_running(), egui::Button::new(label))
+                .clicked()
+            {
+                self.paused = !self.paused;
+            }
+            if ui
+                .add_enabled(!self.lanes.is_empty(), egui::Button::new("⏹"))
+                .on_hover_text("Stop the race")
+                .clicked()
+            {
+                self.stop();
+            }
+        });
+        if !self.finish_order.is_empty() {
+            ui.separator();
+            ui.label("Finishing order:");
+            for (place, &i) in self.finish_order.iter().enumerate() {
+                let lane = &self.lanes[i];
+                ui.label(format!(
+                    "{}. {} ({} ops)",
+                    place + 1,
+                    lane.algorithm.name(),
+                    lane.playback.cursor()
+                ));
+            }
+        }
+    }
+
+    /// Draw the lanes in a grid filling the central panel.
+    pub fn update(&mut self, ctx: &egui::Context) {
+        let dt = ctx.input(|i| i.stable_dt).min(0.1);
+        self.advance(dt);
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if self.lanes.is_empty() {
+                ui.label("Pick some contestants and press Start race.");
+                return;
+            }
+            let rect = ui.available_rect_before_wrap();
+            let columns = (self.lanes.len() as f32).sqrt().ceil() as usize;
+            let rows = self.lanes.len().div_ceil(columns);
+            let cell = egui::vec2(rect.width() / columns as f32, rect.height() / rows as f32);
+            let text_color = ui.visuals().text_color();
+            let painter = ui.painter();
+
+            for (i, lane) in self.lanes.iter().enumerate() {
+                let min = rect.min
+                    + egui::vec2((i % columns) as f32 * cell.x, (i / columns) as f32 * cell.y);
+                let cell_rect = egui::Rect::from_min_size(min, cell).shrink(4.0);
+                let stats = lane.playback.stats();
+                let place = match self.finish_order.iter().position(|&f| f == i) {
+                    Some(place) => format!(" — #{}", place + 1),
+                    None => String::new(),
+                };
+                let header = format!(
+                    "{}{}\n{} cmp  {} swp  {} wr",
+                    lane.algorithm.name(),
+                    place,
+                    stats.comparisons,
+                    stats.swaps,
+                    stats.writes
+                );
+                let galley = painter.layout_no_wrap(
+                    header,
+                    egui::FontId::proportional(12.0),
+                    if place.is_empty() {
+                        text_color
+                    } else {
+                        Color32::GREEN
+                    },
+                );
+                let header_height = galley.size().y + 4.0;
+                painter.galley(cell_rect.min, galley, text_color);
+                let bars_rect = egui::Rect::from_min_max(
+                    cell_rect.min + egui::vec2(0.0, header_height),
+                    cell_rect.max,
+                );
+                draw_bars(painter, bars_rect, &lane.bars);
+            }
+        });
+
+        if self.is_running() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(16));
+        }
+    }
+}
@@ -0,0 +1,674 @@
+//! Ways of drawing the array. The visualizer holds one `BarRenderer` and asks
+//! it to draw the current bars every frame, so the view can change mid-run.
+
+use crate::models::SortBar;
+use crate::playback::{AuxState, Keys, Playback, PowerNode, Rotation};
+use eframe::egui::{self, ecolor::Hsva, pos2, vec2, Color32, Painter, Pos2, Rect};
+use std::f32::consts::TAU;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// A view of the array.
+///
+/// Renderers only draw; they see the bars as playback left them, with
+/// highlight colours from `Compare`, `Swap` and friends on top of
+/// `default_color`.
+pub trait BarRenderer: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn draw(&self, painter: &Painter, rect: Rect, bars: &[SortBar], default_color: Color32);
+}
+
+impl std::fmt::Debug for dyn BarRenderer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The built-in renderers, vertical bars first.
+pub fn builtins() -> Vec<Arc<dyn BarRenderer>> {
+    vec![
+        Arc::new(Bars),
+        Arc::new(Scatter),
+        Arc::new(DisparityCircle),
+        Arc::new(ColorWheel),
+        Arc::new(Spiral),
+        Arc::new(HueStrip),
+    ]
+}
+
+/// The value every height, radius or hue is measured against. Generated
+/// values are below n, but loaded arrays can go higher.
+fn top(bars: &[SortBar]) -> f32 {
+    bars.iter()
+        .map(|b| b.value + 1)
+        .max()
+        .unwrap_or(1)
+        .max(bars.len()) as f32
+}
+
+/// Hue for `value`, from red at the bottom of the range to magenta at the
+/// top, stopping short of the wheel so the two ends stay distinct.
+pub fn hue(value: usize, top: f32) -> Color32 {
+    Hsva::new(0.85 * value as f32 / top, 0.85, 1.0, 1.0).into()
+}
+
+/// Dot size that keeps neighbouring points apart without vanishing.
+fn dot_radius(rect: Rect, n: usize) -> f32 {
+    (rect.width().min(rect.height()) / n as f32).clamp(1.0, 4.0)
+}
+
+/// Point at `angle` (clockwise from twelve o'clock) and `radius` from `center`.
+fn polar(center: Pos2, angle: f32, radius: f32) -> Pos2 {
+    center + radius * vec2(angle.sin(), -angle.cos())
+}
+
+/// Draw `bars` as vertical columns filling `rect`, heights relative to the
+/// largest value.
+pub fn draw_bars(painter: &Painter, rect: Rect, bars: &[SortBar]) {
+    draw_bars_scaled(painter, rect, bars, top(bars));
+}
+
+/// Draw `bars` as vertical columns filling `rect`, with a value of `top`
+/// reaching the top of it.
+fn draw_bars_scaled(painter: &Painter, rect: Rect, bars: &[SortBar], top: f32) {
+    let bar_w = rect.width() / bars.len() as f32;
+    for (i, bar) in bars.iter().enumerate() {
+        let x = rect.left() + i as f32 * bar_w;
+        let h = rect.height() * (bar.value as f32 + 1.0) / top;
+        let y = rect.bottom() - h;
+
+        painter.rect_filled(
+            Rect::from_min_size(pos2(x, y), vec2(bar_w - 1.0, h)),
+            0.0,
+            bar.color,
+        );
+    }
+}
+
+/// Colours of the blocks, and their tags, from the left and the right run
+/// of a block merge, and of the internal buffer.
+const LEFT_BLOCK_COLOR: Color32 = Color32::from_rgb(80, 160, 255);
+const RIGHT_BLOCK_COLOR: Color32 = Color32::from_rgb(255, 200, 60);
+const BUFFER_COLOR: Color32 = Color32::from_rgb(60, 200, 140);
+
+/// Draw the run the way the central panel shows it: `bars` through
+/// `renderer`, with the overlays for what `playback` has been told so far,
+/// aux strips, run band, keys, powers and call brackets under the bars and
+/// the note, blocks and rotation over them. Exports draw frames with this
+/// too.
+pub fn draw_run(
+    painter: &Painter,
+    rect: Rect,
+    renderer: &dyn BarRenderer,
+    bars: &[SortBar],
+    playback: &Playback,
+    default_color: Color32,
+) {
+    let style = painter.ctx().style();
+    let visuals = &style.visuals;
+    let aux = playback.aux_arrays();
+    let max_depth = playback.stats().max_depth;
+    let mut main = rect;
+    if !aux.is_empty() {
+        // Auxiliary arrays get a strip each under the main view
+        let strips_h = (0.35 * rect.height()).min(80.0 * aux.len() as f32);
+        let strips;
+        (main, strips) = main.split_top_bottom_at_y(main.bottom() - strips_h);
+        draw_aux_strips(painter, strips, aux, bars, default_color);
+    }
+    let runs = playback.run_stack();
+    if !runs.is_empty() {
+        // A band of pending runs, for sorts that merge natural runs
+        let band_h = (0.05 * rect.height()).clamp(6.0, 16.0);
+        let band;
+        (main, band) = main.split_top_bottom_at_y(main.bottom() - band_h);
+        draw_run_stack(
+            painter,
+            band.shrink2(vec2(0.0, 2.0)),
+            runs,
+            bars.len(),
+            visuals.weak_text_color(),
+            Color32::from_rgb(255, 140, 0),
+        );
+    }
+    let block_colors: Vec<Color32> = playback
+        .block_origins(bars)
+        .into_iter()
+        .map(|left| {
+            if left {
+                LEFT_BLOCK_COLOR
+            } else {
+                RIGHT_BLOCK_COLOR
+            }
+        })
+        .collect();
+    if let Some(keys) = playback.keys() {
+        // Block merge sort's tags and buffer, each tag in use in the
+        // colour of the block it tags
+        let band_h = (0.05 * rect.height()).clamp(8.0, 16.0);
+        let band;
+        (main, band) = main.split_top_bottom_at_y(main.bottom() - band_h);
+        draw_keys(
+            painter,
+            band.shrink2(vec2(0.0, 2.0)),
+            keys,
+            &block_colors,
+            bars.len(),
+            visuals.weak_text_color(),
+            BUFFER_COLOR,
+        );
+    }
+    let nodes = playback.power_nodes();
+    if !nodes.is_empty() && !runs.is_empty() {
+        // Powersort's boundary powers, just above the run band
+        let boundaries: Vec<(usize, usize)> = runs[1..]
+            .iter()
+            .filter_map(|run| Some((run.start, playback.power_at(run.start)?)))
+            .collect();
+        // The latest boundary is live until its runs are merged away
+        let top = &runs[runs.len() - 1];
+        let latest = nodes.last().filter(|node| {
+            top.end == node.right.start || (runs.len() > 1 && top.end == node.right.end)
+        });
+        let marks;
+        (main, marks) = main.split_top_bottom_at_y(main.bottom() - 24.0);
+        draw_run_powers(
+            painter,
+            marks,
+            &boundaries,
+            latest,
+            bars.len(),
+            visuals.text_color(),
+            Color32::from_rgb(255, 140, 0),
+        );
+    }
+    if max_depth > 0 {
+        // Brackets for the live recursive calls, once a run uses them
+        let brackets_h = (0.2 * rect.height()).min(60.0);
+        let brackets;
+        (main, brackets) = main.split_top_bottom_at_y(main.bottom() - brackets_h);
+        draw_range_brackets(
+            painter,
+            brackets.shrink2(vec2(0.0, 2.0)),
+            playback.call_stack(),
+            bars.len(),
+            max_depth,
+            visuals.weak_text_color(),
+            Color32::from_rgb(255, 140, 0),
+        );
+    }
+    if main != rect {
+        main = main.shrink2(vec2(0.0, 4.0));
+    }
+    renderer.draw(painter, main, bars, default_color);
+    if let Some(note) = playback.note() {
+        draw_note(
+            painter,
+            main,
+            &note.range,
+            bars.len(),
+            note.text,
+            Color32::from_rgb(255, 140, 0),
+        );
+    }
+    if let Some(merge) = playback.block_merge() {
+        draw_blocks(
+            painter,
+            main,
+            merge.blocks(),
+            &block_colors,
+            bars.len(),
+            visuals.weak_text_color(),
+        );
+    }
+    if let Some(rotation) = playback.rotation() {
+        draw_rotation(
+            painter,
+            main,
+            rotation,
+            bars.len(),
+            Color32::from_rgb(255, 140, 0),
+        );
+    }
+}
+
+/// Draw each auxiliary array as a labelled strip of bars, stacked top to
+/// bottom in `rect`. Strips share the main array's scale (`bars`) unless
+/// they hold larger values, and the element last touched is highlighted.
+pub fn draw_aux_strips(
+    painter: &Painter,
+    rect: Rect,
+    arrays: &[AuxState],
+    bars: &[SortBar],
+    default_color: Color32,
+) {
+    let strip_h = rect.height() / arrays.len() as f32;
+    let font = egui::FontId::proportional(11.0);
+    let label_h = 14.0;
+    for (k, array) in arrays.iter().enumerate() {
+        let strip = Rect::from_min_size(
+            rect.min + vec2(0.0, k as f32 * strip_h),
+            vec2(rect.width(), strip_h),
+        )
+        .shrink2(vec2(0.0, 2.0));
+        painter.text(
+            strip.min,
+            egui::Align2::LEFT_TOP,
+            format!("{} [{}]", array.name, array.values.len()),
+            font.clone(),
+            default_color,
+        );
+        if array.values.is_empty() {
+            continue;
+        }
+        let strip_bars: Vec<SortBar> = array
+            .values
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| SortBar {
+                value,
+                color: match array.touched {
+                    Some((t, color)) if t == i => color,
+                    _ => default_color,
+                },
+            })
+            .collect();
+        let own_top = array.values.iter().max().map_or(1, |v| v + 1) as f32;
+        let bars_rect = Rect::from_min_max(strip.min + vec2(0.0, label_h), strip.max);
+        draw_bars_scaled(painter, bars_rect, &strip_bars, top(bars).max(own_top));
+    }
+}
+
+/// Draw the ranges of the active recursive calls as brackets under an
+/// array of `n` elements, outermost at the top of `rect`. Rows are sized
+/// for `max_depth` levels; the innermost call is drawn in `active_color`.
+pub fn draw_range_brackets(
+    painter: &Painter,
+    rect: Rect,
+    ranges: &[Range<usize>],
+    n: usize,
+    max_depth: usize,
+    color: Color32,
+    active_color: Color32,
+) {
+    let w = rect.width() / n.max(1) as f32;
+    let row_h = rect.height() / max_depth.max(ranges.len()).max(1) as f32;
+    let tick = (0.6 * row_h).clamp(1.0, 6.0);
+    for (depth, range) in ranges.iter().enumerate() {
+        let stroke = if depth + 1 == ranges.len() {
+            egui::Stroke::new(2.0, active_color)
+        } else {
+            egui::Stroke::new(1.0, color)
+        };
+        let y = rect.top() + (depth as f32 + 0.5) * row_h;
+        // Pull the ends in a little so neighbouring ranges stay apart
+        let x0 = rect.left() + range.start as f32 * w + 1.0;
+        let x1 = (rect.left() + range.end as f32 * w - 1.0).max(x0);
+        painter.line_segment([pos2(x0, y - tick), pos2(x0, y)], stroke);
+        painter.line_segment([pos2(x0, y), pos2(x1, y)], stroke);
+        painter.line_segment([pos2(x1, y), pos2(x1, y - tick)], stroke);
+    }
+}
+
+/// Draw a run-merging sort's pending runs as a band under an array of `n`
+/// elements, neighbouring runs in alternating shades of `color` and the run
+/// on top of the stack in `active_color`.
+pub fn draw_run_stack(
+    painter: &Painter,
+    rect: Rect,
+    runs: &[Range<usize>],
+    n: usize,
+    color: Color32,
+    active_color: Color32,
+) {
+    let w = rect.width() / n.max(1) as f32;
+    for (k, run) in runs.iter().enumerate() {
+        let fill = if k + 1 == runs.len() {
+            active_color
+        } else if k % 2 == 0 {
+            color
+        } else {
+            color.gamma_multiply(0.5)
+        };
+        let x0 = rect.left() + run.start as f32 * w;
+        let x1 = rect.left() + run.end as f32 * w;
+        painter.rect_filled(
+            Rect::from_min_max(pos2(x0, rect.top()), pos2(x1, rect.bottom()))
+                .shrink2(vec2(1.0, 0.0)),
+            2.0,
+            fill,
+        );
+    }
+}
+
+/// Label run boundaries, given as (position, power) pairs, with their
+/// Powersort power, lower powers with longer ticks as they sit higher in
+/// the merge tree. The boundary being weighed is drawn in `active_color`,
+/// with a mark at the midpoint of each of its runs.
+pub fn draw_run_powers(
+    painter: &Painter,
+    rect: Rect,
+    boundaries: &[(usize, usize)],
+    latest: Option<&PowerNode>,
+    n: usize,
+    color: Color32,
+    active_color: Color32,
+) {
+    let w = rect.width() / n.max(1) as f32;
+    let font = egui::FontId::proportional(10.0);
+    let x_at = |i: f32| rect.left() + i * w;
+    let label = |at: usize, power: usize, color: Color32| {
+        let x = x_at(at as f32);
+        let tick = rect.height() / (power as f32 + 1.0);
+        painter.line_segment(
+            [pos2(x, rect.bottom() - tick), pos2(x, rect.bottom())],
+            egui::Stroke::new(1.0, color),
+        );
+        painter.text(
+            pos2(x + 2.0, rect.top()),
+            egui::Align2::LEFT_TOP,
+            power.to_string(),
+            font.clone(),
+            color,
+        );
+    };
+    for &(at, power) in boundaries {
+        label(at, power, color);
+    }
+    if let Some(node) = latest {
+        label(node.right.start, node.power, active_color);
+        for run in [&node.left, &node.right] {
+            let mid = x_at(0.5 * (run.start + run.end) as f32);
+            painter.circle_filled(pos2(mid, rect.bottom() - 2.0), 2.5, active_color);
+        }
+    }
+}
+
+/// Shade the part of `rect` over elements `range` of an array of `n` and
+/// label it with `text`, for an algorithm's note on how it is handling
+/// those elements.
+pub fn draw_note(
+    painter: &Painter,
+    rect: Rect,
+    range: &Range<usize>,
+    n: usize,
+    text: &str,
+    color: Color32,
+) {
+    let w = rect.width() / n.max(1) as f32;
+    let x0 = rect.left() + range.start as f32 * w;
+    let x1 = rect.left() + range.end as f32 * w;
+    let area = Rect::from_min_max(pos2(x0, rect.top()), pos2(x1, rect.bottom()));
+    painter.rect_filled(area, 0.0, color.gamma_multiply(0.12));
+    painter.rect_stroke(
+        area,
+        0.0,
+        egui::Stroke::new(1.0, color),
+        egui::StrokeKind::Inside,
+    );
+    // Keep the label inside the view even for ranges near the right edge
+    let galley = painter.layout_no_wrap(text.to_owned(), egui::FontId::proportional(13.0), color);
+    let x = x0.min(rect.right() - galley.size().x).max(rect.left());
+    let label = Rect::from_min_size(pos2(x, rect.top()), galley.size()).expand(2.0);
+    painter.rect_filled(label, 2.0, painter.ctx().style().visuals.extreme_bg_color);
+    painter.galley(pos2(x, rect.top()), galley, color);
+}
+
+/// Draw block merge sort's keys as a band under an array of `n` elements:
+/// the tags, each in its colour from `tag_colors` or in `color` past its
+/// end, and the internal buffer in `buffer_color`.
+pub fn draw_keys(
+    painter: &Painter,
+    rect: Rect,
+    keys: &Keys,
+    tag_colors: &[Color32],
+    n: usize,
+    color: Color32,
+    buffer_color: Color32,
+) {
+    let w = rect.width() / n.max(1) as f32;
+    let span = |range: Range<usize>| {
+        Rect::from_min_max(
+            pos2(rect.left() + range.start as f32 * w, rect.top()),
+            pos2(rect.left() + range.end as f32 * w, rect.bottom()),
+        )
+    };
+    let used = keys.tags.start + tag_colors.len().min(keys.tags.len());
+    for (i, &fill) in (keys.tags.start..used).zip(tag_colors) {
+        painter.rect_filled(span(i..i + 1), 0.0, fill);
+    }
+    painter.rect_filled(span(used..keys.tags.end), 0.0, color);
+    painter.rect_filled(span(keys.buffer.clone()), 0.0, buffer_color);
+    let font = egui::FontId::proportional(rect.height().clamp(6.0, 11.0));
+    for (range, text) in [(keys.tags.clone(), "tags"), (keys.buffer.clone(), "buffer")] {
+        let area = span(range);
+        let galley = painter.layout_no_wrap(text.to_owned(), font.clone(), Color32::BLACK);
+        if galley.size().x < area.width() {
+            painter.galley(area.center() - galley.size() / 2.0, galley, Color32::BLACK);
+        }
+    }
+}
+
+/// Mark the blocks of a block merge over an array of `n` elements: a
+/// divider at the start of each, and a cap over it in the colour of the
+/// run it came from, from `colors`.
+pub fn draw_blocks(
+    painter: &Painter,
+    rect: Rect,
+    blocks: impl Iterator<Item = Range<usize>>,
+    colors: &[Color32],
+    n: usize,
+    divider_color: Color32,
+) {
+    let w = rect.width() / n.max(1) as f32;
+    for (block, &color) in blocks.zip(colors) {
+        let x0 = rect.left() + block.start as f32 * w;
+        let x1 = rect.left() + block.end as f32 * w;
+        painter.line_segment(
+            [pos2(x0, rect.top()), pos2(x0, rect.bottom())],
+            egui::Stroke::new(1.0, divider_color),
+        );
+        painter.rect_filled(
+            Rect::from_min_max(pos2(x0, rect.top()), pos2(x1, rect.top() + 5.0))
+                .shrink2(vec2(1.0, 0.0)),
+            1.0,
+            color,
+        );
+    }
+}
+
+/// Shade the two pieces of a rotation over an array of `n` elements and
+/// draw arrows for them trading places.
+pub fn draw_rotation(painter: &Painter, rect: Rect, rotation: &Rotation, n: usize, color: Color32) {
+    let w = rect.width() / n.max(1) as f32;
+    let x_at = |i: usize| rect.left() + i as f32 * w;
+    let y = rect.top() + 22.0;
+    for (range, gamma) in [(&rotation.left, 0.1), (&rotation.right, 0.2)] {
+        painter.rect_filled(
+            Rect::from_min_max(
+                pos2(x_at(range.start), rect.top()),
+                pos2(x_at(range.end), rect.bottom()),
+            ),
+            0.0,
+            color.gamma_multiply(gamma),
+        );
+    }
+    let left = 0.5 * (x_at(rotation.left.start) + x_at(rotation.left.end));
+    let right = 0.5 * (x_at(rotation.right.start) + x_at(rotation.right.end));
+    let stroke = egui::Stroke::new(1.5, color);
+    painter.arrow(pos2(left, y), vec2(right - left, 0.0), stroke);
+    painter.arrow(pos2(right, y + 6.0), vec2(left - right, 0.0), stroke);
+}
+
+/// Vertical bars, one per element.
+pub struct Bars;
+
+impl BarRenderer for Bars {
+    fn name(&self) -> &'static str {
+        "Bars"
+    }
+
+    fn draw(&self, painter: &Painter, rect: Rect, bars: &[SortBar], _: Color32) {
+        draw_bars(painter, rect, bars);
+    }
+}
+
+/// One dot per element at (index, value); sorted data is a diagonal line.
+pub struct Scatter;
+
+impl BarRenderer for Scatter {
+    fn name(&self) -> &'static str {
+        "Scatter"
+    }
+
+    fn draw(&self, painter: &Painter, rect: Rect, bars: &[SortBar], _: Color32) {
+        let n = bars.len() as f32;
+        let top = top(bars);
+        let r = dot_radius(rect, bars.len());
+        for (i, bar) in bars.iter().enumerate() {
+            let x = rect.left() + (i as f32 + 0.5) * rect.width() / n;
+            let y = rect.bottom() - rect.height() * (bar.value as f32 + 0.5) / top;
+            painter.circle_filled(pos2(x, y), r, bar.color);
+        }
+    }
+}
+
+/// Elements around a circle by index, pulled towards the centre the further
+/// they are from their sorted position. A sorted array is a full ring.
+pub struct DisparityCircle;
+
+impl BarRenderer for DisparityCircle {
+    fn name(&self) -> &'static str {
+        "Disparity circle"
+    }
+
+    fn draw(&self, painter: &Painter, rect: Rect, bars: &[SortBar], _: Color32) {
+        let n = bars.len() as f32;
+        let top = top(bars);
+        let center = rect.center();
+        let radius = 0.45 * rect.width().min(rect.height());
+        let r = dot_radius(rect, bars.len());
+        for (i, bar) in bars.iter().enumerate() {
+            // Where the value belongs if the values are a permutation
+            let home = bar.value as f32 * n / top;
+            let disparity = (i as f32 - home).abs() / n;
+            let point = polar(center, TAU * i as f32 / n, radius * (1.0 - disparity));
+            painter.circle_filled(point, r, bar.color);
+        }
+    }
+}
+
+/// Mark highlighted elements, whose colour is lost when hue encodes value,
+/// with a dot in their highlight colour.
+fn mark_highlight(painter: &Painter, at: Pos2, r: f32, bar: &SortBar, default_color: Color32) {
+    if bar.color != default_color {
+        painter.circle_filled(at, r, bar.color);
+    }
+}
+
+/// A disc of wedges by index, each coloured by its value. Sorted data
+/// sweeps once through the spectrum.
+pub struct ColorWheel;
+
+impl BarRenderer for ColorWheel {
+    fn name(&self) -> &'static str {
+        "Colour wheel"
+    }
+
+    fn draw(&self, painter: &Painter, rect: Rect, bars: &[SortBar], default_color: Color32) {
+        let n = bars.len() as f32;
+        let top = top(bars);
+        let center = rect.center();
+        let radius = 0.42 * rect.width().min(rect.height());
+        let marker = (0.04 * radius).max(2.0);
+        for (i, bar) in bars.iter().enumerate() {
+            let start = TAU * i as f32 / n;
+            let end = TAU * (i as f32 + 1.0) / n;
+            // Split wide wedges so the outer edge stays round
+            let steps = ((end - start) / 0.1).ceil().max(1.0) as usize;
+            let mut points = vec![center];
+            points.extend((0..=steps).map(|s| {
+                polar(
+                    center,
+                    start + (end - start) * s as f32 / steps as f32,
+                    radius,
+                )
+            }));
+            painter.add(egui::Shape::convex_polygon(
+                points,
+                hue(bar.value, top),
+                egui::Stroke::NONE,
+            ));
+            let middle = 0.5 * (start + end);
+            mark_highlight(
+                painter,
+                polar(center, middle, radius + 2.0 * marker),
+                marker,
+                bar,
+                default_color,
+            );
+        }
+    }
+}
+
+/// Elements wound outwards by index over a few turns, with the distance
+/// from the centre set by value. Sorted data is a smooth spiral.
+pub struct Spiral;
+
+impl BarRenderer for Spiral {
+    fn name(&self) -> &'static str {
+        "Spiral"
+    }
+
+    fn draw(&self, painter: &Painter, rect: Rect, bars: &[SortBar], _: Color32) {
+        const TURNS: f32 = 3.0;
+        let n = bars.len() as f32;
+        let top = top(bars);
+        let center = rect.center();
+        let radius = 0.45 * rect.width().min(rect.height());
+        let r = dot_radius(rect, bars.len());
+        for (i, bar) in bars.iter().enumerate() {
+            let angle = TURNS * TAU * i as f32 / n;
+            let point = polar(center, angle, radius * (bar.value as f32 + 1.0) / top);
+            painter.circle_filled(point, r, bar.color);
+        }
+    }
+}
+
+/// Every element as a one-pixel column coloured by value, wrapping onto
+/// more rows once there are more elements than pixels across. Meant for
+/// arrays too large for bars.
+pub struct HueStrip;
+
+impl BarRenderer for HueStrip {
+    fn name(&self) -> &'static str {
+        "Hue strip"
+    }
+
+    fn draw(&self, painter: &Painter, rect: Rect, bars: &[SortBar], default_color: Color32) {
+        let top = top(bars);
+        let pixel = 1.0 / painter.ctx().pixels_per_point();
+        let per_row = ((rect.width() / pixel) as usize).clamp(1, bars.len());
+        let rows = bars.len().div_ceil(per_row);
+        let w = rect.width() / per_row as f32;
+        let h = rect.height() / rows as f32;
+        // Leave a band at the top of each row for highlight marks
+        let band = (0.1 * h).min(6.0);
+        for (i, bar) in bars.iter().enumerate() {
+            let x = rect.left() + (i % per_row) as f32 * w;
+            let y = rect.top() + (i / per_row) as f32 * h;
+            painter.rect_filled(
+                Rect::from_min_size(pos2(x, y + band), vec2(w, h - band)),
+                0.0,
+                hue(bar.value, top),
+            );
+            if bar.color != default_color {
+                painter.rect_filled(
+                    Rect::from_min_size(pos2(x, y), vec2(w.max(pixel * 2.0), band)),
+                    0.0,
+                    bar.color,
+                );
+            }
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use crate::gif_handler::GifHandler;
 use crate::gui::bars_render::SortVisualizerApp;
-use eframe::egui::{self, pos2, vec2, Sense, Style, Visuals};
+use crate::gui::race::Race;
+use crate::input::Distribution;
+use crate::sorting::{Category, Registry};
+use eframe::egui::{self, pos2, vec2, Color32, Sense, Style, Visuals};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
 pub enum Theme {
@@ -15,6 +18,13 @@ impl Theme {
             Self::Light => Visuals::light(),
         }
     }
+    /// Bar colour when nothing is highlighting it.
+    pub fn bar_color(self) -> Color32 {
+        match self {
+            Self::Dark => Color32::WHITE,
+            Self::Light => Color32::BLACK,
+        }
+    }
     pub fn default_style(self) -> Style {
         Style {
             visuals: self.default_visuals(),
@@ -28,6 +38,7 @@ enum SortingAlgorithm {
     #[default]
     About,
     Controls,
+    Race,
     Info,
     Duck,
 }
@@ -36,30 +47,49 @@ pub struct Sorthos {
     selected_algorithm: SortingAlgorithm,
     theme: Theme,
     duck_gif: GifHandler,
+    registry: Registry,
     sort_app: SortVisualizerApp,
+    race: Race,
     max_speed: bool,
+    distribution: Distribution,
 }
 
 impl Default for Sorthos {
     fn default() -> Self {
+        Self::with_registry(Registry::with_builtins())
+    }
+}
+
+impl Sorthos {
+    /// Build the app around `registry`, starting on Quick Sort if it is
+    /// registered and on the first algorithm otherwise.
+    ///
+    /// Panics if `registry` is empty.
+    pub fn with_registry(registry: Registry) -> Self {
+        let initial = registry
+            .get("Quick Sort")
+            .or_else(|| registry.iter().next().cloned())
+            .expect("registry must contain at least one algorithm");
         Self {
             selected_algorithm: SortingAlgorithm::default(),
             theme: Theme::default(),
             duck_gif: GifHandler::default(),
-            sort_app: SortVisualizerApp::new(100, crate::sorting::SortingAlgorithm::QuickVisual),
+            sort_app: SortVisualizerApp::new(100, initial),
+            race: Race::new(&registry),
+            registry,
             max_speed: false,
+            distribution: Distribution::default(),
         }
     }
-}
 
-impl Sorthos {
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        let mut app = Self::default();
+    pub fn new(cc: &eframe::CreationContext<'_>, registry: Registry) -> Self {
+        let mut app = Self::with_registry(registry);
         const DUCK_GIF: &[u8] = include_bytes!("../assets/spinning-duck.gif");
         let _ = app
             .duck_gif
             .load_gif_from_bytes(&cc.egui_ctx, DUCK_GIF, "duck");
         app.sort_app.apply_theme(app.theme);
+        app.race.apply_theme(app.theme);
         app
     }
 
@@ -69,38 +99,37 @@ impl Sorthos {
 
         ui.label("Below is a list of how to and where sorting algorithms are used:");
 
-        ui.separator();
-        ui.label("Production-Grade Algorithms");
-        ui.label("- Timsort: O(n log n) worst case, O(n) best case (adaptive), Stable");
-        ui.label("- Heapsort: O(n log n) all cases, Unstable");
-        ui.label("- Merge Sort: O(n log n) all cases, Stable");
-        ui.label("- Quicksort: O(n log n) average, O(n²) worst case, Unstable");
-
-        ui.separator();
-        ui.label("Efficient Specialized Algorithms");
-        ui.label("- Counting Sort: O(n + k), Stable");
-        ui.label("- Radix Sort: O(d × (n + k)), Stable");
-        ui.label("- Shell Sort: O(n^1.25) to O(n^1.5), Unstable");
-
-        ui.separator();
-        ui.label("Advanced Research Algorithms");
-
-        ui.separator();
-        ui.label("Educational Algorithms");
-        ui.label("- Bubble Sort: O(n²) average and worst case, O(n) best case (adaptive), Stable");
-        ui.label(
-            "- Insertion Sort: O(n²) average and worst case, O(n) best case (adaptive), Stable",
-        );
-        ui.label("- Selection Sort: O(n²) all cases, Unstable");
-        ui.label("- Cocktail Sort: O(n²) average and worst case, Stable");
-        ui.label("- Gnome Sort: O(n²) worst case, O(n) best case, Stable");
-
-        ui.separator();
-        ui.label("Specialized and Novelty Algorithms");
-
-        ui.separator();
-        ui.label("Probabilistic Algorithms");
-        ui.label("- Bogo Sort: O((n+1)!) worst case, O(n) best case, Not guaranteed to terminate");
+        for &category in Category::all() {
+            let mut algorithms = self.registry.in_category(category).peekable();
+            if algorithms.peek().is_none() {
+                continue;
+            }
+            ui.separator();
+            ui.label(category.to_string());
+            for algorithm in algorithms {
+                let stability = if algorithm.is_stable() {
+                    "Stable"
+                } else {
+                    "Unstable"
+                };
+                ui.label(format!(
+                    "- {}: {}, {}",
+                    algorithm.name(),
+                    algorithm.complexity(),
+                    stability
+                ));
+                let listing = algorithm.pseudocode();
+                if !listing.is_empty() {
+                    egui::CollapsingHeader::new("Pseudocode")
+                        .id_salt(algorithm.name())
+                        .show(ui, |ui| {
+                            for line in listing {
+                                ui.monospace(*line);
+                            }
+                        });
+                }
+            }
+        }
     }
 
     fn show_duck_page(&mut self, ui: &mut egui::Ui) {
@@ -118,22 +147,22 @@ impl Sorthos {
         ui.label("Controls:");
 
         ui.horizontal(|ui| {
-            if ui.button("Shuffle").clicked() && !self.sort_app.sorting {
+            if ui.button("Shuffle").clicked() && !self.sort_app.is_sorting() {
                 self.sort_app.shuffle_bars();
             }
         });
 
         ui.horizontal(|ui| {
-            if ui.button("Remove Duplicates").clicked() && !self.sort_app.sorting {
+            if ui.button("Remove Duplicates").clicked() && !self.sort_app.is_sorting() {
                 self.sort_app.remove_duplicates();
             }
-            if ui.button("Generate Duplicates").clicked() && !self.sort_app.sorting {
+            if ui.button("Generate Duplicates").clicked() && !self.sort_app.is_sorting() {
                 self.sort_app.generate_with_duplicates();
             }
         });
 
         ui.horizontal(|ui| {
-            if ui.button("Reset").clicked() && !self.sort_app.sorting {
+            if ui.button("Reset").clicked() && !self.sort_app.is_sorting() {
                 self.sort_app.reset_bars();
             }
         });
@@ -143,6 +172,44 @@ impl Sorthos {
 
         ui.separator();
 
+        ui.label("Input:");
+        egui::ComboBox::from_id_salt("distribution")
+            .selected_text(self.distribution.name())
+            .show_ui(ui, |ui| {
+                for &distribution in Distribution::all() {
+                    let selected = self.distribution.name() == distribution.name();
+                    if ui.selectable_label(selected, distribution.name()).clicked() && !selected {
+                        self.distribution = distribution;
+                    }
+                }
+            });
+        if let Some((label, _)) = self.distribution.parameter() {
+            let max = self.sort_app.num_bars;
+            if let Some(value) = self.distribution.parameter_mut() {
+                ui.add(egui::Slider::new(value, 1..=max).text(label));
+            }
+        }
+        if ui.button("Generate").clicked() && !self.sort_app.is_sorting() {
+            self.sort_app.generate(self.distribution);
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Open array…").clicked() && !self.sort_app.is_sorting() {
+                if let Some(path) = array_dialog().pick_file() {
+                    self.sort_app.open_array(&path);
+                }
+            }
+            if ui.button("Save array…").clicked() {
+                if let Some(path) = array_dialog().set_file_name("array.csv").save_file() {
+                    self.sort_app.save_array(&path);
+                }
+            }
+        })
+        .response
+        .on_hover_text("CSV, JSON array, or one value per line (.txt)");
+
+        ui.separator();
+
         ui.label("Status:");
         if !self.sort_app.status_message.is_empty() {
             ui.label(&self.sort_app.status_message);
@@ -159,6 +226,14 @@ impl Sorthos {
     }
 }
 
+/// File dialog offering the formats `array_file` understands.
+fn array_dialog() -> rfd::FileDialog {
+    rfd::FileDialog::new()
+        .add_filter("CSV", &["csv"])
+        .add_filter("JSON", &["json"])
+        .add_filter("Text", &["txt"])
+}
+
 fn toggle_ui(ui: &mut egui::Ui, on: &mut bool) -> egui::Response {
     let size = ui.spacing().interact_size.y * vec2(2.0, 1.0);
     let (rect, mut response) = ui.allocate_exact_size(size, Sense::click());
@@ -202,7 +277,7 @@ impl eframe::App for Sorthos {
                 ui.separator();
                 let mut max_speed = self.max_speed;
                 let toggle_resp = ui.add_enabled(
-                    !self.sort_app.sorting,
+                    !self.sort_app.is_sorting(),
                     egui::widgets::Checkbox::new(&mut max_speed, "Max Speed"),
                 );
                 if toggle_resp.changed() {
@@ -218,6 +293,7 @@ impl eframe::App for Sorthos {
                     self.theme = if is_light { Theme::Light } else { Theme::Dark };
                     self.sort_app.reset_bars();
                     self.sort_app.apply_theme(self.theme);
+                    self.race.apply_theme(self.theme);
                 }
             });
             ui.separator();
@@ -239,6 +315,12 @@ impl eframe::App for Sorthos {
             {
                 self.selected_algorithm = SortingAlgorithm::Controls;
             }
+            if ui
+                .selectable_label(self.selected_algorithm == SortingAlgorithm::Race, "race")
+                .clicked()
+            {
+                self.selected_algorithm = SortingAlgorithm::Race;
+            }
             if ui
                 .selectable_label(self.selected_algorithm == SortingAlgorithm::Duck, "duck")
                 .clicked()
@@ -259,21 +341,40 @@ impl eframe::App for Sorthos {
             SortingAlgorithm::About => {
                 egui::SidePanel::left("sorting_controls").show(ctx, |ui| {
                     ui.label("Algorithm:");
-                    for &alg in crate::sorting::SortingAlgorithm::all() {
-                        if ui
-                            .selectable_label(self.sort_app.algorithm == alg, format!("{alg}"))
-                            .clicked()
-                        {
-                            self.sort_app.algorithm = alg;
+                    for alg in self.registry.iter() {
+                        let selected = self.sort_app.algorithm.name() == alg.name();
+                        if ui.selectable_label(selected, alg.name()).clicked() && !selected {
+                            // Switching algorithms abandons the current run
+                            self.sort_app.cancel_sort();
+                            self.sort_app.algorithm = alg.clone();
                         }
                     }
                     ui.separator();
-                    if ui.button("Sort").clicked() && !self.sort_app.sorting {
+                    self.sort_app.show_speed_controls(ui);
+                    self.sort_app.show_view_controls(ui);
+                    ui.separator();
+                    if ui.button("Sort").clicked() && !self.sort_app.is_sorting() {
                         self.sort_app.start_sorting(self.max_speed);
                     }
+                    self.sort_app.show_playback_controls(ui);
+                    if !self.sort_app.status_message.is_empty() {
+                        ui.small(&self.sort_app.status_message);
+                    }
+                    ui.separator();
+                    self.sort_app.show_stats(ui);
+                    self.sort_app.show_merge_comparison(ui);
+                    ui.separator();
+                    self.sort_app.show_export_controls(ui);
                 });
                 self.sort_app.update(ctx, frame);
             }
+            SortingAlgorithm::Race => {
+                egui::SidePanel::left("race_controls").show(ctx, |ui| {
+                    self.race
+                        .show_controls(ui, &self.registry, &self.sort_app.bars);
+                });
+                self.race.update(ctx);
+            }
             SortingAlgorithm::Controls => {
                 egui::CentralPanel::default().show(ctx, |ui| self.show_controls_page(ui));
             }
@@ -285,6 +386,11 @@ impl eframe::App for Sorthos {
 }
 
 pub fn run_gui() -> Result<(), eframe::Error> {
+    run_gui_with(Registry::with_builtins())
+}
+
+/// Run the GUI offering the algorithms in `registry`.
+pub fn run_gui_with(registry: Registry) -> Result<(), eframe::Error> {
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([800.0, 600.0]),
         ..Default::default()
@@ -292,6 +398,6 @@ pub fn run_gui() -> Result<(), eframe::Error> {
     eframe::run_native(
         "Sorthos",
         options,
-        Box::new(|cc| Ok(Box::new(Sorthos::new(cc)) as Box<dyn eframe::App>)),
+        Box::new(|cc| Ok(Box::new(Sorthos::new(cc, registry)) as Box<dyn eframe::App>)),
     )
 }
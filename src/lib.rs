@@ -0,0 +1,35 @@
+//! Sorthos: a sorting algorithm visualizer.
+//!
+//! The `sorthos` binary only calls [`gui_backend::gui::run_gui`];
+//! `sorthos-cli` runs the same algorithms headless. Other crates can add
+//! their own algorithms by implementing [`sorting::SortAlgorithm`],
+//! registering them in a [`sorting::Registry`] and passing that to
+//! [`gui_backend::gui::run_gui_with`].
+
+#[path = "core/array_file.rs"]
+pub mod array_file;
+#[path = "core/bench.rs"]
+pub mod bench;
+pub mod code;
+pub mod export;
+#[path = "gif/gif_handler.rs"]
+pub mod gif_handler;
+pub mod gui;
+pub mod gui_backend;
+#[path = "core/input.rs"]
+pub mod input;
+#[path = "core/json.rs"]
+pub mod json;
+#[path = "core/models.rs"]
+pub mod models;
+#[path = "core/playback.rs"]
+pub mod playback;
+#[path = "core/sorting.rs"]
+pub mod sorting;
+#[path = "core/stats.rs"]
+pub mod stats;
+
+#[cfg(test)]
+mod algorithm_tests;
+#[cfg(test)]
+mod tests;
@@ -1,10 +1,34 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
 
+    use crate::array_file::{self, ArrayFileError, ArrayFormat};
+    use crate::bench::Summary;
+    use crate::code::speed::{max_speed_sort, parallel_sort};
+    use crate::export::gif::write_gif;
+    use crate::export::history::{history_image, ops_per_row};
+    use crate::export::png::write_png_sequence;
+    use crate::export::wav::{self, write_wav, SAMPLE_RATE};
+    use crate::export::y4m::write_y4m;
+    use crate::export::{
+        frame_count, frames, render_bars, work_chunks, work_len, ExportFormat, ExportOptions,
+        FrameStyle, GifPalette,
+    };
+    use crate::gui::renderers::Scatter;
+    use crate::gui_backend::gui::Theme;
+    use crate::input::Distribution;
     use crate::models::SortBar;
-    use crate::sorting::SortingAlgorithm;
+    use crate::playback::{apply, Playback, PlaybackSpeed, Undo, UNDO_LIMIT};
+    use crate::sorting::{
+        CancelToken, Category, Complexity, Operation, Registry, Sink, SortAlgorithm,
+        OP_CHANNEL_CAPACITY,
+    };
+    use crate::stats::Stats;
     use eframe::egui::Color32;
-    use std::sync::mpsc;
+    use rand::rngs::StdRng;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use std::sync::{mpsc, Arc};
 
     /// Helper function to create a vector of SortBars from values
     fn create_bars(values: Vec<usize>) -> Vec<SortBar> {
@@ -21,6 +45,14 @@ mod tests {
         values.windows(2).all(|w| w[0] <= w[1])
     }
 
+    /// A sink whose receiving end is drained on a background thread, so
+    /// algorithms never block on a full channel
+    fn drained_sink() -> Sink {
+        let (tx, rx) = mpsc::sync_channel(OP_CHANNEL_CAPACITY);
+        std::thread::spawn(move || for _ in rx {});
+        Sink::new(tx, CancelToken::new())
+    }
+
     /// Count duplicates in a slice
     fn count_duplicates(values: &[usize]) -> usize {
         let mut seen = std::collections::HashMap::new();
@@ -28,9 +60,7 @@ mod tests {
         for &value in values {
             let count = seen.entry(value).or_insert(0);
             *count += 1;
-            if *count == 2 {
-                duplicate_count += 1;
-            } else if *count > 2 {
+            if *count >= 2 {
                 duplicate_count += 1;
             }
         }
@@ -49,8 +79,7 @@ mod tests {
         let mut seen = std::collections::HashMap::new();
         let mut unique_values = Vec::new();
         for &value in &values_with_dups {
-            if !seen.contains_key(&value) {
-                seen.insert(value, true);
+            if seen.insert(value, true).is_none() {
                 unique_values.push(value);
             }
         }
@@ -80,8 +109,7 @@ mod tests {
         let mut seen = std::collections::HashMap::new();
         let mut unique_values = Vec::new();
         for &value in &values_no_dups {
-            if !seen.contains_key(&value) {
-                seen.insert(value, true);
+            if seen.insert(value, true).is_none() {
                 unique_values.push(value);
             }
         }
@@ -192,7 +220,7 @@ mod tests {
     /// Test large value handling (for counting sort safety)
     #[test]
     fn test_large_value_handling() {
-        let large_values = vec![100001, 50000, 200000];
+        let large_values = [100001, 50000, 200000];
         let max_val = *large_values.iter().max().unwrap();
 
         const MAX_SAFE_SIZE: usize = 10000;
@@ -207,18 +235,72 @@ mod tests {
 
     /// Test sorting algorithm selection and basic validation
     #[test]
-    fn test_algorithm_enum() {
-        let algorithms = SortingAlgorithm::all();
+    fn test_algorithm_registry() {
+        let registry = Registry::with_builtins();
         assert!(
-            !algorithms.is_empty(),
+            !registry.is_empty(),
             "Should have sorting algorithms available"
         );
 
-        // Test that all algorithms have display names
-        for &algorithm in algorithms {
-            let name = format!("{}", algorithm);
+        // Test that all algorithms have unique display names
+        let mut names = std::collections::HashSet::new();
+        for algorithm in registry.iter() {
+            let name = algorithm.name();
             assert!(!name.is_empty(), "Algorithm should have a display name");
+            assert!(names.insert(name), "Duplicate algorithm name {}", name);
+            assert!(registry.get(&name.to_lowercase()).is_some());
+        }
+    }
+
+    /// Algorithms from outside the built-in set can be registered, and a
+    /// registration with an existing name replaces the old one
+    #[test]
+    fn test_register_custom_algorithm() {
+        struct Reverse;
+
+        impl SortAlgorithm for Reverse {
+            fn name(&self) -> &'static str {
+                "Bubble Sort"
+            }
+
+            fn category(&self) -> Category {
+                Category::Novelty
+            }
+
+            fn is_stable(&self) -> bool {
+                false
+            }
+
+            fn complexity(&self) -> Complexity {
+                Complexity {
+                    best: "O(n)",
+                    average: "O(n)",
+                    worst: "O(n)",
+                    space: "O(1)",
+                }
+            }
+
+            fn run(
+                &self,
+                bars: &mut [SortBar],
+                _sink: &Sink,
+            ) -> Result<(), crate::sorting::Cancelled> {
+                bars.reverse();
+                Ok(())
+            }
         }
+
+        let mut registry = Registry::with_builtins();
+        let before = registry.len();
+        registry.register(Reverse);
+        assert_eq!(registry.len(), before, "same name should replace");
+        let bubble = registry.get("Bubble Sort").unwrap();
+        assert_eq!(bubble.category(), Category::Novelty);
+        assert_eq!(
+            registry.in_category(Category::Novelty).count(),
+            1,
+            "replacement should be listed under its own category"
+        );
     }
 
     /// Test SortBar creation and manipulation
@@ -244,7 +326,7 @@ mod tests {
     /// Test bounds checking for quicksort-style algorithms
     #[test]
     fn test_bounds_checking() {
-        let values = vec![3, 1, 4, 1, 5];
+        let values = [3, 1, 4, 1, 5];
         let len = values.len();
 
         // Test that bounds are properly checked
@@ -259,7 +341,7 @@ mod tests {
         }
 
         // Test edge case with single element
-        let single = vec![42];
+        let single = [42];
         if single.len() <= 1 {
             // Should not attempt to sort
             assert!(
@@ -277,7 +359,7 @@ mod tests {
         let mut bars = create_bars(test_values);
 
         // Simulate a simple sorting operation
-        bars.sort_by(|a, b| a.value.cmp(&b.value));
+        bars.sort_by_key(|b| b.value);
         let sorted_values = extract_values(&bars);
 
         assert!(is_sorted(&sorted_values), "Bars should be sorted");
@@ -357,7 +439,7 @@ mod tests {
     /// Test TimSort functionality specifically
     #[test]
     fn test_timsort_functionality() {
-        use crate::sorting::{tim_sort, Operation};
+        use crate::sorting::tim_sort;
 
         let test_cases = vec![
             vec![5, 2, 8, 1, 9, 3, 7, 4, 6],   // Random order
@@ -374,10 +456,10 @@ mod tests {
         for test_values in test_cases {
             let original_values = test_values.clone();
             let mut bars = create_bars(test_values);
-            let (tx, _rx) = mpsc::channel::<Operation>();
+            let sink = drained_sink();
 
             // Apply TimSort
-            tim_sort(&mut bars, &tx);
+            tim_sort(&mut bars, &sink).unwrap();
 
             // Extract sorted values
             let sorted_values = extract_values(&bars);
@@ -406,17 +488,17 @@ mod tests {
     /// Test TimSort with edge cases
     #[test]
     fn test_timsort_edge_cases() {
-        use crate::sorting::{tim_sort, Operation};
+        use crate::sorting::tim_sort;
 
         // Test empty array
         let mut empty_bars: Vec<SortBar> = vec![];
-        let (tx, _rx) = mpsc::channel::<Operation>();
-        tim_sort(&mut empty_bars, &tx);
+        let sink = drained_sink();
+        tim_sort(&mut empty_bars, &sink).unwrap();
         assert!(empty_bars.is_empty(), "Empty array should remain empty");
 
         // Test single element
         let mut single_bars = create_bars(vec![42]);
-        tim_sort(&mut single_bars, &tx);
+        tim_sort(&mut single_bars, &sink).unwrap();
         assert_eq!(
             extract_values(&single_bars),
             vec![42],
@@ -426,15 +508,629 @@ mod tests {
         // Test array with MIN_MERGE size (32 elements)
         let test_32: Vec<usize> = (0..32).rev().collect();
         let mut bars_32 = create_bars(test_32.clone());
-        tim_sort(&mut bars_32, &tx);
+        tim_sort(&mut bars_32, &sink).unwrap();
         let sorted_32 = extract_values(&bars_32);
         assert!(is_sorted(&sorted_32), "32-element array should be sorted");
 
         // Test array just above MIN_MERGE size (33 elements)
         let test_33: Vec<usize> = (0..33).rev().collect();
         let mut bars_33 = create_bars(test_33.clone());
-        tim_sort(&mut bars_33, &tx);
+        tim_sort(&mut bars_33, &sink).unwrap();
         let sorted_33 = extract_values(&bars_33);
         assert!(is_sorted(&sorted_33), "33-element array should be sorted");
     }
+
+    /// Playback in ops/sec mode applies operations in proportion to elapsed time
+    #[test]
+    fn test_playback_ops_per_second() {
+        let mut bars = create_bars(vec![3, 2, 1, 0]);
+        let mut playback = Playback::new(PlaybackSpeed::OpsPerSecond(100.0));
+        for _ in 0..10 {
+            playback.push(Operation::SetColor(0, Color32::RED));
+        }
+
+        assert!(!playback.advance(0.05, &mut bars, Color32::WHITE));
+        assert_eq!(playback.cursor(), 5, "100 ops/sec for 50ms is 5 ops");
+        assert_eq!(playback.buffered(), 5);
+
+        // Fractions of an operation carry over between frames
+        playback.advance(0.025, &mut bars, Color32::WHITE);
+        playback.advance(0.025, &mut bars, Color32::WHITE);
+        assert_eq!(playback.cursor(), 10);
+    }
+
+    /// Playback in ops/frame mode applies a fixed count and reports Done
+    #[test]
+    fn test_playback_ops_per_frame() {
+        let mut bars = create_bars(vec![1, 0]);
+        let mut playback = Playback::new(PlaybackSpeed::OpsPerFrame(2));
+        playback.push(Operation::Compare(0, 1));
+        playback.push(Operation::Swap(0, 1));
+        playback.push(Operation::SetColor(0, Color32::WHITE));
+        playback.push(Operation::Done);
+
+        assert!(!playback.advance(0.0, &mut bars, Color32::BLACK));
+        assert_eq!(extract_values(&bars), vec![0, 1]);
+        assert!(playback.advance(0.0, &mut bars, Color32::BLACK));
+        assert_eq!(
+            bars[0].color,
+            Color32::BLACK,
+            "WHITE resets should map to the theme default"
+        );
+        assert_eq!(playback.buffered(), 0);
+    }
+
+    /// Paused playback applies nothing until stepped, and an Overwrite steps
+    /// back to the bar it replaced
+    #[test]
+    fn test_playback_pause_and_step() {
+        let mut bars = create_bars(vec![4, 5]);
+        bars[1].color = Color32::RED;
+        let mut playback = Playback::new(PlaybackSpeed::OpsPerFrame(10));
+        playback.push(Operation::Overwrite(1, SortBar::new(9)));
+        playback.push(Operation::Done);
+        playback.paused = true;
+
+        assert!(!playback.advance(1.0, &mut bars, Color32::WHITE));
+        assert_eq!(playback.cursor(), 0, "paused playback should not advance");
+
+        assert!(playback.step_forward(&mut bars, Color32::WHITE));
+        assert_eq!(extract_values(&bars), vec![4, 9]);
+        assert!(playback.step_forward(&mut bars, Color32::WHITE));
+        assert!(playback.is_finished());
+        assert!(!playback.step_forward(&mut bars, Color32::WHITE));
+
+        assert!(playback.step_backward(&mut bars));
+        assert!(!playback.is_finished());
+        assert!(playback.step_backward(&mut bars));
+        assert_eq!(extract_values(&bars), vec![4, 5]);
+        assert_eq!(bars[1].color, Color32::RED);
+        assert!(!playback.step_backward(&mut bars));
+    }
+
+    /// Undo records stay small and stop at `UNDO_LIMIT`, and stepping back
+    /// as far as they go restores the state from that point
+    #[test]
+    fn test_playback_undo_limit() {
+        assert!(std::mem::size_of::<Undo>() <= 40);
+
+        let mut bars = create_bars(vec![1, 2, 3]);
+        let mut playback = Playback::new(PlaybackSpeed::OpsPerFrame(1));
+        let extra = 1000;
+        for k in 0..UNDO_LIMIT + extra {
+            playback.push(Operation::Swap(k % 2, 2));
+            if k + 1 == extra {
+                playback.push(Operation::Note(0, 3, "note"));
+            }
+        }
+        while playback.step_forward(&mut bars, Color32::WHITE) {}
+        let mut stepped = 0;
+        while playback.step_backward(&mut bars) {
+            stepped += 1;
+        }
+        assert_eq!(stepped, UNDO_LIMIT);
+        assert!(!playback.can_step_backward());
+        assert_eq!(playback.cursor(), extra + 1);
+        assert_eq!(playback.stats().swaps, extra);
+        assert_eq!(playback.note().map(|note| note.text), Some("note"));
+
+        // Only the swaps the undo records no longer reach are left
+        let mut expected = vec![1, 2, 3];
+        for k in 0..extra {
+            expected.swap(k % 2, 2);
+        }
+        assert_eq!(extract_values(&bars), expected);
+    }
+
+    /// Stepping back restores the stats exactly, including an exit or drop
+    /// that `Stats::record` stopped at zero
+    #[test]
+    fn test_playback_undoes_stats() {
+        let trace = [
+            Operation::ExitRange(0, 2),
+            Operation::EnterRange(0, 2),
+            Operation::AuxCreate(0, "buffer", 2),
+            Operation::AuxDrop(0, 5),
+            Operation::ExitRange(0, 2),
+            Operation::ExitRange(0, 2),
+            Operation::EnterRange(0, 2),
+        ];
+        let mut bars = create_bars(vec![2, 1]);
+        let mut playback = Playback::new(PlaybackSpeed::OpsPerFrame(1));
+        for op in trace {
+            playback.push(op);
+        }
+        let mut seen = vec![*playback.stats()];
+        while playback.step_forward(&mut bars, Color32::WHITE) {
+            seen.push(*playback.stats());
+        }
+        assert_eq!(seen.last().map(|stats| stats.depth), Some(1));
+        assert_eq!(seen.last().map(|stats| stats.peak_aux), Some(2));
+        while playback.step_backward(&mut bars) {
+            assert_eq!(*playback.stats(), seen[playback.cursor()]);
+        }
+        assert_eq!(*playback.stats(), Stats::default());
+    }
+
+    /// Command-line style lookups ignore case, separators and "sort"
+    #[test]
+    fn test_registry_find() {
+        let registry = Registry::with_builtins();
+        for query in ["quick", "Quick Sort", "quick-sort", "QUICKSORT"] {
+            let found = registry.find(query).expect(query);
+            assert_eq!(found.name(), "Quick Sort");
+        }
+        assert_eq!(registry.find("tim").unwrap().name(), "Tim Sort");
+        assert!(registry.find("sort").is_none());
+        assert!(registry.find("nope").is_none());
+    }
+
+    /// Generated inputs are reproducible from a seed and have the right shape
+    #[test]
+    fn test_input_distributions() {
+        for &distribution in Distribution::all() {
+            let a = distribution.generate(50, &mut StdRng::seed_from_u64(7));
+            let b = distribution.generate(50, &mut StdRng::seed_from_u64(7));
+            assert_eq!(a, b, "{} should be reproducible", distribution);
+            assert_eq!(a.len(), 50);
+            assert!(a.iter().all(|&v| v < 50), "{} out of range", distribution);
+            assert_eq!(
+                distribution.name().parse::<Distribution>(),
+                Ok(distribution)
+            );
+        }
+
+        let mut rng = StdRng::seed_from_u64(1);
+        assert!(is_sorted(&Distribution::Sorted.generate(20, &mut rng)));
+        let reversed = Distribution::Reversed.generate(20, &mut rng);
+        assert!(reversed.windows(2).all(|w| w[0] > w[1]));
+        let duplicates = Distribution::Duplicates.generate(30, &mut rng);
+        assert!(count_duplicates(&duplicates) > 0);
+        assert!("sideways".parse::<Distribution>().is_err());
+    }
+
+    /// The structured shapes look the way their names say
+    #[test]
+    fn test_input_shapes() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let n = 60;
+
+        let nearly = Distribution::NearlySorted { swaps: 2 }.generate(n, &mut rng);
+        let misplaced = nearly.iter().enumerate().filter(|(i, v)| *i != **v).count();
+        assert!(misplaced <= 4, "two swaps move at most four elements");
+
+        let pipe = Distribution::OrganPipe.generate(n, &mut rng);
+        let peak = pipe
+            .iter()
+            .position(|&v| v == *pipe.iter().max().unwrap())
+            .unwrap();
+        assert!(is_sorted(&pipe[..=peak]));
+        assert!(pipe[peak..].windows(2).all(|w| w[0] >= w[1]));
+
+        let saw = Distribution::Sawtooth { teeth: 3 }.generate(n, &mut rng);
+        assert_eq!(saw.windows(2).filter(|w| w[0] > w[1]).count(), 2);
+
+        let few = Distribution::FewUnique { unique: 3 }.generate(n, &mut rng);
+        let distinct: std::collections::HashSet<_> = few.iter().collect();
+        assert!(distinct.len() <= 3);
+
+        let tail = Distribution::SortedWithTail { tail: 10 }.generate(n, &mut rng);
+        assert!(is_sorted(&tail[..n - 10]));
+
+        let runs = Distribution::RandomRuns { runs: 4 }.generate(n, &mut rng);
+        assert!(runs.chunks(15).all(is_sorted));
+
+        let skewed = Distribution::Skewed.generate(1000, &mut rng);
+        assert!(skewed.iter().filter(|&&v| v < 500).count() > 700);
+
+        assert_eq!(
+            "few-unique:3".parse::<Distribution>(),
+            Ok(Distribution::FewUnique { unique: 3 })
+        );
+        assert_eq!(
+            Distribution::FewUnique { unique: 3 }.to_string(),
+            "few-unique:3"
+        );
+        assert!("sorted:3".parse::<Distribution>().is_err());
+        assert!("few-unique:x".parse::<Distribution>().is_err());
+    }
+
+    /// Arrays round-trip through every file format, and bad input is
+    /// reported with the offending value
+    #[test]
+    fn test_array_files() {
+        let values = vec![5, 0, 12, 3, 3];
+        for format in [ArrayFormat::Csv, ArrayFormat::Json, ArrayFormat::Lines] {
+            let text = array_file::format(&values, format);
+            assert_eq!(array_file::parse(&text, format).unwrap(), values);
+        }
+
+        assert_eq!(
+            array_file::parse("1, 2\n3,\n", ArrayFormat::Csv).unwrap(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            array_file::parse("[\n  4,\n  2\n]", ArrayFormat::Json).unwrap(),
+            vec![4, 2]
+        );
+        assert!(matches!(
+            array_file::parse("1\n-2\n", ArrayFormat::Lines),
+            Err(ArrayFileError::InvalidValue { position: 2, .. })
+        ));
+        assert!(matches!(
+            array_file::parse("[1,,2]", ArrayFormat::Json),
+            Err(ArrayFileError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            array_file::parse("{\"values\": [1]}", ArrayFormat::Json),
+            Err(ArrayFileError::NotJsonArray)
+        ));
+        assert!(matches!(
+            array_file::parse(" \n", ArrayFormat::Lines),
+            Err(ArrayFileError::Empty)
+        ));
+        let limit = array_file::MAX_VALUE.to_string();
+        assert_eq!(
+            array_file::parse(&limit, ArrayFormat::Lines).unwrap(),
+            vec![array_file::MAX_VALUE]
+        );
+        assert!(matches!(
+            array_file::parse("[3, 1000000000]", ArrayFormat::Json),
+            Err(ArrayFileError::TooLarge {
+                position: 2,
+                value: 1_000_000_000
+            })
+        ));
+
+        let path = std::env::temp_dir().join(format!("sorthos-{}.json", std::process::id()));
+        array_file::save(&path, &values).unwrap();
+        assert_eq!(ArrayFormat::from_path(&path), ArrayFormat::Json);
+        assert_eq!(array_file::load(&path).unwrap(), values);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            array_file::load(&path),
+            Err(ArrayFileError::Io(_))
+        ));
+    }
+
+    /// Offscreen frames draw bars like the central panel, and a GIF gets one
+    /// frame per stride plus the initial state
+    #[test]
+    fn test_gif_export() {
+        use image::codecs::gif::GifDecoder;
+        use image::AnimationDecoder;
+
+        let style = FrameStyle {
+            width: 40,
+            height: 20,
+            theme: Theme::Dark,
+            ..FrameStyle::default()
+        };
+        let mut bars = create_bars(vec![1, 3, 0, 2]);
+        bars[1].color = Color32::RED;
+        let image = render_bars(&bars, &style);
+        // The tallest bar fills its column, the shortest only the bottom quarter
+        assert_eq!(image.get_pixel(12, 0).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(22, 19).0, Color32::WHITE.to_array());
+        assert_ne!(image.get_pixel(22, 10).0, Color32::WHITE.to_array());
+
+        let trace = vec![
+            Operation::Compare(0, 1),
+            Operation::Swap(0, 2),
+            Operation::SetColor(0, Color32::WHITE),
+            Operation::Done,
+        ];
+        let options = ExportOptions {
+            format: ExportFormat::Gif,
+            style,
+            stride: 3,
+            delay_ms: 50,
+            palette: GifPalette::Grayscale,
+        };
+        let mut gif = Vec::new();
+        write_gif(&bars, &trace, &options, &mut gif).unwrap();
+        let frames = GifDecoder::new(std::io::Cursor::new(gif))
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        assert_eq!(frames.len(), frame_count(trace.len(), 3));
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].buffer().dimensions(), (40, 20));
+        let gray = frames[0].buffer().get_pixel(12, 0).0;
+        assert!(gray[0] == gray[1] && gray[1] == gray[2]);
+    }
+
+    /// Exported frames go through the renderer chosen on screen, and show
+    /// the same overlays, here the brackets of a recursive call
+    #[test]
+    fn test_export_follows_view() {
+        let bars = create_bars(vec![1, 3, 0, 2]);
+        let style = FrameStyle {
+            width: 40,
+            height: 40,
+            theme: Theme::Dark,
+            ..FrameStyle::default()
+        };
+        let scatter = FrameStyle {
+            renderer: Arc::new(Scatter),
+            ..style.clone()
+        };
+        let plain = render_bars(&bars, &style);
+        assert_ne!(render_bars(&bars, &scatter), plain);
+        let trace = [Operation::Compare(0, 1)];
+        let last = |trace: &[Operation], style: &FrameStyle| frames(&bars, trace, 1, style).last();
+        let mut compared = bars.clone();
+        apply(&trace[0], &mut compared, Color32::WHITE);
+        assert_eq!(
+            last(&trace, &scatter),
+            Some(render_bars(&compared, &scatter))
+        );
+
+        let recursing = [Operation::EnterRange(0, 4), Operation::Compare(0, 1)];
+        assert_ne!(last(&recursing, &style), last(&trace, &style));
+    }
+
+    /// PNG frames and Y4M video carry the same frames as GIF, without a
+    /// palette in the way
+    #[test]
+    fn test_png_and_y4m_export() {
+        let bars = create_bars(vec![1, 3, 0, 2]);
+        let trace = vec![
+            Operation::Compare(0, 1),
+            Operation::Swap(0, 2),
+            Operation::Done,
+        ];
+        let options = ExportOptions {
+            format: ExportFormat::PngSequence,
+            style: FrameStyle {
+                width: 40,
+                height: 20,
+                theme: Theme::Light,
+                ..FrameStyle::default()
+            },
+            stride: 2,
+            ..ExportOptions::default()
+        };
+
+        let dir = std::env::temp_dir().join(format!("sorthos-frames-{}", std::process::id()));
+        write_png_sequence(&bars, &trace, &options, &dir).unwrap();
+        let mut names: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            ["frame_000000.png", "frame_000001.png", "frame_000002.png"]
+        );
+        let first = image::open(dir.join(&names[0])).unwrap().to_rgba8();
+        assert_eq!(first.dimensions(), (40, 20));
+        // The input is drawn in the theme's bar colour, like on screen
+        assert_eq!(first.get_pixel(12, 0).0, Color32::BLACK.to_array());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let mut y4m = Vec::new();
+        write_y4m(&bars, &trace, &options, &mut y4m).unwrap();
+        let header = b"YUV4MPEG2 W40 H20 F1000:40 Ip A1:1 C444 XCOLORRANGE=LIMITED\n";
+        assert!(y4m.starts_with(header));
+        let frame_len = b"FRAME\n".len() + 3 * 40 * 20;
+        assert_eq!(
+            y4m.len(),
+            header.len() + frame_count(trace.len(), 2) * frame_len
+        );
+        // Light theme background is bright, so luma is near the top of the range
+        let luma = y4m[header.len() + b"FRAME\n".len()];
+        assert!(luma > 200 && luma <= 235);
+    }
+
+    /// Sonified runs follow the video frame clock: a silent frame for the
+    /// input, then each stride of operations sounds within its own frame
+    #[test]
+    fn test_wav_export() {
+        let bars = create_bars(vec![3, 1, 2, 0]);
+        let trace = vec![
+            Operation::Compare(0, 1),
+            Operation::Swap(0, 1),
+            Operation::Read(2),
+            Operation::Overwrite(3, SortBar::new(3)),
+            Operation::Done,
+        ];
+        let options = ExportOptions {
+            format: ExportFormat::Wav,
+            stride: 2,
+            delay_ms: 100,
+            ..ExportOptions::default()
+        };
+        let frame_len = (SAMPLE_RATE / 10) as usize;
+
+        let samples = wav::synthesize(&bars, &trace, &options);
+        assert_eq!(samples.len(), frame_count(trace.len(), 2) * frame_len);
+        assert!(samples[..frame_len].iter().all(|&s| s == 0));
+        assert!(samples[frame_len..2 * frame_len].iter().any(|&s| s != 0));
+        // Nothing audible in the last chunk, and earlier tones have faded
+        let tail = &samples[samples.len() - frame_len / 4..];
+        assert!(tail.iter().all(|s| s.unsigned_abs() < 200));
+
+        let mut out = Vec::new();
+        write_wav(&bars, &trace, &options, &mut out).unwrap();
+        assert_eq!(&out[..4], b"RIFF");
+        assert_eq!(&out[8..16], b"WAVEfmt ");
+        assert_eq!(&out[36..40], b"data");
+        assert_eq!(out.len(), 44 + 2 * samples.len());
+    }
+
+    /// Race lanes sort identical copies on a shared clock, and the algorithm
+    /// needing fewer operations crosses the line first
+    #[test]
+    fn test_race_finishing_order() {
+        use crate::gui::race::Race;
+
+        let registry = Registry::with_builtins();
+        let mut race = Race::new(&registry);
+        race.contestants = vec![
+            registry.get("Bubble Sort").unwrap(),
+            registry.get("Heap Sort").unwrap(),
+        ];
+        race.speed = PlaybackSpeed::OpsPerFrame(7);
+        let input = create_bars((0..32).rev().collect());
+        race.start(&input);
+        assert!(race.is_running());
+
+        for _ in 0..100_000 {
+            if !race.is_running() {
+                break;
+            }
+            race.advance(0.016);
+        }
+        assert!(!race.is_running());
+        assert_eq!(
+            race.finishers().collect::<Vec<_>>(),
+            ["Heap Sort", "Bubble Sort"]
+        );
+        for bars in race.lane_bars() {
+            assert!(is_sorted(&extract_values(bars)));
+        }
+    }
+
+    /// The history image has a row for the input and one per `every`
+    /// operations, each showing the array as it was at that point
+    #[test]
+    fn test_history_image() {
+        let input = create_bars(vec![2, 0, 1]);
+        let trace = vec![
+            Operation::Compare(0, 1),
+            Operation::Swap(0, 1),
+            Operation::Overwrite(1, SortBar::new(1)),
+            Operation::Overwrite(2, SortBar::new(2)),
+            Operation::Done,
+        ];
+        let image = history_image(&input, &trace, 2);
+        assert_eq!(image.dimensions(), (3, 4));
+        // Row 1 is after the swap: [0, 2, 1]; the last row is sorted
+        assert_eq!(image.get_pixel(0, 1), image.get_pixel(0, 3));
+        assert_eq!(image.get_pixel(1, 1), image.get_pixel(0, 0));
+        assert_eq!(image.get_pixel(1, 3), image.get_pixel(2, 0));
+        assert_eq!(image.get_pixel(2, 3), image.get_pixel(0, 0));
+        // Values keep distinct colours
+        assert_ne!(image.get_pixel(0, 3), image.get_pixel(2, 3));
+
+        assert_eq!(ops_per_row(1_000, 512), 2);
+        assert_eq!(ops_per_row(0, 512), 1);
+    }
+
+    /// Markers don't count towards a stride, as in playback, so they never
+    /// make a frame, a history row or a stretch of audio of their own
+    #[test]
+    fn test_exports_skip_markers() {
+        let input = create_bars(vec![2, 0, 1]);
+        let style = FrameStyle {
+            width: 30,
+            height: 10,
+            theme: Theme::Dark,
+            ..FrameStyle::default()
+        };
+        let markers = vec![
+            Operation::Line(1),
+            Operation::EnterRange(0, 3),
+            Operation::Note(0, 3, "note"),
+            Operation::ExitRange(0, 3),
+        ];
+        assert_eq!(work_len(&markers), 0);
+        assert_eq!(frames(&input, &markers, 1, &style).count(), 1);
+        assert_eq!(frame_count(work_len(&markers), 1), 1);
+        assert_eq!(history_image(&input, &markers, 1).height(), 1);
+
+        let trace = vec![
+            Operation::Line(0),
+            Operation::Compare(0, 1),
+            Operation::Line(1),
+            Operation::Swap(0, 1),
+            Operation::Line(2),
+            Operation::Line(3),
+            Operation::Compare(1, 2),
+            Operation::Line(4),
+        ];
+        let chunks: Vec<usize> = work_chunks(&trace, 2).map(<[_]>::len).collect();
+        assert_eq!(chunks, [4, 3]);
+        assert_eq!(frames(&input, &trace, 2, &style).count(), 3);
+        assert_eq!(history_image(&input, &trace, 1).height(), 4);
+        let options = ExportOptions {
+            format: ExportFormat::Wav,
+            stride: 1,
+            delay_ms: 100,
+            ..ExportOptions::default()
+        };
+        let frame_len = (SAMPLE_RATE / 10) as usize;
+        assert_eq!(
+            wav::synthesize(&input, &trace, &options).len(),
+            4 * frame_len
+        );
+    }
+
+    /// Benchmark summaries use the sample standard deviation and Student's t
+    #[test]
+    fn test_bench_summary() {
+        let summary = Summary::of(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        assert_eq!(summary.mean, 5.0);
+        assert!((summary.std_dev - 2.138).abs() < 1e-3);
+        // t(7) = 2.365
+        assert!((summary.ci95 - 2.365 * summary.std_dev / 8f64.sqrt()).abs() < 1e-9);
+
+        assert_eq!(Summary::of(&[3.0]).ci95, 0.0);
+        assert_eq!(Summary::of(&[]), Summary::default());
+    }
+
+    /// The parallel sort agrees with the standard library for sizes that
+    /// don't split evenly, and splits even arrays the size of the screen's
+    #[test]
+    fn test_parallel_sort() {
+        let mut rng = StdRng::seed_from_u64(20);
+        for (n, threads) in [
+            (0, 4),
+            (100, 8),
+            (315, 8),
+            (50_001, 4),
+            (70_000, 3),
+            (40_000, 16),
+        ] {
+            let mut values: Vec<usize> = (0..n).map(|_| rng.gen_range(0..1_000)).collect();
+            let mut expected = values.clone();
+            expected.sort();
+            let used = parallel_sort(&mut values, threads);
+            assert_eq!(values, expected, "n = {}, threads = {}", n, threads);
+            assert!(used >= 1 && used <= threads);
+            if n < 128 {
+                assert_eq!(used, 1);
+            }
+            if n == 315 {
+                assert_eq!(used, 4);
+            }
+        }
+    }
+
+    /// Max speed hands its result over as overwrites that turn the input
+    /// into the sorted array
+    #[test]
+    fn test_max_speed_sort_delivers_result() {
+        let mut rng = StdRng::seed_from_u64(21);
+        let input = create_bars((0..30_000).map(|_| rng.gen_range(0..30_000)).collect());
+        let sink = Sink::recording();
+        let report = max_speed_sort(&input, 4, &sink).unwrap();
+        assert_eq!(report.threads, 4);
+
+        let trace = sink.into_trace();
+        assert!(trace
+            .iter()
+            .all(|op| matches!(op, Operation::Overwrite(..))));
+        let mut bars = input.clone();
+        for op in &trace {
+            apply(op, &mut bars, Color32::WHITE);
+        }
+        assert!(is_sorted(&extract_values(&bars)));
+        assert_eq!(
+            count_duplicates(&extract_values(&bars)),
+            count_duplicates(&extract_values(&input))
+        );
+    }
 }
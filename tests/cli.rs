@@ -0,0 +1,43 @@
+//! Tests that run the `sorthos-cli` binary itself.
+
+use std::process::Command;
+
+/// Quick Sort on sorted input recurses once per element. Capping the main
+/// thread's stack at 1 MB makes 5000 elements enough to overflow it, so this
+/// checks the sort runs on a thread of its own.
+#[cfg(unix)]
+#[test]
+fn test_cli_sorts_deep_recursion() {
+    let output = Command::new("sh")
+        .args(["-c", "ulimit -s 1024 && exec \"$0\" \"$@\""])
+        .arg(env!("CARGO_BIN_EXE_sorthos-cli"))
+        .args(["quick", "-n", "5000", "-d", "sorted", "--sink", "none"])
+        .output()
+        .expect("failed to run sorthos-cli");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}{}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("sorted:       true"));
+}
+
+/// Options that take one value reject a list instead of using its first item
+#[test]
+fn test_cli_rejects_lists_for_single_values() {
+    for args in [
+        ["bench", "--trials", "5,6"],
+        ["quick", "--stride", "3,4"],
+        ["quick", "--size", "10,20"],
+        ["quick", "--seed", "1,2"],
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_sorthos-cli"))
+            .args(args)
+            .output()
+            .expect("failed to run sorthos-cli");
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("invalid"));
+    }
+}